@@ -0,0 +1,334 @@
+// DDS 纹理容器，支持传统的 FourCC 文件头和 DX10 扩展文件头
+// https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dx-graphics-dds-pguide
+
+use pipeline::{Texture, TextureFormat, TextureViewDimension};
+
+use crate::texture::{empty_texture, read_bytes, read_u32, TextureLoadError};
+
+pub(crate) const DDS_MAGIC: [u8; 4] = *b"DDS ";
+
+// DDS_HEADER 中的标志位
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+// DDS_HEADER_DXT10 中的标志位和资源维度
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const DDS_DIMENSION_TEXTURE1D: u32 = 2;
+const DDS_DIMENSION_TEXTURE3D: u32 = 4;
+
+// magic 和 DDS_HEADER 的大小，之后是 DX10 文件头或者纹理数据
+const HEADER_SIZE: usize = 128;
+const DX10_HEADER_SIZE: usize = 20;
+
+pub fn load_dds(path: &str) -> Result<Texture, TextureLoadError> {
+    decode_dds(&std::fs::read(path)?)
+}
+
+pub fn decode_dds(bytes: &[u8]) -> Result<Texture, TextureLoadError> {
+    if !bytes.starts_with(&DDS_MAGIC) {
+        return Err(TextureLoadError::InvalidIdentifier);
+    }
+    let flags = read_u32(bytes, 8)?;
+    let height = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 16)?;
+    let depth = read_u32(bytes, 24)?;
+    let mip_level_count = match read_u32(bytes, 28)? {
+        count if flags & DDSD_MIPMAPCOUNT != 0 && count > 0 => count,
+        _ => 1,
+    };
+    let pixel_flags = read_u32(bytes, 80)?;
+    let four_cc = read_bytes(bytes, 84, 4)?;
+    let caps2 = read_u32(bytes, 112)?;
+
+    let mut data_offset = HEADER_SIZE;
+    let (format, dimension, depth_or_array_layers) =
+        if pixel_flags & DDPF_FOURCC != 0 && four_cc == b"DX10" {
+            data_offset += DX10_HEADER_SIZE;
+            let dxgi_format = read_u32(bytes, HEADER_SIZE)?;
+            let resource_dimension = read_u32(bytes, HEADER_SIZE + 4)?;
+            let misc_flag = read_u32(bytes, HEADER_SIZE + 8)?;
+            let array_size = read_u32(bytes, HEADER_SIZE + 12)?.max(1);
+            let format = dxgi_texture_format(dxgi_format).ok_or_else(|| {
+                TextureLoadError::UnsupportedFormat(format!("DXGI_FORMAT {dxgi_format}"))
+            })?;
+            let (dimension, layers) = match resource_dimension {
+                DDS_DIMENSION_TEXTURE1D if array_size == 1 => (TextureViewDimension::D1, 1),
+                DDS_DIMENSION_TEXTURE3D if array_size == 1 => (TextureViewDimension::D3, depth),
+                DDS_DIMENSION_TEXTURE1D | DDS_DIMENSION_TEXTURE3D => {
+                    return Err(TextureLoadError::UnsupportedDimension)
+                }
+                _ if misc_flag & DDS_RESOURCE_MISC_TEXTURECUBE != 0 => match array_size {
+                    1 => (TextureViewDimension::Cube, 6),
+                    _ => (
+                        TextureViewDimension::CubeArray,
+                        array_size
+                            .checked_mul(6)
+                            .ok_or(TextureLoadError::InvalidTexture)?,
+                    ),
+                },
+                _ if array_size > 1 => (TextureViewDimension::D2Array, array_size),
+                _ => (TextureViewDimension::D2, 1),
+            };
+            (format, dimension, layers)
+        } else {
+            let format = legacy_texture_format(bytes, pixel_flags, four_cc)?;
+            // 只支持六个面都存在的立方体贴图
+            let (dimension, layers) = if caps2 & DDSCAPS2_CUBEMAP != 0 {
+                (TextureViewDimension::Cube, 6)
+            } else if caps2 & DDSCAPS2_VOLUME != 0 {
+                (TextureViewDimension::D3, depth)
+            } else {
+                (TextureViewDimension::D2, 1)
+            };
+            (format, dimension, layers)
+        };
+
+    // 数据按照数组层依次存放，每一层中依次存放每个 mip 层级，和 Texture 的布局一致
+    let mut texture = empty_texture(
+        width,
+        height,
+        depth_or_array_layers,
+        dimension,
+        format,
+        mip_level_count,
+        bytes.len().saturating_sub(data_offset),
+    )?;
+    let size = texture.data.len();
+    texture
+        .data
+        .copy_from_slice(read_bytes(bytes, data_offset, size)?);
+    // 没有 alpha 通道的 32 位格式（X8R8G8B8 等）alpha 为不透明
+    if pixel_flags & DDPF_RGB != 0 && pixel_flags & DDPF_ALPHAPIXELS == 0 && format.size() == 4 {
+        texture
+            .data
+            .iter_mut()
+            .skip(3)
+            .step_by(4)
+            .for_each(|a| *a = 255);
+    }
+    Ok(texture)
+}
+
+// 没有 DX10 文件头时由 FourCC 或者各个通道的掩码确定格式
+fn legacy_texture_format(
+    bytes: &[u8],
+    pixel_flags: u32,
+    four_cc: &[u8],
+) -> Result<TextureFormat, TextureLoadError> {
+    let bit_count = read_u32(bytes, 88)?;
+    let red_mask = read_u32(bytes, 92)?;
+    let format = if pixel_flags & DDPF_FOURCC != 0 {
+        match four_cc {
+            b"DXT1" => Some(TextureFormat::Bc1RgbaUnorm),
+            b"DXT2" | b"DXT3" => Some(TextureFormat::Bc2RgbaUnorm),
+            b"DXT4" | b"DXT5" => Some(TextureFormat::Bc3RgbaUnorm),
+            b"ATI1" | b"BC4U" => Some(TextureFormat::Bc4RUnorm),
+            b"ATI2" | b"BC5U" => Some(TextureFormat::Bc5RgUnorm),
+            // D3DFORMAT 的值直接作为 FourCC
+            _ => match u32::from_le_bytes(four_cc.try_into().unwrap()) {
+                36 => Some(TextureFormat::Rgba16Unorm),
+                113 => Some(TextureFormat::Rgba16Float),
+                114 => Some(TextureFormat::R32Float),
+                116 => Some(TextureFormat::Rgba32Float),
+                _ => None,
+            },
+        }
+    } else if pixel_flags & DDPF_RGB != 0 && bit_count == 32 {
+        match red_mask {
+            0x000000FF => Some(TextureFormat::Rgba8Unorm),
+            0x00FF0000 => Some(TextureFormat::Bgra8Unorm),
+            _ => None,
+        }
+    } else if pixel_flags & DDPF_LUMINANCE != 0 && bit_count == 8 {
+        Some(TextureFormat::R8Unorm)
+    } else {
+        None
+    };
+    format.ok_or_else(|| {
+        TextureLoadError::UnsupportedFormat(format!(
+            "FourCC {:?}, {bit_count} bit RGB mask {red_mask:#x}",
+            String::from_utf8_lossy(four_cc)
+        ))
+    })
+}
+
+// DXGI_FORMAT 对应的纹理格式
+fn dxgi_texture_format(dxgi_format: u32) -> Option<TextureFormat> {
+    Some(match dxgi_format {
+        2 => TextureFormat::Rgba32Float,
+        10 => TextureFormat::Rgba16Float,
+        11 => TextureFormat::Rgba16Unorm,
+        28 => TextureFormat::Rgba8Unorm,
+        29 => TextureFormat::Rgba8UnormSrgb,
+        35 => TextureFormat::Rg16Unorm,
+        41 => TextureFormat::R32Float,
+        42 => TextureFormat::R32Uint,
+        49 => TextureFormat::Rg8Unorm,
+        56 => TextureFormat::R16Unorm,
+        61 => TextureFormat::R8Unorm,
+        71 => TextureFormat::Bc1RgbaUnorm,
+        72 => TextureFormat::Bc1RgbaUnormSrgb,
+        74 => TextureFormat::Bc2RgbaUnorm,
+        75 => TextureFormat::Bc2RgbaUnormSrgb,
+        77 => TextureFormat::Bc3RgbaUnorm,
+        78 => TextureFormat::Bc3RgbaUnormSrgb,
+        80 => TextureFormat::Bc4RUnorm,
+        83 => TextureFormat::Bc5RgUnorm,
+        87 => TextureFormat::Bgra8Unorm,
+        91 => TextureFormat::Bgra8UnormSrgb,
+        98 => TextureFormat::Bc7RgbaUnorm,
+        99 => TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // DDS_HEADER，four_cc 为 DX10 时需要再追加 DX10 文件头
+    fn header(
+        width: u32,
+        height: u32,
+        mip_level_count: u32,
+        four_cc: &[u8; 4],
+        caps2: u32,
+    ) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&DDS_MAGIC);
+        let mut write = |offset: usize, value: u32| {
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes())
+        };
+        write(4, 124);
+        write(8, DDSD_MIPMAPCOUNT);
+        write(12, height);
+        write(16, width);
+        write(28, mip_level_count);
+        write(76, 32);
+        write(80, DDPF_FOURCC);
+        write(112, caps2);
+        bytes[84..88].copy_from_slice(four_cc);
+        bytes
+    }
+
+    fn dx10_header(dxgi_format: u32, misc_flag: u32, array_size: u32) -> Vec<u8> {
+        [dxgi_format, 3, misc_flag, array_size, 0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = header(4, 4, 1, b"DXT1", 0);
+        assert!(matches!(
+            decode_dds(&bytes[..100]),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+        // 缺少 DX10 文件头
+        let bytes = header(4, 4, 1, b"DX10", 0);
+        assert!(matches!(
+            decode_dds(&bytes),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+        // 数据比文件头描述的少一个字节
+        let mut bytes = header(4, 4, 1, b"DXT1", 0);
+        bytes.extend([0; 8]);
+        assert!(decode_dds(&bytes).is_ok());
+        bytes.pop();
+        assert!(matches!(
+            decode_dds(&bytes),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn legacy_cube_layout() {
+        // FourCC 为 D3DFMT_R32F，六个面依次存放
+        let mut bytes = header(1, 1, 1, &114u32.to_le_bytes(), DDSCAPS2_CUBEMAP);
+        bytes.extend((0..6).flat_map(|face| (face as f32).to_le_bytes()));
+        let texture = decode_dds(&bytes).unwrap();
+        assert_eq!(texture.format, TextureFormat::R32Float);
+        assert_eq!(texture.dimension, TextureViewDimension::Cube);
+        assert_eq!(texture.depth_or_array_layers, 6);
+        assert_eq!(texture.mip_level_data(4, 0), 4f32.to_le_bytes());
+    }
+
+    // 2x2 的 R8Unorm 纹理有两个 mip 层级，每个数组层依次存放两个层级，纹素为层号和层级
+    fn r8_layers(layers: u8) -> Vec<u8> {
+        (0..layers)
+            .flat_map(|layer| [layer * 16; 4].into_iter().chain([layer * 16 + 1]))
+            .collect()
+    }
+
+    #[test]
+    fn dx10_array_layout() {
+        let mut bytes = header(2, 2, 2, b"DX10", 0);
+        bytes.extend(dx10_header(61, 0, 3));
+        bytes.extend(r8_layers(3));
+        let texture = decode_dds(&bytes).unwrap();
+        assert_eq!(texture.dimension, TextureViewDimension::D2Array);
+        assert_eq!(texture.depth_or_array_layers, 3);
+        assert_eq!(texture.mip_level_data(2, 0), [32; 4]);
+        assert_eq!(texture.mip_level_data(2, 1), [33]);
+    }
+
+    #[test]
+    fn dx10_cube_layout() {
+        let mut bytes = header(2, 2, 2, b"DX10", 0);
+        bytes.extend(dx10_header(61, DDS_RESOURCE_MISC_TEXTURECUBE, 1));
+        bytes.extend(r8_layers(6));
+        let texture = decode_dds(&bytes).unwrap();
+        assert_eq!(texture.dimension, TextureViewDimension::Cube);
+        assert_eq!(texture.depth_or_array_layers, 6);
+        assert_eq!(texture.mip_level_data(5, 1), [81]);
+
+        // 数组大小为立方体贴图的个数
+        let mut bytes = header(2, 2, 2, b"DX10", 0);
+        bytes.extend(dx10_header(61, DDS_RESOURCE_MISC_TEXTURECUBE, 2));
+        bytes.extend(r8_layers(12));
+        let texture = decode_dds(&bytes).unwrap();
+        assert_eq!(texture.dimension, TextureViewDimension::CubeArray);
+        assert_eq!(texture.depth_or_array_layers, 12);
+        assert_eq!(texture.mip_level_data(11, 0), [176; 4]);
+    }
+
+    #[test]
+    fn oversized_mip_level_count() {
+        let mut bytes = header(4, 4, 40, b"DXT1", 0);
+        bytes.extend([0; 8 * 40]);
+        assert!(matches!(
+            decode_dds(&bytes),
+            Err(TextureLoadError::InvalidTexture)
+        ));
+    }
+
+    #[test]
+    fn oversized_dimensions() {
+        // 按照文件头需要上百 GB 的数据，不能先分配再检查
+        let mut bytes = header(1 << 20, 1 << 20, 1, b"DXT1", 0);
+        bytes.extend([0; 64]);
+        assert!(matches!(
+            decode_dds(&bytes),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+        let mut bytes = header(u32::MAX, u32::MAX, 1, b"DX10", 0);
+        bytes.extend(dx10_header(2, 0, u32::MAX));
+        // 字节数溢出 usize
+        assert!(matches!(
+            decode_dds(&bytes),
+            Err(TextureLoadError::InvalidTexture)
+        ));
+        // 立方体贴图数组的层数溢出
+        let mut bytes = header(4, 4, 1, b"DX10", 0);
+        bytes.extend(dx10_header(71, DDS_RESOURCE_MISC_TEXTURECUBE, u32::MAX / 3));
+        assert!(matches!(
+            decode_dds(&bytes),
+            Err(TextureLoadError::InvalidTexture)
+        ));
+    }
+}
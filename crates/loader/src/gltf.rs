@@ -1,5 +1,6 @@
 use gltf::image;
-use pipeline::{Texture, TextureFormat};
+use math::Vec4;
+use pipeline::{srgb_to_linear, MipmapFilter, Texture, TextureFormat, TextureViewDimension};
 use render::{Color, Mesh, StandardMaterial};
 
 pub fn load_gltf(path: &str) -> (Vec<Mesh>, Vec<StandardMaterial>) {
@@ -57,6 +58,27 @@ pub fn load_gltf(path: &str) -> (Vec<Mesh>, Vec<StandardMaterial>) {
                             reader.read_tangents().unwrap().collect::<Vec<[f32; 4]>>(),
                         );
                     }
+                    // 骨骼索引保持 u16 的量化格式直接写入顶点缓冲
+                    gltf::Semantic::Joints(0) => {
+                        mesh.insert_attribute(
+                            Mesh::ATTRIBUTE_JOINT_INDEX,
+                            reader
+                                .read_joints(0)
+                                .unwrap()
+                                .into_u16()
+                                .collect::<Vec<[u16; 4]>>(),
+                        );
+                    }
+                    gltf::Semantic::Weights(0) => {
+                        mesh.insert_attribute(
+                            Mesh::ATTRIBUTE_JOINT_WEIGHT,
+                            reader
+                                .read_weights(0)
+                                .unwrap()
+                                .into_f32()
+                                .collect::<Vec<[f32; 4]>>(),
+                        );
+                    }
                     _ => {
                         println!("other semantic:{:?}", semantic)
                     }
@@ -84,67 +106,290 @@ pub fn load_gltf(path: &str) -> (Vec<Mesh>, Vec<StandardMaterial>) {
         standard_material.base_color_texture = pbr.base_color_texture().map(|info| {
             let source = info.texture().source();
             let image = images.get(source.index()).unwrap();
-            Texture {
-                width: image.width,
-                height: image.height,
-                format: match image.format {
-                    image::Format::R8 => TextureFormat::R8Unorm,
-                    image::Format::R8G8B8 => TextureFormat::Rgb8Unorm,
-                    image::Format::R8G8B8A8 => TextureFormat::Rgba8Unorm,
-                    _ => TextureFormat::Rgba8Unorm,
-                },
-                data: image.pixels.clone(),
-            }
+            load_texture(image, true)
         });
 
         standard_material.metallic_roughness_texture = pbr.metallic_roughness_texture().map(|v| {
             let source = v.texture().source();
             let image = images.get(source.index()).unwrap();
-            Texture {
-                width: image.width,
-                height: image.height,
-                format: match image.format {
-                    image::Format::R8 => TextureFormat::R8Unorm,
-                    image::Format::R8G8B8 => TextureFormat::Rgb8Unorm,
-                    image::Format::R8G8B8A8 => TextureFormat::Rgba8Unorm,
-                    _ => TextureFormat::Rgba8Unorm,
-                },
-                data: image.pixels.clone(),
-            }
+            load_texture(image, false)
         });
 
         standard_material.normal_map_texture = material.normal_texture().map(|v| {
+            standard_material.normal_map_scale = v.scale();
             let source = v.texture().source();
             let image = images.get(source.index()).unwrap();
-            Texture {
-                width: image.width,
-                height: image.height,
-                format: match image.format {
-                    image::Format::R8 => TextureFormat::R8Unorm,
-                    image::Format::R8G8B8 => TextureFormat::Rgb8Unorm,
-                    image::Format::R8G8B8A8 => TextureFormat::Rgba8Unorm,
-                    _ => TextureFormat::Rgba8Unorm,
-                },
-                data: image.pixels.clone(),
-            }
+            load_texture(image, false)
         });
 
         standard_material.emissive_texture = material.emissive_texture().map(|v| {
             let source = v.texture().source();
             let image = images.get(source.index()).unwrap();
-            Texture {
-                width: image.width,
-                height: image.height,
-                format: match image.format {
-                    image::Format::R8 => TextureFormat::R8Unorm,
-                    image::Format::R8G8B8 => TextureFormat::Rgb8Unorm,
-                    image::Format::R8G8B8A8 => TextureFormat::Rgba8Unorm,
-                    _ => TextureFormat::Rgba8Unorm,
-                },
-                data: image.pixels.clone(),
-            }
+            load_texture(image, true)
         });
         materials.push(standard_material);
     }
     (meshs, materials)
 }
+
+// 加载纹理并生成完整的 mip 链，缩小时采样不会闪烁
+// 按照 glTF 标准，base color 和 emissive 纹理是 srgb 编码的，采样时需要转换到线性空间
+// 其他纹理存储的是线性数据，srgb 为 false
+fn load_texture(image: &image::Data, srgb: bool) -> Texture {
+    let (format, data) = convert_image(image, srgb);
+    let mut texture = Texture {
+        width: image.width,
+        height: image.height,
+        depth_or_array_layers: 1,
+        dimension: TextureViewDimension::D2,
+        format,
+        mip_level_count: 1,
+        data,
+    };
+    texture.generate_mipmaps(MipmapFilter::Box);
+    texture
+}
+
+// 把图片转换为可以直接采样的纹理格式，没有对应格式的图片扩展为 rgba
+// 双通道的图片由灰度 + alpha 的图片解码得到，作为 srgb 颜色时扩展为 (l, l, l, a)
+fn convert_image(image: &image::Data, srgb: bool) -> (TextureFormat, Vec<u8>) {
+    let pixels = &image.pixels;
+    match (image.format, srgb) {
+        (image::Format::R8, false) => (TextureFormat::R8Unorm, pixels.clone()),
+        (image::Format::R8G8, false) => (TextureFormat::Rg8Unorm, pixels.clone()),
+        (image::Format::R8G8B8, false) => (TextureFormat::Rgb8Unorm, pixels.clone()),
+        (image::Format::R8G8B8A8, false) => (TextureFormat::Rgba8Unorm, pixels.clone()),
+        (image::Format::R8G8B8A8, true) => (TextureFormat::Rgba8UnormSrgb, pixels.clone()),
+        // 没有单通道、双通道和三通道的 srgb 格式
+        (image::Format::R8, true) => (
+            TextureFormat::Rgba8UnormSrgb,
+            pixels.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+        ),
+        (image::Format::R8G8, true) => (
+            TextureFormat::Rgba8UnormSrgb,
+            pixels
+                .chunks_exact(2)
+                .flat_map(|la| [la[0], la[0], la[0], la[1]])
+                .collect(),
+        ),
+        (image::Format::R8G8B8, true) => (
+            TextureFormat::Rgba8UnormSrgb,
+            pixels
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+        ),
+        (image::Format::R16, false) => (TextureFormat::R16Unorm, pixels.clone()),
+        // 双通道的法线贴图保持 Rg16Unorm，材质按照格式在着色器里重建 z
+        (image::Format::R16G16, false) => (TextureFormat::Rg16Unorm, pixels.clone()),
+        (
+            image::Format::R16
+            | image::Format::R16G16
+            | image::Format::R16G16B16
+            | image::Format::R16G16B16A16,
+            _,
+        ) => convert_image_16(image, srgb),
+        // 浮点图片一般是 hdr 图片，本身就是线性的，不需要 srgb 解码
+        (image::Format::R32G32B32FLOAT, _) => (
+            TextureFormat::Rgba32Float,
+            pixels
+                .chunks_exact(12)
+                .flat_map(|rgb| [rgb, &1f32.to_ne_bytes()].concat())
+                .collect(),
+        ),
+        (image::Format::R32G32B32A32FLOAT, _) => (TextureFormat::Rgba32Float, pixels.clone()),
+    }
+}
+
+// 16 位的图片扩展为 Rgba16Unorm，双通道的图片只有 srgb 颜色会走到这里，扩展为 (l, l, l, a)
+// srgb 颜色在这里转换到线性空间后存储为 Rgba16Float
+fn convert_image_16(image: &image::Data, srgb: bool) -> (TextureFormat, Vec<u8>) {
+    // 图片按照本机字节序存储，pixels 不一定按照 u16 对齐
+    let values: Vec<u16> = image
+        .pixels
+        .chunks_exact(2)
+        .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+        .collect();
+    let rgba: Vec<[u16; 4]> = match image.format {
+        image::Format::R16 => values.iter().map(|&l| [l, l, l, u16::MAX]).collect(),
+        image::Format::R16G16 => values
+            .chunks_exact(2)
+            .map(|la| [la[0], la[0], la[0], la[1]])
+            .collect(),
+        image::Format::R16G16B16 => values
+            .chunks_exact(3)
+            .map(|rgb| [rgb[0], rgb[1], rgb[2], u16::MAX])
+            .collect(),
+        _ => values
+            .chunks_exact(4)
+            .map(|rgba| [rgba[0], rgba[1], rgba[2], rgba[3]])
+            .collect(),
+    };
+    if !srgb {
+        return (
+            TextureFormat::Rgba16Unorm,
+            bytemuck::cast_slice(&rgba).to_vec(),
+        );
+    }
+    let format = TextureFormat::Rgba16Float;
+    let mut data = vec![0; rgba.len() * format.size()];
+    for (texel, bytes) in rgba.iter().zip(data.chunks_exact_mut(format.size())) {
+        let [r, g, b, a] = texel.map(|v| v as f32 / u16::MAX as f32);
+        format.encode(
+            Vec4::new(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a),
+            bytes,
+        );
+    }
+    (format, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(format: image::Format, pixels: Vec<u8>) -> image::Data {
+        image::Data {
+            pixels,
+            format,
+            width: 1,
+            height: 1,
+        }
+    }
+
+    fn bytes16(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+    }
+
+    fn bytes32(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+    }
+
+    // 解码转换后的第一个纹素
+    fn texel(format: TextureFormat, data: &[u8]) -> [f32; 4] {
+        let (r, g, b, a) = format.decode(&data[..format.size()]).into();
+        [r, g, b, a]
+    }
+
+    fn assert_texel(actual: [f32; 4], expected: [f32; 4]) {
+        for (a, e) in actual.into_iter().zip(expected) {
+            assert!((a - e).abs() < 1e-3, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn convert_8_bit_linear() {
+        let cases = [
+            (image::Format::R8, vec![51], TextureFormat::R8Unorm),
+            (image::Format::R8G8, vec![51, 102], TextureFormat::Rg8Unorm),
+            (
+                image::Format::R8G8B8,
+                vec![51, 102, 153],
+                TextureFormat::Rgb8Unorm,
+            ),
+            (
+                image::Format::R8G8B8A8,
+                vec![51, 102, 153, 204],
+                TextureFormat::Rgba8Unorm,
+            ),
+        ];
+        for (format, pixels, expected) in cases {
+            let (converted, data) = convert_image(&image(format, pixels.clone()), false);
+            assert_eq!(converted, expected);
+            assert_eq!(data, pixels);
+        }
+    }
+
+    #[test]
+    fn convert_8_bit_srgb() {
+        let cases = [
+            (image::Format::R8, vec![51], [51, 51, 51, 255]),
+            (image::Format::R8G8, vec![51, 102], [51, 51, 51, 102]),
+            (
+                image::Format::R8G8B8,
+                vec![51, 102, 153],
+                [51, 102, 153, 255],
+            ),
+            (
+                image::Format::R8G8B8A8,
+                vec![51, 102, 153, 204],
+                [51, 102, 153, 204],
+            ),
+        ];
+        for (format, pixels, expected) in cases {
+            let (converted, data) = convert_image(&image(format, pixels), true);
+            assert_eq!(converted, TextureFormat::Rgba8UnormSrgb);
+            assert_eq!(data, expected);
+        }
+    }
+
+    #[test]
+    fn convert_16_bit_linear() {
+        let (format, data) = convert_image(&image(image::Format::R16, bytes16(&[0x8000])), false);
+        assert_eq!(format, TextureFormat::R16Unorm);
+        assert_eq!(data, bytes16(&[0x8000]));
+
+        // 双通道的法线贴图不能扩展出 b = 0，否则不会按照两通道重建 z
+        let pixels = bytes16(&[0, u16::MAX]);
+        let (format, data) = convert_image(&image(image::Format::R16G16, pixels.clone()), false);
+        assert_eq!(format, TextureFormat::Rg16Unorm);
+        assert_eq!(data, pixels);
+        assert_texel(texel(format, &data), [0., 1., 0., 1.]);
+
+        let (format, data) =
+            convert_image(&image(image::Format::R16G16B16, bytes16(&[1, 2, 3])), false);
+        assert_eq!(format, TextureFormat::Rgba16Unorm);
+        assert_eq!(data, bytes16(&[1, 2, 3, u16::MAX]));
+
+        let pixels = bytes16(&[1, 2, 3, 4]);
+        let (format, data) =
+            convert_image(&image(image::Format::R16G16B16A16, pixels.clone()), false);
+        assert_eq!(format, TextureFormat::Rgba16Unorm);
+        assert_eq!(data, pixels);
+    }
+
+    #[test]
+    fn convert_16_bit_srgb() {
+        let half = 0x8000;
+        let l = srgb_to_linear(half as f32 / u16::MAX as f32);
+        let cases = [
+            (image::Format::R16, vec![half], [l, l, l, 1.]),
+            (image::Format::R16G16, vec![half, 0], [l, l, l, 0.]),
+            (
+                image::Format::R16G16B16,
+                vec![half, 0, u16::MAX],
+                [l, 0., 1., 1.],
+            ),
+            (
+                image::Format::R16G16B16A16,
+                vec![half, 0, u16::MAX, half],
+                [l, 0., 1., 0.5],
+            ),
+        ];
+        for (format, values, expected) in cases {
+            let (converted, data) = convert_image(&image(format, bytes16(&values)), true);
+            assert_eq!(converted, TextureFormat::Rgba16Float);
+            assert_texel(texel(converted, &data), expected);
+        }
+    }
+
+    #[test]
+    fn convert_float() {
+        // 浮点图片不做 srgb 解码
+        for srgb in [false, true] {
+            let (format, data) = convert_image(
+                &image(image::Format::R32G32B32FLOAT, bytes32(&[0.5, 2., 4.])),
+                srgb,
+            );
+            assert_eq!(format, TextureFormat::Rgba32Float);
+            assert_eq!(data, bytes32(&[0.5, 2., 4., 1.]));
+
+            let pixels = bytes32(&[0.5, 2., 4., 0.25]);
+            let (format, data) = convert_image(
+                &image(image::Format::R32G32B32A32FLOAT, pixels.clone()),
+                srgb,
+            );
+            assert_eq!(format, TextureFormat::Rgba32Float);
+            assert_eq!(data, pixels);
+        }
+    }
+}
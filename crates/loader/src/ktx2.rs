@@ -0,0 +1,260 @@
+// KTX2 纹理容器
+// https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html
+
+use pipeline::{MipmapFilter, Texture, TextureFormat, TextureViewDimension};
+
+use crate::texture::{empty_texture, read_bytes, read_u32, read_u64, TextureLoadError};
+
+pub(crate) const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+// 文件头之后依次为 dfd、kvd、sgd 的索引，然后是每个 mip 层级的索引
+const LEVEL_INDEX_OFFSET: usize = 80;
+
+pub fn load_ktx2(path: &str) -> Result<Texture, TextureLoadError> {
+    decode_ktx2(&std::fs::read(path)?)
+}
+
+pub fn decode_ktx2(bytes: &[u8]) -> Result<Texture, TextureLoadError> {
+    if !bytes.starts_with(&KTX2_IDENTIFIER) {
+        return Err(TextureLoadError::InvalidIdentifier);
+    }
+    let vk_format = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?;
+    let depth = read_u32(bytes, 28)?;
+    let layer_count = read_u32(bytes, 32)?;
+    let face_count = read_u32(bytes, 36)?;
+    let level_count = read_u32(bytes, 40)?;
+    let supercompression = read_u32(bytes, 44)?;
+    if supercompression != 0 {
+        return Err(TextureLoadError::UnsupportedSupercompression(
+            supercompression,
+        ));
+    }
+    let format = texture_format(vk_format)
+        .ok_or_else(|| TextureLoadError::UnsupportedFormat(format!("vkFormat {vk_format}")))?;
+
+    // 高度、深度和层数为 0 分别表示一维纹理、非三维纹理和非数组纹理
+    let layers = layer_count
+        .max(1)
+        .checked_mul(face_count)
+        .ok_or(TextureLoadError::InvalidTexture)?;
+    let dimension = match (depth, layer_count, face_count) {
+        (0, 0, 6) => TextureViewDimension::Cube,
+        (0, _, 6) => TextureViewDimension::CubeArray,
+        (0, 0, 1) if height == 0 => TextureViewDimension::D1,
+        (0, 0, 1) => TextureViewDimension::D2,
+        (0, _, 1) => TextureViewDimension::D2Array,
+        (_, 0, 1) => TextureViewDimension::D3,
+        _ => return Err(TextureLoadError::UnsupportedDimension),
+    };
+    let depth_or_array_layers = match dimension {
+        TextureViewDimension::D3 => depth,
+        _ => layers,
+    };
+    // level_count 为 0 时文件中只有第 0 层，需要在加载时生成其他层级
+    let mut texture = empty_texture(
+        width,
+        height.max(1),
+        depth_or_array_layers,
+        dimension,
+        format,
+        level_count.max(1),
+        bytes.len(),
+    )?;
+
+    // 每个 mip 层级中依次存放每个数组层的每个面，这里的数据是每个数组层依次存放所有层级
+    for level in 0..texture.mip_level_count {
+        let index = LEVEL_INDEX_OFFSET + level as usize * 24;
+        let offset = read_u64(bytes, index)? as usize;
+        let length = read_u64(bytes, index + 8)? as usize;
+        let level_data = read_bytes(bytes, offset, length)?;
+        for layer in 0..texture.array_layer_count() {
+            let dst = texture.subresource_offset(layer, level);
+            let size = texture.mip_level_data(layer, level).len();
+            let src = read_bytes(level_data, layer as usize * size, size)?;
+            texture.data[dst..dst + size].copy_from_slice(src);
+        }
+    }
+    if level_count == 0 && !format.is_compressed() && !format.is_integer() {
+        texture.generate_mipmaps(MipmapFilter::Box);
+    }
+    Ok(texture)
+}
+
+// VkFormat 对应的纹理格式
+fn texture_format(vk_format: u32) -> Option<TextureFormat> {
+    Some(match vk_format {
+        9 => TextureFormat::R8Unorm,
+        16 => TextureFormat::Rg8Unorm,
+        23 => TextureFormat::Rgb8Unorm,
+        37 => TextureFormat::Rgba8Unorm,
+        43 => TextureFormat::Rgba8UnormSrgb,
+        44 => TextureFormat::Bgra8Unorm,
+        50 => TextureFormat::Bgra8UnormSrgb,
+        70 => TextureFormat::R16Unorm,
+        77 => TextureFormat::Rg16Unorm,
+        91 => TextureFormat::Rgba16Unorm,
+        97 => TextureFormat::Rgba16Float,
+        98 => TextureFormat::R32Uint,
+        100 => TextureFormat::R32Float,
+        109 => TextureFormat::Rgba32Float,
+        133 => TextureFormat::Bc1RgbaUnorm,
+        134 => TextureFormat::Bc1RgbaUnormSrgb,
+        135 => TextureFormat::Bc2RgbaUnorm,
+        136 => TextureFormat::Bc2RgbaUnormSrgb,
+        137 => TextureFormat::Bc3RgbaUnorm,
+        138 => TextureFormat::Bc3RgbaUnormSrgb,
+        139 => TextureFormat::Bc4RUnorm,
+        141 => TextureFormat::Bc5RgUnorm,
+        145 => TextureFormat::Bc7RgbaUnorm,
+        146 => TextureFormat::Bc7RgbaUnormSrgb,
+        147 => TextureFormat::Etc2Rgb8Unorm,
+        148 => TextureFormat::Etc2Rgb8UnormSrgb,
+        149 => TextureFormat::Etc2Rgb8A1Unorm,
+        150 => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        151 => TextureFormat::Etc2Rgba8Unorm,
+        152 => TextureFormat::Etc2Rgba8UnormSrgb,
+        153 => TextureFormat::EacR11Unorm,
+        155 => TextureFormat::EacRg11Unorm,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 只有文件头和层级索引，没有 dfd 和 kvd
+    fn header(
+        vk_format: u32,
+        size: [u32; 3],
+        layer_count: u32,
+        face_count: u32,
+        level_count: u32,
+    ) -> Vec<u8> {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        for value in [
+            vk_format,
+            1,
+            size[0],
+            size[1],
+            size[2],
+            layer_count,
+            face_count,
+            level_count,
+            0,
+        ] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.resize(LEVEL_INDEX_OFFSET + level_count.max(1) as usize * 24, 0);
+        bytes
+    }
+
+    // 在文件末尾追加一个 mip 层级的数据，并写入层级索引
+    fn push_level(bytes: &mut Vec<u8>, level: usize, data: &[u8]) {
+        let index = LEVEL_INDEX_OFFSET + level * 24;
+        let offset = bytes.len() as u64;
+        bytes[index..index + 8].copy_from_slice(&offset.to_le_bytes());
+        bytes[index + 8..index + 16].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes.extend(data);
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = header(9, [2, 2, 0], 0, 1, 1);
+        assert!(matches!(
+            decode_ktx2(&bytes[..30]),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+        // 层级索引超出文件
+        assert!(matches!(
+            decode_ktx2(&bytes[..LEVEL_INDEX_OFFSET + 8]),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+        // 层级数据比索引中的长度短
+        let mut bytes = header(9, [2, 2, 0], 0, 1, 1);
+        push_level(&mut bytes, 0, &[1, 2, 3, 4]);
+        assert!(decode_ktx2(&bytes).is_ok());
+        bytes.pop();
+        assert!(matches!(
+            decode_ktx2(&bytes),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn array_layout() {
+        // 文件中每个层级依次存放两个数组层，层级 1 放在层级 0 之前，按照层级索引读取
+        let mut bytes = header(9, [2, 2, 0], 2, 1, 2);
+        push_level(&mut bytes, 1, &[10, 11]);
+        push_level(&mut bytes, 0, &[0, 0, 0, 0, 1, 1, 1, 1]);
+        let texture = decode_ktx2(&bytes).unwrap();
+        assert_eq!(texture.dimension, TextureViewDimension::D2Array);
+        assert_eq!(texture.depth_or_array_layers, 2);
+        // Texture 中每个数组层依次存放所有层级
+        assert_eq!(texture.data, [0, 0, 0, 0, 10, 1, 1, 1, 1, 11]);
+        assert_eq!(texture.mip_level_data(1, 0), [1; 4]);
+        assert_eq!(texture.mip_level_data(1, 1), [11]);
+    }
+
+    #[test]
+    fn cube_layout() {
+        let faces: Vec<u8> = (0..6).collect();
+        let mut bytes = header(9, [1, 1, 0], 0, 6, 1);
+        push_level(&mut bytes, 0, &faces);
+        let texture = decode_ktx2(&bytes).unwrap();
+        assert_eq!(texture.dimension, TextureViewDimension::Cube);
+        assert_eq!(texture.depth_or_array_layers, 6);
+        assert_eq!(texture.data, faces);
+
+        // 立方体贴图数组中每个数组层依次存放六个面
+        let faces: Vec<u8> = (0..12).collect();
+        let mut bytes = header(9, [1, 1, 0], 2, 6, 1);
+        push_level(&mut bytes, 0, &faces);
+        let texture = decode_ktx2(&bytes).unwrap();
+        assert_eq!(texture.dimension, TextureViewDimension::CubeArray);
+        assert_eq!(texture.depth_or_array_layers, 12);
+        assert_eq!(texture.mip_level_data(7, 0), [7]);
+    }
+
+    #[test]
+    fn compressed_mip_chain() {
+        // 8x4 的 BC1 纹理，层级 0 为两个块，层级 1 和 2 不足一个块也占用一个块
+        let mut bytes = header(133, [8, 4, 0], 0, 1, 4);
+        push_level(&mut bytes, 0, &[0; 16]);
+        push_level(&mut bytes, 1, &[1; 8]);
+        push_level(&mut bytes, 2, &[2; 8]);
+        push_level(&mut bytes, 3, &[3; 8]);
+        let texture = decode_ktx2(&bytes).unwrap();
+        assert_eq!(texture.format, TextureFormat::Bc1RgbaUnorm);
+        assert_eq!(texture.data.len(), 40);
+        assert_eq!(texture.mip_level_data(0, 3), [3; 8]);
+    }
+
+    #[test]
+    fn oversized_mip_level_count() {
+        let bytes = header(133, [4, 4, 0], 0, 1, 40);
+        assert!(matches!(
+            decode_ktx2(&bytes),
+            Err(TextureLoadError::InvalidTexture)
+        ));
+    }
+
+    #[test]
+    fn oversized_dimensions() {
+        let bytes = header(109, [1 << 20, 1 << 20, 0], 0, 1, 1);
+        assert!(matches!(
+            decode_ktx2(&bytes),
+            Err(TextureLoadError::UnexpectedEof)
+        ));
+        // 数组层数和面数的乘积溢出
+        let bytes = header(37, [4, 4, 0], u32::MAX, 6, 1);
+        assert!(matches!(
+            decode_ktx2(&bytes),
+            Err(TextureLoadError::InvalidTexture)
+        ));
+    }
+}
@@ -1,2 +1,8 @@
+pub mod dds;
 pub mod gltf;
+pub mod ktx2;
+pub mod texture;
+pub use crate::dds::*;
 pub use crate::gltf::*;
+pub use crate::ktx2::*;
+pub use crate::texture::*;
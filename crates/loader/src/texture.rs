@@ -0,0 +1,116 @@
+use std::{fmt, io};
+
+use pipeline::{Texture, TextureFormat, TextureViewDimension};
+
+use crate::dds::{decode_dds, DDS_MAGIC};
+use crate::ktx2::{decode_ktx2, KTX2_IDENTIFIER};
+
+#[derive(Debug)]
+pub enum TextureLoadError {
+    Io(io::Error),
+    // 文件头不是 KTX2 或者 DDS
+    InvalidIdentifier,
+    // 不支持的纹理格式，包含容器中格式的描述
+    UnsupportedFormat(String),
+    // KTX2 的超压缩（Basis、zstd 等）
+    UnsupportedSupercompression(u32),
+    // 不支持的纹理维度，比如三维纹理数组
+    UnsupportedDimension,
+    // 数据比文件头描述的纹理少
+    UnexpectedEof,
+    // 文件头描述的大小、层级数量等不是有效的纹理
+    InvalidTexture,
+}
+impl fmt::Display for TextureLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureLoadError::Io(error) => write!(f, "failed to read texture file: {error}"),
+            TextureLoadError::InvalidIdentifier => write!(f, "not a KTX2 or DDS file"),
+            TextureLoadError::UnsupportedFormat(format) => {
+                write!(f, "unsupported texture format {format}")
+            }
+            TextureLoadError::UnsupportedSupercompression(scheme) => {
+                write!(f, "unsupported supercompression scheme {scheme}")
+            }
+            TextureLoadError::UnsupportedDimension => write!(f, "unsupported texture dimension"),
+            TextureLoadError::UnexpectedEof => write!(f, "texture data is truncated"),
+            TextureLoadError::InvalidTexture => write!(f, "invalid texture description"),
+        }
+    }
+}
+impl std::error::Error for TextureLoadError {}
+impl From<io::Error> for TextureLoadError {
+    fn from(error: io::Error) -> Self {
+        TextureLoadError::Io(error)
+    }
+}
+
+// 按照文件头加载 KTX2 或者 DDS 纹理，包含文件中所有的数组层和 mip 层级
+pub fn load_texture(path: &str) -> Result<Texture, TextureLoadError> {
+    decode_texture(&std::fs::read(path)?)
+}
+
+pub fn decode_texture(bytes: &[u8]) -> Result<Texture, TextureLoadError> {
+    if bytes.starts_with(&KTX2_IDENTIFIER) {
+        decode_ktx2(bytes)
+    } else if bytes.starts_with(&DDS_MAGIC) {
+        decode_dds(bytes)
+    } else {
+        Err(TextureLoadError::InvalidIdentifier)
+    }
+}
+
+// 没有数据的纹理，检查描述是否有效之后由容器填充 data
+// available 为文件中剩余的字节数，纹理数据比它还大时文件一定被截断了，不需要分配
+pub(crate) fn empty_texture(
+    width: u32,
+    height: u32,
+    depth_or_array_layers: u32,
+    dimension: TextureViewDimension,
+    format: TextureFormat,
+    mip_level_count: u32,
+    available: usize,
+) -> Result<Texture, TextureLoadError> {
+    let mut texture = Texture {
+        width,
+        height,
+        depth_or_array_layers,
+        dimension,
+        format,
+        mip_level_count,
+        data: Vec::new(),
+    };
+    // 文件头中的 mip 层级数量和大小可能任意大，先检查再计算大小和分配
+    let size = texture
+        .checked_size_in_bytes()
+        .ok_or(TextureLoadError::InvalidTexture)?;
+    if size > available {
+        return Err(TextureLoadError::UnexpectedEof);
+    }
+    texture.data = vec![0; size];
+    if !texture.is_valid() {
+        return Err(TextureLoadError::InvalidTexture);
+    }
+    Ok(texture)
+}
+
+pub(crate) fn read_bytes(
+    bytes: &[u8],
+    offset: usize,
+    len: usize,
+) -> Result<&[u8], TextureLoadError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or(TextureLoadError::UnexpectedEof)
+}
+
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, TextureLoadError> {
+    let bytes = read_bytes(bytes, offset, 4)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+pub(crate) fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, TextureLoadError> {
+    let bytes = read_bytes(bytes, offset, 8)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
@@ -438,6 +438,181 @@ impl From<[f32; 4]> for Vec4 {
     }
 }
 
+// 整数向量，对应着色器中的 vec2<u32>/vec2<i32> 等类型
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UVec2 {
+    pub x: u32,
+    pub y: u32,
+}
+impl UVec2 {
+    pub const ZERO: Self = Self::splat(0);
+    pub const ONE: Self = Self::splat(1);
+
+    pub const fn new(x: u32, y: u32) -> Self {
+        Self { x, y }
+    }
+    pub const fn splat(v: u32) -> Self {
+        Self::new(v, v)
+    }
+}
+impl From<[u32; 2]> for UVec2 {
+    fn from(v: [u32; 2]) -> Self {
+        UVec2::new(v[0], v[1])
+    }
+}
+impl From<UVec2> for [u32; 2] {
+    fn from(v: UVec2) -> Self {
+        [v.x, v.y]
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UVec3 {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+impl UVec3 {
+    pub const ZERO: Self = Self::splat(0);
+    pub const ONE: Self = Self::splat(1);
+
+    pub const fn new(x: u32, y: u32, z: u32) -> Self {
+        Self { x, y, z }
+    }
+    pub const fn splat(v: u32) -> Self {
+        Self::new(v, v, v)
+    }
+}
+impl From<[u32; 3]> for UVec3 {
+    fn from(v: [u32; 3]) -> Self {
+        UVec3::new(v[0], v[1], v[2])
+    }
+}
+impl From<UVec3> for [u32; 3] {
+    fn from(v: UVec3) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UVec4 {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+    pub w: u32,
+}
+impl UVec4 {
+    pub const ZERO: Self = Self::splat(0);
+    pub const ONE: Self = Self::splat(1);
+
+    pub const fn new(x: u32, y: u32, z: u32, w: u32) -> Self {
+        Self { x, y, z, w }
+    }
+    pub const fn splat(v: u32) -> Self {
+        Self::new(v, v, v, v)
+    }
+}
+impl From<[u32; 4]> for UVec4 {
+    fn from(v: [u32; 4]) -> Self {
+        UVec4::new(v[0], v[1], v[2], v[3])
+    }
+}
+impl From<UVec4> for [u32; 4] {
+    fn from(v: UVec4) -> Self {
+        [v.x, v.y, v.z, v.w]
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct IVec2 {
+    pub x: i32,
+    pub y: i32,
+}
+impl IVec2 {
+    pub const ZERO: Self = Self::splat(0);
+    pub const ONE: Self = Self::splat(1);
+
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+    pub const fn splat(v: i32) -> Self {
+        Self::new(v, v)
+    }
+}
+impl From<[i32; 2]> for IVec2 {
+    fn from(v: [i32; 2]) -> Self {
+        IVec2::new(v[0], v[1])
+    }
+}
+impl From<IVec2> for [i32; 2] {
+    fn from(v: IVec2) -> Self {
+        [v.x, v.y]
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct IVec3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+impl IVec3 {
+    pub const ZERO: Self = Self::splat(0);
+    pub const ONE: Self = Self::splat(1);
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+    pub const fn splat(v: i32) -> Self {
+        Self::new(v, v, v)
+    }
+}
+impl From<[i32; 3]> for IVec3 {
+    fn from(v: [i32; 3]) -> Self {
+        IVec3::new(v[0], v[1], v[2])
+    }
+}
+impl From<IVec3> for [i32; 3] {
+    fn from(v: IVec3) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct IVec4 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub w: i32,
+}
+impl IVec4 {
+    pub const ZERO: Self = Self::splat(0);
+    pub const ONE: Self = Self::splat(1);
+
+    pub const fn new(x: i32, y: i32, z: i32, w: i32) -> Self {
+        Self { x, y, z, w }
+    }
+    pub const fn splat(v: i32) -> Self {
+        Self::new(v, v, v, v)
+    }
+}
+impl From<[i32; 4]> for IVec4 {
+    fn from(v: [i32; 4]) -> Self {
+        IVec4::new(v[0], v[1], v[2], v[3])
+    }
+}
+impl From<IVec4> for [i32; 4] {
+    fn from(v: IVec4) -> Self {
+        [v.x, v.y, v.z, v.w]
+    }
+}
+
 // 矩阵为列存储
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
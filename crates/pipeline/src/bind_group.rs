@@ -1,37 +1,38 @@
-use crate::TextureFormat;
+use std::fmt;
 
-#[derive(Debug, Clone, Default)]
-pub struct Sampler {}
-#[derive(Debug, Clone, Default)]
-pub struct Texture {
-    pub width: u32,
-    pub height: u32,
-    pub format: TextureFormat,
-    pub data: Vec<u8>,
-}
+use crate::{
+    AddressSpace, Buffer, FilterMode, Sampler, ShaderStruct, StorageTexture, StorageTextureAccess,
+    Texture, TextureFormat, TextureViewDimension, UNIFORM_OFFSET_ALIGNMENT,
+};
 
 #[derive(Debug, Clone, Default)]
 pub enum BindType {
     // u32存储uniform对应的索引
     Uniform(Vec<u8>),
+    // 存储缓冲，着色器可以读写
+    Storage(Buffer),
     Sampler(Sampler),
     Texture(Texture),
+    // 存储纹理，着色器可以读写
+    StorageTexture(StorageTexture),
     #[default]
     Empty,
 }
-impl From<BindType> for Texture {
-    fn from(value: BindType) -> Self {
-        match value {
-            BindType::Texture(v) => v,
-            _ => Texture::default(),
-        }
+impl BindType {
+    // 按照 WGSL uniform 的内存布局编码
+    pub fn uniform<T: ShaderStruct>(value: &T) -> Self {
+        BindType::Uniform(value.encode(AddressSpace::Uniform))
     }
-}
-impl From<BindType> for Sampler {
-    fn from(value: BindType) -> Self {
-        match value {
-            BindType::Sampler(v) => v,
-            _ => Sampler::default(),
+
+    // 资源类型的名字，用于错误信息
+    pub fn name(&self) -> &'static str {
+        match self {
+            BindType::Uniform(_) => "uniform",
+            BindType::Storage(_) => "storage buffer",
+            BindType::Sampler(_) => "sampler",
+            BindType::Texture(_) => "texture",
+            BindType::StorageTexture(_) => "storage texture",
+            BindType::Empty => "empty",
         }
     }
 }
@@ -40,6 +41,16 @@ impl From<Texture> for BindType {
         BindType::Texture(value)
     }
 }
+impl From<StorageTexture> for BindType {
+    fn from(value: StorageTexture) -> Self {
+        BindType::StorageTexture(value)
+    }
+}
+impl From<Buffer> for BindType {
+    fn from(value: Buffer) -> Self {
+        BindType::Storage(value)
+    }
+}
 impl From<Sampler> for BindType {
     fn from(value: Sampler) -> Self {
         BindType::Sampler(value)
@@ -47,3 +58,851 @@ impl From<Sampler> for BindType {
 }
 
 pub type BindGroup = Vec<BindType>;
+
+bitflags::bitflags! {
+    /// Describes the shader stages that a binding will be visible from.
+    ///
+    /// Corresponds to [WebGPU `GPUShaderStageFlags`](
+    /// https://gpuweb.github.io/gpuweb/#typedefdef-gpushaderstageflags).
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct ShaderStages: u32 {
+        const NONE = 0;
+        const VERTEX = 1 << 0;
+        const FRAGMENT = 1 << 1;
+        const COMPUTE = 1 << 2;
+        const VERTEX_FRAGMENT = Self::VERTEX.bits() | Self::FRAGMENT.bits();
+    }
+}
+
+// 绑定资源的类型，对应 wgpu 的 BindingType
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BindingType {
+    // min_binding_size 为 uniform 至少需要的字节数，为 0 时不检查
+    // has_dynamic_offset 为 true 时设置绑定组需要提供动态偏移
+    Uniform {
+        has_dynamic_offset: bool,
+        min_binding_size: usize,
+    },
+    // read_only 只是声明，着色器拿到的缓冲仍然可以写入
+    Storage {
+        read_only: bool,
+        min_binding_size: usize,
+    },
+    Sampler(SamplerBindingType),
+    // view_dimension 为着色器中声明的纹理维度，例如 texture_cube 对应 Cube
+    // sample_type 为采样得到的类型，例如 texture_depth_2d 对应 Depth
+    Texture {
+        sample_type: TextureSampleType,
+        view_dimension: TextureViewDimension,
+    },
+    // 对应 WGSL 的 texture_storage_2d<format, access> 等类型，格式需要完全一致
+    StorageTexture {
+        access: StorageTextureAccess,
+        format: TextureFormat,
+        view_dimension: TextureViewDimension,
+    },
+}
+impl BindingType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BindingType::Uniform { .. } => "uniform",
+            BindingType::Storage { .. } => "storage buffer",
+            BindingType::Sampler(_) => "sampler",
+            BindingType::Texture { .. } => "texture",
+            BindingType::StorageTexture { .. } => "storage texture",
+        }
+    }
+}
+
+// 采样器绑定的类型，对应 wgpu 的 SamplerBindingType
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SamplerBindingType {
+    // 可以使用线性过滤的采样器
+    #[default]
+    Filtering,
+    // 所有过滤方式都为 Nearest 的采样器
+    NonFiltering,
+    // 比较采样器，对应 WGSL 的 sampler_comparison，compare 不能为 None
+    Comparison,
+}
+impl SamplerBindingType {
+    fn accepts(&self, sampler: &Sampler) -> bool {
+        let state = &sampler.state;
+        match self {
+            SamplerBindingType::Filtering => state.compare.is_none(),
+            SamplerBindingType::NonFiltering => {
+                state.compare.is_none()
+                    && state.mag_filter == FilterMode::Nearest
+                    && state.min_filter == FilterMode::Nearest
+                    && state.mipmap_filter == FilterMode::Nearest
+            }
+            SamplerBindingType::Comparison => state.compare.is_some(),
+        }
+    }
+}
+
+// 纹理采样得到的类型，对应 wgpu 的 TextureSampleType
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextureSampleType {
+    // filterable 为 false 时不能使用线性过滤，深度纹理也可以绑定为不可过滤的浮点纹理
+    Float { filterable: bool },
+    // 深度纹理，可以使用比较采样器
+    Depth,
+    Sint,
+    Uint,
+}
+impl Default for TextureSampleType {
+    fn default() -> Self {
+        TextureSampleType::Float { filterable: true }
+    }
+}
+impl TextureSampleType {
+    // 绑定的纹理格式是否满足着色器声明的类型
+    fn accepts(&self, format: TextureFormat) -> bool {
+        match (self, format.sample_type()) {
+            (TextureSampleType::Float { filterable: false }, TextureSampleType::Float { .. })
+            | (TextureSampleType::Float { filterable: false }, TextureSampleType::Depth) => true,
+            (expected, found) => *expected == found,
+        }
+    }
+}
+
+/// Describes a single binding inside a bind group.
+///
+/// Corresponds to [WebGPU `GPUBindGroupLayoutEntry`](
+/// https://gpuweb.github.io/gpuweb/#dictdef-gpubindgrouplayoutentry).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BindGroupLayoutEntry {
+    pub binding: usize,
+    pub visibility: ShaderStages,
+    pub ty: BindingType,
+}
+
+// 绑定组布局，声明绑定组中每个绑定的索引、资源类型以及可见的着色器阶段
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindGroupLayout {
+    pub entries: Vec<BindGroupLayoutEntry>,
+}
+impl BindGroupLayout {
+    pub fn new(entries: Vec<BindGroupLayoutEntry>) -> Self {
+        Self { entries }
+    }
+
+    // 检查绑定组是否满足布局，group 只用于错误信息
+    pub fn validate(&self, group: usize, bind_group: &BindGroup) -> Result<(), BindingError> {
+        for entry in &self.entries {
+            let binding = entry.binding;
+            let resource = match bind_group.get(binding) {
+                Some(BindType::Empty) | None => {
+                    return Err(BindingError::MissingBinding { group, binding })
+                }
+                Some(resource) => resource,
+            };
+            match (entry.ty, resource) {
+                (
+                    BindingType::Uniform {
+                        min_binding_size, ..
+                    },
+                    BindType::Uniform(data),
+                ) => {
+                    if data.len() < min_binding_size {
+                        return Err(BindingError::SizeMismatch {
+                            group,
+                            binding,
+                            expected: min_binding_size,
+                            found: data.len(),
+                        });
+                    }
+                }
+                (
+                    BindingType::Storage {
+                        min_binding_size, ..
+                    },
+                    BindType::Storage(buffer),
+                ) => {
+                    if buffer.size() < min_binding_size {
+                        return Err(BindingError::SizeMismatch {
+                            group,
+                            binding,
+                            expected: min_binding_size,
+                            found: buffer.size(),
+                        });
+                    }
+                }
+                (
+                    BindingType::Texture {
+                        sample_type,
+                        view_dimension,
+                    },
+                    BindType::Texture(texture),
+                ) => {
+                    // 零大小的纹理在采样时会越界
+                    if !texture.is_valid() {
+                        return Err(BindingError::InvalidTexture { group, binding });
+                    }
+                    if texture.dimension != view_dimension {
+                        return Err(BindingError::DimensionMismatch {
+                            group,
+                            binding,
+                            expected: view_dimension,
+                            found: texture.dimension,
+                        });
+                    }
+                    if !sample_type.accepts(texture.format) {
+                        return Err(BindingError::SampleTypeMismatch {
+                            group,
+                            binding,
+                            expected: sample_type,
+                            found: texture.format,
+                        });
+                    }
+                }
+                (
+                    BindingType::StorageTexture {
+                        format,
+                        view_dimension,
+                        ..
+                    },
+                    BindType::StorageTexture(texture),
+                ) => {
+                    if texture.width == 0
+                        || texture.height == 0
+                        || texture.depth_or_array_layers == 0
+                        || texture.data.size() < texture.size_in_bytes()
+                    {
+                        return Err(BindingError::InvalidTexture { group, binding });
+                    }
+                    if texture.dimension != view_dimension {
+                        return Err(BindingError::DimensionMismatch {
+                            group,
+                            binding,
+                            expected: view_dimension,
+                            found: texture.dimension,
+                        });
+                    }
+                    if texture.format != format {
+                        return Err(BindingError::FormatMismatch {
+                            group,
+                            binding,
+                            expected: format,
+                            found: texture.format,
+                        });
+                    }
+                }
+                (BindingType::Sampler(ty), BindType::Sampler(sampler)) => {
+                    if !ty.accepts(sampler) {
+                        return Err(BindingError::SamplerTypeMismatch {
+                            group,
+                            binding,
+                            expected: ty,
+                        });
+                    }
+                }
+                (ty, resource) => {
+                    return Err(BindingError::TypeMismatch {
+                        group,
+                        binding,
+                        expected: ty.name(),
+                        found: resource.name(),
+                    })
+                }
+            }
+        }
+        // 布局中没有声明的绑定只允许为空
+        for (binding, resource) in bind_group.iter().enumerate() {
+            if !matches!(resource, BindType::Empty)
+                && !self.entries.iter().any(|entry| entry.binding == binding)
+            {
+                return Err(BindingError::UnexpectedBinding { group, binding });
+            }
+        }
+        Ok(())
+    }
+}
+
+// 管线布局，按照绑定组的索引存储每个绑定组的布局
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipelineLayout {
+    pub bind_group_layouts: Vec<BindGroupLayout>,
+}
+impl PipelineLayout {
+    pub fn new(bind_group_layouts: Vec<BindGroupLayout>) -> Self {
+        Self { bind_group_layouts }
+    }
+
+    // 设置绑定组时的检查，返回每个绑定对应的动态偏移
+    // 动态偏移按照绑定索引从小到大的顺序对应布局中 has_dynamic_offset 的绑定
+    pub fn validate_bind_group(
+        &self,
+        group: usize,
+        bind_group: &BindGroup,
+        offsets: &[u32],
+    ) -> Result<Vec<usize>, BindingError> {
+        let layout = self
+            .bind_group_layouts
+            .get(group)
+            .ok_or(BindingError::UnexpectedGroup { group })?;
+        layout.validate(group, bind_group)?;
+
+        let mut dynamic_entries: Vec<(usize, usize)> = layout
+            .entries
+            .iter()
+            .filter_map(|entry| match entry.ty {
+                BindingType::Uniform {
+                    has_dynamic_offset: true,
+                    min_binding_size,
+                } => Some((entry.binding, min_binding_size)),
+                _ => None,
+            })
+            .collect();
+        dynamic_entries.sort();
+        if dynamic_entries.len() != offsets.len() {
+            return Err(BindingError::DynamicOffsetCount {
+                group,
+                expected: dynamic_entries.len(),
+                found: offsets.len(),
+            });
+        }
+
+        let mut dynamic_offsets = vec![0; bind_group.len()];
+        for (&(binding, min_binding_size), &offset) in dynamic_entries.iter().zip(offsets) {
+            let offset = offset as usize;
+            if !offset.is_multiple_of(UNIFORM_OFFSET_ALIGNMENT) {
+                return Err(BindingError::UnalignedDynamicOffset {
+                    group,
+                    binding,
+                    offset,
+                });
+            }
+            // validate 已经保证绑定是 uniform
+            if let BindType::Uniform(data) = &bind_group[binding] {
+                if offset + min_binding_size > data.len() {
+                    return Err(BindingError::DynamicOffsetOutOfBounds {
+                        group,
+                        binding,
+                        offset,
+                        size: data.len(),
+                    });
+                }
+            }
+            dynamic_offsets[binding] = offset;
+        }
+        Ok(dynamic_offsets)
+    }
+
+    // 绘制时的检查，布局中声明的绑定组都必须已经设置
+    pub fn validate_bind_groups(&self, bind_groups: &BindGroups) -> Result<(), BindingError> {
+        for (group, layout) in self.bind_group_layouts.iter().enumerate() {
+            match bind_groups.groups.get(group) {
+                Some(bind_group) if !bind_group.is_empty() || layout.entries.is_empty() => {
+                    layout.validate(group, bind_group)?
+                }
+                _ => return Err(BindingError::MissingGroup { group }),
+            }
+        }
+        Ok(())
+    }
+}
+
+// 着色器访问绑定资源时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingError {
+    // 对应的绑定组没有设置
+    MissingGroup {
+        group: usize,
+    },
+    // 绑定组中没有对应的绑定或者绑定为空
+    MissingBinding {
+        group: usize,
+        binding: usize,
+    },
+    // 绑定的资源类型和着色器期望的不一致
+    TypeMismatch {
+        group: usize,
+        binding: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+    // uniform 的大小小于着色器期望的类型大小
+    SizeMismatch {
+        group: usize,
+        binding: usize,
+        expected: usize,
+        found: usize,
+    },
+    // 纹理大小为 0、大小和维度不匹配、mip 层级数量不合法或者数据长度小于所有子资源的大小
+    InvalidTexture {
+        group: usize,
+        binding: usize,
+    },
+    // 纹理的维度和着色器期望的不一致
+    DimensionMismatch {
+        group: usize,
+        binding: usize,
+        expected: TextureViewDimension,
+        found: TextureViewDimension,
+    },
+    // 纹理格式采样得到的类型和着色器期望的不一致，例如把颜色纹理绑定到 texture_depth_2d
+    SampleTypeMismatch {
+        group: usize,
+        binding: usize,
+        expected: TextureSampleType,
+        found: TextureFormat,
+    },
+    // 存储纹理的格式和着色器声明的不一致
+    FormatMismatch {
+        group: usize,
+        binding: usize,
+        expected: TextureFormat,
+        found: TextureFormat,
+    },
+    // 采样器和着色器期望的类型不一致，例如比较采样器没有设置 compare
+    SamplerTypeMismatch {
+        group: usize,
+        binding: usize,
+        expected: SamplerBindingType,
+    },
+    // 管线布局中没有这个绑定组
+    UnexpectedGroup {
+        group: usize,
+    },
+    // 绑定组布局中没有声明这个绑定
+    UnexpectedBinding {
+        group: usize,
+        binding: usize,
+    },
+    // 动态偏移的数量和布局中带动态偏移的绑定数量不一致
+    DynamicOffsetCount {
+        group: usize,
+        expected: usize,
+        found: usize,
+    },
+    // 动态偏移没有按照 UNIFORM_OFFSET_ALIGNMENT 对齐
+    UnalignedDynamicOffset {
+        group: usize,
+        binding: usize,
+        offset: usize,
+    },
+    // 动态偏移之后剩余的数据不足 min_binding_size
+    DynamicOffsetOutOfBounds {
+        group: usize,
+        binding: usize,
+        offset: usize,
+        size: usize,
+    },
+    // 绑定组布局中这个绑定的 visibility 不包含访问它的着色器阶段
+    NotVisible {
+        group: usize,
+        binding: usize,
+        stage: ShaderStages,
+    },
+}
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingError::MissingGroup { group } => write!(f, "bind group {group} is not set"),
+            BindingError::MissingBinding { group, binding } => {
+                write!(f, "binding {binding} of bind group {group} is missing")
+            }
+            BindingError::TypeMismatch {
+                group,
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "binding {binding} of bind group {group} is a {found}, expected a {expected}"
+            ),
+            BindingError::SizeMismatch {
+                group,
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "binding {binding} of bind group {group} holds {found} bytes, expected at least {expected} bytes"
+            ),
+            BindingError::InvalidTexture { group, binding } => write!(
+                f,
+                "binding {binding} of bind group {group} is an empty, truncated or malformed texture"
+            ),
+            BindingError::DimensionMismatch {
+                group,
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "binding {binding} of bind group {group} is a {found:?} texture, expected {expected:?}"
+            ),
+            BindingError::SampleTypeMismatch {
+                group,
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "binding {binding} of bind group {group} has format {found:?}, which cannot be sampled as {expected:?}"
+            ),
+            BindingError::FormatMismatch {
+                group,
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "binding {binding} of bind group {group} has format {found:?}, expected {expected:?}"
+            ),
+            BindingError::SamplerTypeMismatch {
+                group,
+                binding,
+                expected,
+            } => write!(
+                f,
+                "binding {binding} of bind group {group} is not a {expected:?} sampler"
+            ),
+            BindingError::UnexpectedGroup { group } => {
+                write!(f, "bind group {group} is not declared in the pipeline layout")
+            }
+            BindingError::UnexpectedBinding { group, binding } => write!(
+                f,
+                "binding {binding} of bind group {group} is not declared in the bind group layout"
+            ),
+            BindingError::DynamicOffsetCount {
+                group,
+                expected,
+                found,
+            } => write!(
+                f,
+                "bind group {group} expects {expected} dynamic offsets, found {found}"
+            ),
+            BindingError::UnalignedDynamicOffset {
+                group,
+                binding,
+                offset,
+            } => write!(
+                f,
+                "dynamic offset {offset} of binding {binding} in bind group {group} is not a multiple of {UNIFORM_OFFSET_ALIGNMENT}"
+            ),
+            BindingError::DynamicOffsetOutOfBounds {
+                group,
+                binding,
+                offset,
+                size,
+            } => write!(
+                f,
+                "dynamic offset {offset} of binding {binding} in bind group {group} is out of bounds of the {size} bytes uniform"
+            ),
+            BindingError::NotVisible {
+                group,
+                binding,
+                stage,
+            } => write!(
+                f,
+                "binding {binding} of bind group {group} is not visible to the {stage:?} stage"
+            ),
+        }
+    }
+}
+impl std::error::Error for BindingError {}
+
+// 渲染时设置的所有绑定组，着色器通过不可变引用访问其中的资源
+#[derive(Debug, Clone, Default)]
+pub struct BindGroups {
+    groups: Vec<BindGroup>,
+    // 每个绑定组中每个绑定的动态偏移，没有动态偏移的绑定为 0
+    dynamic_offsets: Vec<Vec<usize>>,
+    // 每个绑定组中每个绑定可见的着色器阶段，按照布局设置，没有布局时为空，所有阶段都可以访问
+    visibility: Vec<Vec<ShaderStages>>,
+}
+impl BindGroups {
+    // 设置对应位置的绑定组，会替换掉之前设置的绑定组和动态偏移
+    // 块压缩格式的纹理会解压为 decompressed_format 的格式
+    pub fn set(&mut self, index: usize, group: BindGroup) {
+        self.set_with_offsets(index, group, Vec::new());
+    }
+
+    // dynamic_offsets 按照绑定索引存储，长度不足的部分视为 0
+    pub fn set_with_offsets(
+        &mut self,
+        index: usize,
+        group: BindGroup,
+        dynamic_offsets: Vec<usize>,
+    ) {
+        if self.groups.len() <= index {
+            self.groups.resize(index + 1, BindGroup::new());
+            self.dynamic_offsets.resize(index + 1, Vec::new());
+            self.visibility.resize(index + 1, Vec::new());
+        }
+        // wgpu 中压缩纹理由硬件直接采样，这里在绑定时解压一次，采样时不需要每读取一个纹素就解码整个块
+        self.groups[index] = group
+            .into_iter()
+            .map(|resource| match resource {
+                BindType::Texture(texture) if texture.format.is_compressed() => {
+                    BindType::Texture(texture.decompress())
+                }
+                resource => resource,
+            })
+            .collect();
+        self.dynamic_offsets[index] = dynamic_offsets;
+        self.visibility[index] = Vec::new();
+    }
+
+    // 有管线布局时会按照布局检查绑定组，检查不通过时不会设置
+    // offsets 为动态偏移，对应布局中 has_dynamic_offset 的 uniform
+    pub fn set_validated(
+        &mut self,
+        layout: Option<&PipelineLayout>,
+        index: usize,
+        group: BindGroup,
+        offsets: &[u32],
+    ) -> Result<(), BindingError> {
+        let dynamic_offsets = match layout {
+            Some(layout) => layout.validate_bind_group(index, &group, offsets)?,
+            // 没有布局时无法知道哪些绑定有动态偏移
+            None if !offsets.is_empty() => {
+                return Err(BindingError::DynamicOffsetCount {
+                    group: index,
+                    expected: 0,
+                    found: offsets.len(),
+                })
+            }
+            None => Vec::new(),
+        };
+        let len = group.len();
+        self.set_with_offsets(index, group, dynamic_offsets);
+        // validate_bind_group 已经保证有布局时绑定组在布局中
+        if let Some(layout) = layout {
+            let mut visibility = vec![ShaderStages::NONE; len];
+            for entry in &layout.bind_group_layouts[index].entries {
+                visibility[entry.binding] = entry.visibility;
+            }
+            self.visibility[index] = visibility;
+        }
+        Ok(())
+    }
+
+    fn dynamic_offset(&self, group: usize, binding: usize) -> usize {
+        self.dynamic_offsets[group]
+            .get(binding)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // stage 为访问绑定的着色器阶段，只能包含一个阶段
+    // 按照布局设置的绑定组只有 visibility 包含这个阶段的绑定可以访问
+    pub fn get(
+        &self,
+        stage: ShaderStages,
+        group: usize,
+        binding: usize,
+    ) -> Result<&BindType, BindingError> {
+        let bind_group = self
+            .groups
+            .get(group)
+            .ok_or(BindingError::MissingGroup { group })?;
+        let resource = match bind_group.get(binding) {
+            Some(BindType::Empty) | None => {
+                return Err(BindingError::MissingBinding { group, binding })
+            }
+            Some(resource) => resource,
+        };
+        match self.visibility[group].get(binding) {
+            Some(visibility) if !visibility.contains(stage) => Err(BindingError::NotVisible {
+                group,
+                binding,
+                stage,
+            }),
+            _ => Ok(resource),
+        }
+    }
+
+    // 读取 uniform 并按照 WGSL uniform 的内存布局解码为对应的类型
+    pub fn uniform<T: ShaderStruct>(
+        &self,
+        stage: ShaderStages,
+        group: usize,
+        binding: usize,
+    ) -> Result<T, BindingError> {
+        match self.get(stage, group, binding)? {
+            BindType::Uniform(data) => {
+                let data = &data[self.dynamic_offset(group, binding).min(data.len())..];
+                let size = T::size(AddressSpace::Uniform);
+                if data.len() < size {
+                    return Err(BindingError::SizeMismatch {
+                        group,
+                        binding,
+                        expected: size,
+                        found: data.len(),
+                    });
+                }
+                Ok(T::decode(data, AddressSpace::Uniform))
+            }
+            other => Err(BindingError::TypeMismatch {
+                group,
+                binding,
+                expected: "uniform",
+                found: other.name(),
+            }),
+        }
+    }
+
+    pub fn storage(
+        &self,
+        stage: ShaderStages,
+        group: usize,
+        binding: usize,
+    ) -> Result<&Buffer, BindingError> {
+        match self.get(stage, group, binding)? {
+            BindType::Storage(buffer) => Ok(buffer),
+            other => Err(BindingError::TypeMismatch {
+                group,
+                binding,
+                expected: "storage buffer",
+                found: other.name(),
+            }),
+        }
+    }
+
+    pub fn texture(
+        &self,
+        stage: ShaderStages,
+        group: usize,
+        binding: usize,
+    ) -> Result<&Texture, BindingError> {
+        match self.get(stage, group, binding)? {
+            BindType::Texture(texture) => Ok(texture),
+            other => Err(BindingError::TypeMismatch {
+                group,
+                binding,
+                expected: "texture",
+                found: other.name(),
+            }),
+        }
+    }
+
+    pub fn storage_texture(
+        &self,
+        stage: ShaderStages,
+        group: usize,
+        binding: usize,
+    ) -> Result<&StorageTexture, BindingError> {
+        match self.get(stage, group, binding)? {
+            BindType::StorageTexture(texture) => Ok(texture),
+            other => Err(BindingError::TypeMismatch {
+                group,
+                binding,
+                expected: "storage texture",
+                found: other.name(),
+            }),
+        }
+    }
+
+    pub fn sampler(
+        &self,
+        stage: ShaderStages,
+        group: usize,
+        binding: usize,
+    ) -> Result<&Sampler, BindingError> {
+        match self.get(stage, group, binding)? {
+            BindType::Sampler(sampler) => Ok(sampler),
+            other => Err(BindingError::TypeMismatch {
+                group,
+                binding,
+                expected: "sampler",
+                found: other.name(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::UVec3;
+
+    fn fragment_uniform_layout() -> PipelineLayout {
+        PipelineLayout::new(vec![BindGroupLayout::new(vec![BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Uniform {
+                has_dynamic_offset: false,
+                min_binding_size: 4,
+            },
+        }])])
+    }
+
+    #[test]
+    fn binding_visibility() {
+        let layout = fragment_uniform_layout();
+        let mut bind_groups = BindGroups::default();
+        bind_groups
+            .set_validated(Some(&layout), 0, vec![BindType::uniform(&1u32)], &[])
+            .unwrap();
+        assert_eq!(
+            bind_groups.uniform::<u32>(ShaderStages::FRAGMENT, 0, 0),
+            Ok(1)
+        );
+        assert_eq!(
+            bind_groups.uniform::<u32>(ShaderStages::VERTEX, 0, 0),
+            Err(BindingError::NotVisible {
+                group: 0,
+                binding: 0,
+                stage: ShaderStages::VERTEX,
+            })
+        );
+        assert!(matches!(
+            bind_groups.get(ShaderStages::COMPUTE, 0, 0),
+            Err(BindingError::NotVisible { .. })
+        ));
+
+        // 没有布局设置的绑定组所有阶段都可以访问
+        bind_groups.set(0, vec![BindType::uniform(&2u32)]);
+        assert_eq!(
+            bind_groups.uniform::<u32>(ShaderStages::VERTEX, 0, 0),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn compressed_texture_is_decompressed_when_bound() {
+        // 两个 BC1 块，c0 分别为红色和绿色，索引都为 0
+        let texture = Texture {
+            width: 8,
+            height: 4,
+            depth_or_array_layers: 1,
+            dimension: TextureViewDimension::D2,
+            format: TextureFormat::Bc1RgbaUnorm,
+            mip_level_count: 1,
+            data: [0x00, 0xF8, 0, 0, 0, 0, 0, 0, 0xE0, 0x07, 0, 0, 0, 0, 0, 0].to_vec(),
+        };
+        let mut bind_groups = BindGroups::default();
+        bind_groups.set(0, vec![BindType::Texture(texture.clone())]);
+        let bound = bind_groups.texture(ShaderStages::FRAGMENT, 0, 0).unwrap();
+        assert_eq!(bound.format, TextureFormat::Rgba8Unorm);
+        assert_eq!(bound.data.len(), 8 * 4 * 4);
+        for (x, y) in [(0, 0), (3, 3), (4, 0), (7, 3)] {
+            let coords = UVec3::new(x, y, 0);
+            let expected = texture.texel(coords, 0, 0);
+            assert_eq!(bound.texel(coords, 0, 0).x, expected.x);
+            assert_eq!(bound.texel(coords, 0, 0).y, expected.y);
+        }
+        assert_eq!(bound.texel(UVec3::new(7, 3, 0), 0, 0).y, 1.);
+
+        // EAC R11 解压为 16 位格式，保留 11 位的精度
+        let texture = Texture {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+            dimension: TextureViewDimension::D2,
+            format: TextureFormat::EacR11Unorm,
+            mip_level_count: 1,
+            data: [128, 0x2D, 0, 0, 0, 0, 0, 0].to_vec(),
+        };
+        bind_groups.set(0, vec![BindType::Texture(texture.clone())]);
+        let bound = bind_groups.texture(ShaderStages::FRAGMENT, 0, 0).unwrap();
+        assert_eq!(bound.format, TextureFormat::Rgba16Unorm);
+        let coords = UVec3::ZERO;
+        assert_eq!(bound.texel(coords, 0, 0).x, texture.texel(coords, 0, 0).x);
+    }
+}
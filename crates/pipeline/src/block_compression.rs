@@ -0,0 +1,1097 @@
+// 块压缩格式的软件解码，每个块为 4x4 个纹素，解码为按行依次排列的 16 个 rgba8 纹素
+// EAC R11 和 RG11 解码为 16 个 rgba16 纹素
+// BC1-BC5: https://learn.microsoft.com/en-us/windows/win32/direct3d10/d3d10-graphics-programming-guide-resources-block-compression
+// BC7: https://learn.microsoft.com/en-us/windows/win32/direct3d11/bc7-format-mode-reference
+// ETC2/EAC: https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#ETC2
+
+use crate::TextureFormat;
+
+pub(crate) type Block = [[u8; 4]; 16];
+pub(crate) type Block16 = [[u16; 4]; 16];
+
+// 解码一个块，bytes 的长度为格式的块大小
+pub(crate) fn decode_block(format: TextureFormat, bytes: &[u8]) -> Block {
+    match format {
+        TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => decode_bc1(bytes, true),
+        TextureFormat::Bc2RgbaUnorm | TextureFormat::Bc2RgbaUnormSrgb => {
+            // 前 8 字节为每个纹素 4 位的 alpha
+            let alpha = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+            let mut texels = decode_bc1(&bytes[8..], false);
+            for (i, texel) in texels.iter_mut().enumerate() {
+                texel[3] = ((alpha >> (4 * i)) & 0xF) as u8 * 17;
+            }
+            texels
+        }
+        TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => {
+            let alpha = decode_bc4(&bytes[..8]);
+            let mut texels = decode_bc1(&bytes[8..], false);
+            for (texel, alpha) in texels.iter_mut().zip(alpha) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        TextureFormat::Bc4RUnorm => decode_bc4(bytes).map(|r| [r, 0, 0, 255]),
+        TextureFormat::Bc5RgUnorm => {
+            let (r, g) = (decode_bc4(&bytes[..8]), decode_bc4(&bytes[8..]));
+            std::array::from_fn(|i| [r[i], g[i], 0, 255])
+        }
+        TextureFormat::Bc7RgbaUnorm | TextureFormat::Bc7RgbaUnormSrgb => decode_bc7(bytes),
+        TextureFormat::Etc2Rgb8Unorm | TextureFormat::Etc2Rgb8UnormSrgb => {
+            decode_etc2(bytes, false)
+        }
+        TextureFormat::Etc2Rgb8A1Unorm | TextureFormat::Etc2Rgb8A1UnormSrgb => {
+            decode_etc2(bytes, true)
+        }
+        TextureFormat::Etc2Rgba8Unorm | TextureFormat::Etc2Rgba8UnormSrgb => {
+            // 前 8 字节为 EAC 编码的 alpha，后 8 字节和 Etc2Rgb8 相同
+            let alpha = decode_eac(&bytes[..8]);
+            let mut texels = decode_etc2(&bytes[8..], false);
+            for (texel, alpha) in texels.iter_mut().zip(alpha) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        _ => panic!("{format:?} is not a block-compressed format"),
+    }
+}
+
+// 解码一个 EAC R11 或 RG11 的块，RG11 的前 8 字节为 r，后 8 字节为 g
+pub(crate) fn decode_block16(format: TextureFormat, bytes: &[u8]) -> Block16 {
+    match format {
+        TextureFormat::EacR11Unorm => decode_eac11(bytes).map(|r| [r, 0, 0, u16::MAX]),
+        TextureFormat::EacRg11Unorm => {
+            let (r, g) = (decode_eac11(&bytes[..8]), decode_eac11(&bytes[8..]));
+            std::array::from_fn(|i| [r[i], g[i], 0, u16::MAX])
+        }
+        _ => panic!("{format:?} is not an 11 bit EAC format"),
+    }
+}
+
+// 两个 rgb565 端点颜色和每个纹素 2 位的索引
+// BC1 在 c0 <= c1 时为三色模式，第 4 个颜色为透明黑色，BC2 和 BC3 的颜色块总是四色模式
+fn decode_bc1(bytes: &[u8], allow_transparent: bool) -> Block {
+    let c0 = u16::from_le_bytes([bytes[0], bytes[1]]);
+    let c1 = u16::from_le_bytes([bytes[2], bytes[3]]);
+    let (e0, e1) = (rgb565(c0), rgb565(c1));
+    let mix = |w0: u32, w1: u32| -> [u8; 4] {
+        let sum = w0 + w1;
+        let c = |i: usize| ((e0[i] as u32 * w0 + e1[i] as u32 * w1 + sum / 2) / sum) as u8;
+        [c(0), c(1), c(2), 255]
+    };
+    let colors = if c0 > c1 || !allow_transparent {
+        [e0, e1, mix(2, 1), mix(1, 2)]
+    } else {
+        [e0, e1, mix(1, 1), [0; 4]]
+    };
+    let indices = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    std::array::from_fn(|i| colors[((indices >> (2 * i)) & 3) as usize])
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let (r, g, b) = (color >> 11, (color >> 5) & 0x3F, color & 0x1F);
+    [
+        (r << 3 | r >> 2) as u8,
+        (g << 2 | g >> 4) as u8,
+        (b << 3 | b >> 2) as u8,
+        255,
+    ]
+}
+
+// 两个 8 位端点和每个纹素 3 位的索引
+// e0 > e1 时在端点之间插值 6 个值，否则插值 4 个值，另外两个值为 0 和 255
+fn decode_bc4(bytes: &[u8]) -> [u8; 16] {
+    let (e0, e1) = (bytes[0] as u32, bytes[1] as u32);
+    let mut values = [0u8; 8];
+    values[0] = e0 as u8;
+    values[1] = e1 as u8;
+    if e0 > e1 {
+        for i in 1..7 {
+            values[i as usize + 1] = (((7 - i) * e0 + i * e1 + 3) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            values[i as usize + 1] = (((5 - i) * e0 + i * e1 + 2) / 5) as u8;
+        }
+        values[7] = 255;
+    }
+    let mut indices = [0; 8];
+    indices[..6].copy_from_slice(&bytes[2..8]);
+    let indices = u64::from_le_bytes(indices);
+    std::array::from_fn(|i| values[((indices >> (3 * i)) & 7) as usize])
+}
+
+// BC7 各个模式的参数
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    // 每个端点都有一个 p 位
+    endpoint_pbits: bool,
+    // 每个子集的两个端点共享一个 p 位
+    shared_pbits: bool,
+    index_bits: u32,
+    // 模式 4 和 5 的颜色和 alpha 分别使用不同的索引
+    index_bits2: u32,
+}
+
+#[rustfmt::skip]
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 3, index_bits2: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_pbits: false, shared_pbits: true, index_bits: 3, index_bits2: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index_bits2: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 2, index_bits2: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index_bits2: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index_bits2: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_pbits: true, shared_pbits: false, index_bits: 4, index_bits2: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_pbits: true, shared_pbits: false, index_bits: 2, index_bits2: 0 },
+];
+
+// 两个子集的分区，第 i 位为 1 表示第 i 个纹素属于子集 1
+#[rustfmt::skip]
+const BC7_PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80,
+    0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00, 0xFFF0, 0xF000,
+    0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C,
+    0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A,
+    0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C,
+    0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+// 三个子集的分区，每个纹素所属的子集
+#[rustfmt::skip]
+const BC7_PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+// 各个子集的锚点纹素，锚点的索引最高位隐含为 0，少存储一位，子集 0 的锚点总是纹素 0
+#[rustfmt::skip]
+const BC7_ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+#[rustfmt::skip]
+const BC7_ANCHORS_3_SECOND: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3,
+    3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5, 15, 15,
+    8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15,
+    3, 15, 5, 5, 5, 8, 5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+];
+
+#[rustfmt::skip]
+const BC7_ANCHORS_3_THIRD: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8,
+    15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6, 10, 15, 15, 10, 8,
+    15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8,
+    15, 3, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+// 索引位数为 2、3、4 时的插值权重
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+// 从低位开始依次读取的位流
+struct BitReader {
+    bits: u128,
+    position: u32,
+}
+impl BitReader {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.position) as u32 & ((1 << count) - 1);
+        self.position += count;
+        value
+    }
+}
+
+fn decode_bc7(bytes: &[u8]) -> Block {
+    let mut reader = BitReader {
+        bits: u128::from_le_bytes(bytes[..16].try_into().unwrap()),
+        position: 0,
+    };
+    // 模式由第一个为 1 的位决定，没有为 1 的位时是无效的块，解码为透明黑色
+    let Some(mode) = (0..8).find(|_| reader.read(1) == 1) else {
+        return [[0; 4]; 16];
+    };
+    let mode = &BC7_MODES[mode];
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    // endpoints[subset][endpoint][channel]，依次存储所有子集的 r、g、b、a
+    let mut endpoints = [[[0u32; 4]; 2]; 3];
+    for channel in 0..4 {
+        let bits = if channel < 3 {
+            mode.color_bits
+        } else {
+            mode.alpha_bits
+        };
+        for subset in &mut endpoints[..mode.subsets] {
+            for endpoint in subset.iter_mut() {
+                endpoint[channel] = reader.read(bits);
+            }
+        }
+    }
+    let (mut color_bits, mut alpha_bits) = (mode.color_bits, mode.alpha_bits);
+    if mode.endpoint_pbits || mode.shared_pbits {
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+        for subset in &mut endpoints[..mode.subsets] {
+            let shared = if mode.shared_pbits { reader.read(1) } else { 0 };
+            for endpoint in subset.iter_mut() {
+                let pbit = if mode.endpoint_pbits {
+                    reader.read(1)
+                } else {
+                    shared
+                };
+                endpoint.iter_mut().for_each(|c| *c = *c << 1 | pbit);
+            }
+        }
+    }
+    // 扩展到 8 位，低位重复高位，没有 alpha 的模式 alpha 为 255
+    let endpoints = endpoints.map(|subset| {
+        subset.map(|endpoint| {
+            std::array::from_fn::<u32, 4, _>(|channel| match channel {
+                3 if mode.alpha_bits == 0 => 255,
+                3 => unquantize(endpoint[channel], alpha_bits),
+                _ => unquantize(endpoint[channel], color_bits),
+            })
+        })
+    });
+
+    let subset_of = |texel: usize| -> usize {
+        match mode.subsets {
+            1 => 0,
+            2 => (BC7_PARTITIONS_2[partition] >> texel & 1) as usize,
+            _ => BC7_PARTITIONS_3[partition][texel] as usize,
+        }
+    };
+    let anchors = match mode.subsets {
+        1 => [0, 0, 0],
+        2 => [0, BC7_ANCHORS_2[partition] as usize, 0],
+        _ => [
+            0,
+            BC7_ANCHORS_3_SECOND[partition] as usize,
+            BC7_ANCHORS_3_THIRD[partition] as usize,
+        ],
+    };
+    let indices: [u32; 16] = std::array::from_fn(|texel| {
+        let anchor = anchors[subset_of(texel)] == texel;
+        reader.read(mode.index_bits - anchor as u32)
+    });
+    // 第二组索引只有一个子集，锚点为纹素 0
+    let indices2: [u32; 16] = std::array::from_fn(|texel| {
+        if mode.index_bits2 == 0 {
+            return 0;
+        }
+        reader.read(mode.index_bits2 - (texel == 0) as u32)
+    });
+
+    std::array::from_fn(|texel| {
+        let [e0, e1] = endpoints[subset_of(texel)];
+        let primary = (indices[texel], mode.index_bits);
+        let secondary = (indices2[texel], mode.index_bits2);
+        let ((color_index, color_index_bits), (alpha_index, alpha_index_bits)) =
+            match (mode.index_bits2, index_selection) {
+                (0, _) => (primary, primary),
+                (_, 0) => (primary, secondary),
+                _ => (secondary, primary),
+            };
+        let mut color: [u8; 4] = std::array::from_fn(|channel| {
+            if channel < 3 {
+                bc7_interpolate(e0[channel], e1[channel], color_index, color_index_bits)
+            } else {
+                bc7_interpolate(e0[channel], e1[channel], alpha_index, alpha_index_bits)
+            }
+        });
+        // 旋转时交换 alpha 和对应的颜色通道
+        if rotation > 0 {
+            color.swap(rotation as usize - 1, 3);
+        }
+        color
+    })
+}
+
+fn unquantize(value: u32, bits: u32) -> u32 {
+    let value = value << (8 - bits);
+    value | value >> bits
+}
+
+fn bc7_interpolate(e0: u32, e1: u32, index: u32, bits: u32) -> u8 {
+    let weight = match bits {
+        2 => BC7_WEIGHTS_2[index as usize],
+        3 => BC7_WEIGHTS_3[index as usize],
+        _ => BC7_WEIGHTS_4[index as usize],
+    };
+    (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+}
+
+// ETC1 的亮度修改值，每行为较小和较大的修改值
+const ETC_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+// ETC2 T 模式和 H 模式的距离
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+// EAC 的修改值
+#[rustfmt::skip]
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+// ETC2 的 rgb 块，按大端序存储
+// 差分模式下基础颜色溢出时分别表示 T、H 和平面模式
+// punch_through 为 true 时是 Etc2Rgb8A1，差分位表示块是否不透明，没有单独模式
+fn decode_etc2(bytes: &[u8], punch_through: bool) -> Block {
+    let b = bytes;
+    let indices = u32::from_be_bytes([b[4], b[5], b[6], b[7]]);
+    // 纹素 (x, y) 的索引按列存储在第 x * 4 + y 位，高位和低位分别在 indices 的高 16 位和低 16 位
+    let index = |texel: usize| -> usize {
+        let bit = (texel % 4) * 4 + texel / 4;
+        (((indices >> (16 + bit)) & 1) << 1 | (indices >> bit) & 1) as usize
+    };
+    let differential = b[3] & 2 != 0;
+    let opaque = !punch_through || differential;
+
+    let bases = if differential || punch_through {
+        let r = (b[0] >> 3) as i32 + sign_extend3(b[0]);
+        let g = (b[1] >> 3) as i32 + sign_extend3(b[1]);
+        let bl = (b[2] >> 3) as i32 + sign_extend3(b[2]);
+        if !(0..32).contains(&r) {
+            return etc2_paint_mode(b, index, opaque, false);
+        }
+        if !(0..32).contains(&g) {
+            return etc2_paint_mode(b, index, opaque, true);
+        }
+        if !(0..32).contains(&bl) {
+            return etc2_planar(b);
+        }
+        [
+            [b[0] >> 3, b[1] >> 3, b[2] >> 3].map(|c| extend5(c as i32)),
+            [r, g, bl].map(extend5),
+        ]
+    } else {
+        [
+            [b[0] >> 4, b[1] >> 4, b[2] >> 4].map(|c| c as i32 * 17),
+            [b[0] & 0xF, b[1] & 0xF, b[2] & 0xF].map(|c| c as i32 * 17),
+        ]
+    };
+    // flip 为 0 时左右两个 2x4 的子块，为 1 时上下两个 4x2 的子块
+    let flip = b[3] & 1 != 0;
+    let tables = [(b[3] >> 5) as usize, ((b[3] >> 2) & 7) as usize];
+    std::array::from_fn(|texel| {
+        let (x, y) = (texel % 4, texel / 4);
+        let subblock = if flip { y / 2 } else { x / 2 };
+        let index = index(texel);
+        let [small, large] = ETC_MODIFIERS[tables[subblock]];
+        let modifier = match index {
+            // 不透明位为 0 时索引 2 表示透明，索引 0 的修改值为 0
+            0 if !opaque => 0,
+            0 => small,
+            1 => large,
+            2 if !opaque => return [0; 4],
+            2 => -small,
+            _ => -large,
+        };
+        let base = bases[subblock];
+        let c = |i: usize| (base[i] + modifier).clamp(0, 255) as u8;
+        [c(0), c(1), c(2), 255]
+    })
+}
+
+// T 模式和 H 模式，两个 4 位的基础颜色加减距离得到 4 个颜色，索引直接选择颜色
+fn etc2_paint_mode(b: &[u8], index: impl Fn(usize) -> usize, opaque: bool, h_mode: bool) -> Block {
+    let add = |color: [i32; 3], d: i32| color.map(|c| (c + d).clamp(0, 255));
+    let colors = if h_mode {
+        let c0 = [
+            (b[0] >> 3) & 0xF,
+            (b[0] & 7) << 1 | (b[1] >> 4) & 1,
+            (b[1] & 8) | (b[1] & 3) << 1 | b[2] >> 7,
+        ];
+        let c1 = [
+            (b[2] >> 3) & 0xF,
+            (b[2] & 7) << 1 | b[3] >> 7,
+            (b[3] >> 3) & 0xF,
+        ];
+        let value = |c: [u8; 3]| (c[0] as u32) << 8 | (c[1] as u32) << 4 | c[2] as u32;
+        let distance = (b[3] & 4) | (b[3] & 1) << 1 | (value(c0) >= value(c1)) as u8;
+        let d = ETC_DISTANCES[distance as usize];
+        let (c0, c1) = (c0.map(|c| c as i32 * 17), c1.map(|c| c as i32 * 17));
+        [add(c0, d), add(c0, -d), add(c1, d), add(c1, -d)]
+    } else {
+        let c0 = [(b[0] >> 1) & 0xC | b[0] & 3, b[1] >> 4, b[1] & 0xF].map(|c| c as i32 * 17);
+        let c1 = [b[2] >> 4, b[2] & 0xF, b[3] >> 4].map(|c| c as i32 * 17);
+        let d = ETC_DISTANCES[((b[3] >> 1) & 6 | b[3] & 1) as usize];
+        [c0, add(c1, d), c1, add(c1, -d)]
+    };
+    std::array::from_fn(|texel| {
+        let index = index(texel);
+        if !opaque && index == 2 {
+            return [0; 4];
+        }
+        let [r, g, b] = colors[index];
+        [r as u8, g as u8, b as u8, 255]
+    })
+}
+
+// 平面模式，由原点、水平和竖直方向的三个颜色插值，总是不透明
+fn etc2_planar(b: &[u8]) -> Block {
+    let extend6 = |c: u8| (c << 2 | c >> 4) as i32;
+    let extend7 = |c: u8| (c << 1 | c >> 6) as i32;
+    let origin = [
+        extend6((b[0] >> 1) & 0x3F),
+        extend7((b[0] & 1) << 6 | (b[1] >> 1) & 0x3F),
+        extend6((b[1] & 1) << 5 | (b[2] & 0x18) | (b[2] & 3) << 1 | b[3] >> 7),
+    ];
+    let horizontal = [
+        extend6(((b[3] >> 2) & 0x1F) << 1 | b[3] & 1),
+        extend7(b[4] >> 1),
+        extend6((b[4] & 1) << 5 | b[5] >> 3),
+    ];
+    let vertical = [
+        extend6((b[5] & 7) << 3 | b[6] >> 5),
+        extend7((b[6] & 0x1F) << 2 | b[7] >> 6),
+        extend6(b[7] & 0x3F),
+    ];
+    std::array::from_fn(|texel| {
+        let (x, y) = ((texel % 4) as i32, (texel / 4) as i32);
+        let c = |i: usize| {
+            let value = x * (horizontal[i] - origin[i]) + y * (vertical[i] - origin[i]);
+            ((value + 4 * origin[i] + 2) >> 2).clamp(0, 255) as u8
+        };
+        [c(0), c(1), c(2), 255]
+    })
+}
+
+// 低 3 位为有符号的差值
+fn sign_extend3(value: u8) -> i32 {
+    (((value & 7) << 5) as i8 >> 5) as i32
+}
+
+fn extend5(c: i32) -> i32 {
+    c << 3 | c >> 2
+}
+
+// EAC 块的基础值、倍数和每个纹素的修改值，每个纹素 3 位的索引按列存储
+fn eac_modifiers(bytes: &[u8]) -> (i32, i32, [i32; 16]) {
+    let base = bytes[0] as i32;
+    let multiplier = (bytes[1] >> 4) as i32;
+    let modifiers = EAC_MODIFIERS[(bytes[1] & 0xF) as usize];
+    let indices = u64::from_be_bytes(bytes[..8].try_into().unwrap());
+    let texels = std::array::from_fn(|texel| {
+        let bit = (texel % 4) * 4 + texel / 4;
+        let index = (indices >> (45 - 3 * bit)) & 7;
+        modifiers[index as usize]
+    });
+    (base, multiplier, texels)
+}
+
+// EAC 的 alpha 块，基础值加上修改值乘以倍数
+fn decode_eac(bytes: &[u8]) -> [u8; 16] {
+    let (base, multiplier, modifiers) = eac_modifiers(bytes);
+    modifiers.map(|modifier| (base + modifier * multiplier).clamp(0, 255) as u8)
+}
+
+// EAC R11 的块，基础值和修改值扩展到 11 位，倍数为 0 时修改值不放大，结果按高位重复扩展到 16 位
+fn decode_eac11(bytes: &[u8]) -> [u16; 16] {
+    let (base, multiplier, modifiers) = eac_modifiers(bytes);
+    modifiers.map(|modifier| {
+        let modifier = if multiplier == 0 {
+            modifier
+        } else {
+            modifier * multiplier * 8
+        };
+        let value = (base * 8 + 4 + modifier).clamp(0, 2047) as u16;
+        value << 5 | value >> 6
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 纹素按行排列，第 i 个纹素使用索引 i % 4，即每行依次为 4 个颜色
+    const ROW_INDICES: u32 = 0xE4E4E4E4;
+
+    #[test]
+    fn bc1_four_color() {
+        // c0 = (31, 0, 0)，c1 = (0, 32, 16) 扩展到 8 位为 (0, 130, 132)，c0 > c1 为四色模式
+        let c0 = 0xF800u16.to_le_bytes();
+        let c1 = 0x0410u16.to_le_bytes();
+        let block = [&c0[..], &c1, &ROW_INDICES.to_le_bytes()].concat();
+        let texels = decode_block(TextureFormat::Bc1RgbaUnorm, &block);
+        let row = [
+            [255, 0, 0, 255],
+            [0, 130, 132, 255],
+            // 2/3 * c0 + 1/3 * c1
+            [170, 43, 44, 255],
+            // 1/3 * c0 + 2/3 * c1
+            [85, 87, 88, 255],
+        ];
+        assert_eq!(texels, std::array::from_fn(|i| row[i % 4]));
+    }
+
+    #[test]
+    fn bc1_three_color() {
+        // c0 = 0 <= c1 = (8, 32, 8) 为三色模式，第 3 个颜色为中点，第 4 个颜色为透明黑色
+        let c0 = 0u16.to_le_bytes();
+        let c1 = 0x4408u16.to_le_bytes();
+        let block = [&c0[..], &c1, &ROW_INDICES.to_le_bytes()].concat();
+        let row = [
+            [0, 0, 0, 255],
+            [66, 130, 66, 255],
+            [33, 65, 33, 255],
+            [0; 4],
+        ];
+        let texels = decode_block(TextureFormat::Bc1RgbaUnorm, &block);
+        assert_eq!(texels, std::array::from_fn(|i| row[i % 4]));
+        // BC3 的颜色块总是四色模式，第 4 个颜色不透明
+        let alpha = [255, 255, 0, 0, 0, 0, 0, 0];
+        let block = [&alpha[..], &block].concat();
+        let texels = decode_block(TextureFormat::Bc3RgbaUnorm, &block);
+        assert_eq!(texels[2], [22, 43, 22, 255]);
+        assert_eq!(texels[3], [44, 87, 44, 255]);
+    }
+
+    // 按 (值, 位数) 从低位开始依次写入 BC7 的块
+    fn bc7_block(fields: &[(u32, u32)]) -> [u8; 16] {
+        let mut bits = 0u128;
+        let mut position = 0;
+        for &(value, count) in fields {
+            assert!(value < 1 << count);
+            bits |= (value as u128) << position;
+            position += count;
+        }
+        assert_eq!(position, 128);
+        bits.to_le_bytes()
+    }
+
+    fn bc7_mode(mode: u32) -> (u32, u32) {
+        (1 << mode, mode + 1)
+    }
+
+    fn bc7_values(values: &[u32], bits: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        values.iter().map(move |&value| (value, bits))
+    }
+
+    // 锚点纹素的索引少一位
+    fn bc7_indices(indices: [u32; 16], bits: u32, anchors: &[usize]) -> Vec<(u32, u32)> {
+        (0..16)
+            .map(|texel| (indices[texel], bits - anchors.contains(&texel) as u32))
+            .collect()
+    }
+
+    #[test]
+    fn bc7_mode0() {
+        // 三个子集，分区 0 为 [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2]，锚点为纹素 0、3、15
+        // 4 位端点加上 p 位扩展到 8 位，s0: (247, 0, 0) -> 0，s1: (8, 255, 8)，s2: (8, 8, 255) -> 0
+        let mut fields = vec![bc7_mode(0), (0, 4)];
+        fields.extend(bc7_values(&[15, 0, 0, 0, 0, 0], 4));
+        fields.extend(bc7_values(&[0, 0, 15, 15, 0, 0], 4));
+        fields.extend(bc7_values(&[0, 0, 0, 0, 15, 0], 4));
+        fields.extend(bc7_values(&[0, 0, 1, 1, 1, 0], 1));
+        let indices = [0, 7, 0, 0, 3, 0, 0, 0, 0, 7, 4, 0, 0, 0, 0, 3];
+        fields.extend(bc7_indices(indices, 3, &[0, 3, 15]));
+        let s1 = [8, 255, 8, 255];
+        let s2 = [8, 8, 255, 255];
+        #[rustfmt::skip]
+        let expected = [
+            [247, 0, 0, 255], [0, 0, 0, 255], s1, s1,
+            [143, 0, 0, 255], [247, 0, 0, 255], s1, s1,
+            [247, 0, 0, 255], [0, 0, 0, 255], [3, 3, 108, 255], s1,
+            s2, s2, s2, [5, 5, 147, 255],
+        ];
+        assert_eq!(decode_bc7(&bc7_block(&fields)), expected);
+    }
+
+    #[test]
+    fn bc7_mode1() {
+        // 两个子集，分区 17 为 0x008E，子集 1 为纹素 1、2、3、7，锚点为纹素 2
+        // 6 位端点加上子集共享的 p 位，s0: (253, 0, 0) -> 0，s1: (2, 2, 255) -> (2, 255, 2)
+        let mut fields = vec![bc7_mode(1), (17, 6)];
+        fields.extend(bc7_values(&[63, 0, 0, 0], 6));
+        fields.extend(bc7_values(&[0, 0, 0, 63], 6));
+        fields.extend(bc7_values(&[0, 0, 63, 0], 6));
+        fields.extend(bc7_values(&[0, 1], 1));
+        let indices = [0, 7, 3, 0, 7, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        fields.extend(bc7_indices(indices, 3, &[0, 2]));
+        let s0 = [253, 0, 0, 255];
+        #[rustfmt::skip]
+        let expected = [
+            s0, [2, 255, 2, 255], [2, 109, 148, 255], [2, 2, 255, 255],
+            [0, 0, 0, 255], [107, 0, 0, 255], s0, [2, 2, 255, 255],
+            s0, s0, s0, s0,
+            s0, s0, s0, s0,
+        ];
+        assert_eq!(decode_bc7(&bc7_block(&fields)), expected);
+    }
+
+    #[test]
+    fn bc7_mode2() {
+        // 三个子集，分区 10 按行分为 0、1、2、2，锚点为纹素 0、6、15，5 位端点没有 p 位
+        // s0: (255, 0, 0) -> 0，s1: (0, 255, 0) -> (0, 132, 0)，s2: (0, 0, 255) -> 255
+        let mut fields = vec![bc7_mode(2), (10, 6)];
+        fields.extend(bc7_values(&[31, 0, 0, 0, 0, 31], 5));
+        fields.extend(bc7_values(&[0, 0, 31, 16, 0, 31], 5));
+        fields.extend(bc7_values(&[0, 0, 0, 0, 31, 31], 5));
+        let indices = [0, 1, 2, 3, 3, 2, 1, 0, 0, 1, 2, 3, 0, 0, 0, 1];
+        fields.extend(bc7_indices(indices, 2, &[0, 6, 15]));
+        #[rustfmt::skip]
+        let expected = [
+            [255, 0, 0, 255], [171, 0, 0, 255], [84, 0, 0, 255], [0, 0, 0, 255],
+            [0, 132, 0, 255], [0, 172, 0, 255], [0, 215, 0, 255], [0, 255, 0, 255],
+            [0, 0, 255, 255], [84, 84, 255, 255], [171, 171, 255, 255], [255, 255, 255, 255],
+            [0, 0, 255, 255], [0, 0, 255, 255], [0, 0, 255, 255], [84, 84, 255, 255],
+        ];
+        assert_eq!(decode_bc7(&bc7_block(&fields)), expected);
+    }
+
+    #[test]
+    fn bc7_mode3() {
+        // 两个子集，分区 0 为 0xCCCC，右边两列为子集 1，锚点为纹素 15
+        // 7 位端点加上 p 位，s0: (255, 1, 1) -> 0，s1: (0, 128, 0) -> (1, 1, 255)
+        let mut fields = vec![bc7_mode(3), (0, 6)];
+        fields.extend(bc7_values(&[127, 0, 0, 0], 7));
+        fields.extend(bc7_values(&[0, 0, 64, 0], 7));
+        fields.extend(bc7_values(&[0, 0, 0, 127], 7));
+        fields.extend(bc7_values(&[1, 0, 0, 1], 1));
+        let indices = [0, 1, 2, 3, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        fields.extend(bc7_indices(indices, 2, &[0, 15]));
+        let s0 = [255, 1, 1, 255];
+        let s1 = [0, 128, 0, 255];
+        #[rustfmt::skip]
+        let expected = [
+            s0, [171, 1, 1, 255], [1, 43, 171, 255], [1, 1, 255, 255],
+            s0, [0, 0, 0, 255], s1, s1,
+            s0, s0, s1, s1,
+            s0, s0, s1, [0, 86, 84, 255],
+        ];
+        assert_eq!(decode_bc7(&bc7_block(&fields)), expected);
+    }
+
+    // 模式 4 的端点为 (255, 0, 0, 0) -> (0, 255, 132, 255)
+    // 2 位索引依次为 0、3、1，3 位索引依次为 0、0、7、2，其余纹素索引为 0
+    fn bc7_mode4_block(rotation: u32, index_selection: u32) -> [u8; 16] {
+        let mut fields = vec![bc7_mode(4), (rotation, 2), (index_selection, 1)];
+        fields.extend(bc7_values(&[31, 0, 0, 31, 0, 16], 5));
+        fields.extend(bc7_values(&[0, 63], 6));
+        let mut indices = [0; 16];
+        indices[..3].copy_from_slice(&[0, 3, 1]);
+        fields.extend(bc7_indices(indices, 2, &[0]));
+        indices[..4].copy_from_slice(&[0, 0, 7, 2]);
+        fields.extend(bc7_indices(indices, 3, &[0]));
+        bc7_block(&fields)
+    }
+
+    #[test]
+    fn bc7_mode4() {
+        // 索引选择位为 0 时颜色使用 2 位索引，alpha 使用 3 位索引
+        let texels = decode_bc7(&bc7_mode4_block(0, 0));
+        assert_eq!(texels[0], [255, 0, 0, 0]);
+        assert_eq!(texels[1], [0, 255, 132, 0]);
+        assert_eq!(texels[2], [171, 84, 43, 255]);
+        assert_eq!(texels[3], [255, 0, 0, 72]);
+        assert_eq!(texels[4..], [[255, 0, 0, 0]; 12]);
+
+        // 索引选择位为 1 时交换，旋转 3 交换 b 和 alpha
+        let texels = decode_bc7(&bc7_mode4_block(3, 1));
+        assert_eq!(texels[0], [255, 0, 0, 0]);
+        assert_eq!(texels[1], [255, 0, 255, 0]);
+        assert_eq!(texels[2], [0, 255, 84, 132]);
+        assert_eq!(texels[3], [183, 72, 0, 37]);
+        assert_eq!(texels[4..], [[255, 0, 0, 0]; 12]);
+    }
+
+    #[test]
+    fn bc7_mode5() {
+        // 7 位颜色和 8 位 alpha，端点为 (255, 0, 0, 0) -> (0, 255, 255, 128)，旋转 1 交换 r 和 alpha
+        let mut fields = vec![bc7_mode(5), (1, 2)];
+        fields.extend(bc7_values(&[127, 0, 0, 127, 0, 127], 7));
+        fields.extend(bc7_values(&[0, 128], 8));
+        let mut indices = [0; 16];
+        indices[..3].copy_from_slice(&[0, 3, 1]);
+        fields.extend(bc7_indices(indices, 2, &[0]));
+        indices[..4].copy_from_slice(&[0, 0, 3, 2]);
+        fields.extend(bc7_indices(indices, 2, &[0]));
+        let texels = decode_bc7(&bc7_block(&fields));
+        assert_eq!(texels[0], [0, 0, 0, 255]);
+        assert_eq!(texels[1], [0, 255, 255, 0]);
+        assert_eq!(texels[2], [128, 84, 84, 171]);
+        assert_eq!(texels[3], [86, 0, 0, 255]);
+        assert_eq!(texels[4..], [[0, 0, 0, 255]; 12]);
+    }
+
+    #[test]
+    fn bc7_mode6() {
+        // 7 位端点加上 p 位，端点为 (254, 0, 0, 254) -> (1, 255, 129, 255)，4 位索引
+        let mut fields = vec![bc7_mode(6)];
+        fields.extend(bc7_values(&[127, 0, 0, 127, 0, 64, 127, 127], 7));
+        fields.extend(bc7_values(&[0, 1], 1));
+        let mut indices = [0; 16];
+        indices[..4].copy_from_slice(&[0, 15, 8, 4]);
+        fields.extend(bc7_indices(indices, 4, &[0]));
+        let texels = decode_bc7(&bc7_block(&fields));
+        assert_eq!(texels[0], [254, 0, 0, 254]);
+        assert_eq!(texels[1], [1, 255, 129, 255]);
+        assert_eq!(texels[2], [120, 135, 69, 255]);
+        assert_eq!(texels[3], [187, 68, 34, 254]);
+        assert_eq!(texels[4..], [[254, 0, 0, 254]; 12]);
+    }
+
+    #[test]
+    fn bc7_mode7() {
+        // 两个子集，分区 13 为 0xFF00，下面两行为子集 1，锚点为纹素 15
+        // 5 位端点加上 p 位，s0: (255, 4, 4, 255) -> 0，s1: (0, 251, 0, 130) -> (4, 4, 4, 4)
+        let mut fields = vec![bc7_mode(7), (13, 6)];
+        fields.extend(bc7_values(&[31, 0, 0, 0], 5));
+        fields.extend(bc7_values(&[0, 0, 31, 0], 5));
+        fields.extend(bc7_values(&[0, 0, 0, 0], 5));
+        fields.extend(bc7_values(&[31, 0, 16, 0], 5));
+        fields.extend(bc7_values(&[1, 0, 0, 1], 1));
+        let indices = [0, 3, 1, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 1];
+        fields.extend(bc7_indices(indices, 2, &[0, 15]));
+        let s0 = [255, 4, 4, 255];
+        let s1 = [0, 251, 0, 130];
+        #[rustfmt::skip]
+        let expected = [
+            s0, [0, 0, 0, 0], [171, 3, 3, 171], s0,
+            s0, s0, s0, s0,
+            [3, 85, 3, 45], s1, s1, s1,
+            s1, s1, s1, [1, 170, 1, 89],
+        ];
+        assert_eq!(decode_bc7(&bc7_block(&fields)), expected);
+    }
+
+    #[test]
+    fn bc7_reserved_mode() {
+        // 没有模式位的块解码为透明黑色
+        assert_eq!(decode_bc7(&[0; 16]), [[0; 4]; 16]);
+    }
+
+    // ETC2 的索引按列存储，纹素 (x, y) 索引的高位和低位分别在大端序的第 16 + x * 4 + y 和 x * 4 + y 位
+    fn etc2_block(header: [u8; 4], indices: [u32; 16]) -> [u8; 8] {
+        let mut bits = 0u32;
+        for (texel, index) in indices.into_iter().enumerate() {
+            let bit = (texel % 4) * 4 + texel / 4;
+            bits |= (index >> 1) << (16 + bit) | (index & 1) << bit;
+        }
+        let mut block = [0; 8];
+        block[..4].copy_from_slice(&header);
+        block[4..].copy_from_slice(&bits.to_be_bytes());
+        block
+    }
+
+    // 第 i 个纹素使用索引 i % 4
+    const ETC2_ROW_INDICES: [u32; 16] = [0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3];
+
+    #[test]
+    fn etc2_individual() {
+        // 两个 4 位的基础颜色 (136, 136, 136) 和 (0, 68, 255)，修改值表 0 和 7，flip 为 0 时左右两个子块
+        let mut indices = [0; 16];
+        indices[..4].copy_from_slice(&[0, 1, 2, 3]);
+        let block = etc2_block([0x80, 0x84, 0x8F, 7 << 2], indices);
+        let left = [138, 138, 138, 255];
+        let right = [47, 115, 255, 255];
+        #[rustfmt::skip]
+        let expected = [
+            left, [144, 144, 144, 255], [0, 21, 208, 255], [0, 0, 72, 255],
+            left, left, right, right,
+            left, left, right, right,
+            left, left, right, right,
+        ];
+        assert_eq!(decode_etc2(&block, false), expected);
+    }
+
+    // 差分模式的基础颜色为 (16, 8, 0) 和差值 (-4, 3, 2)，扩展到 8 位为 (132, 66, 0) 和 (99, 90, 16)
+    // 修改值表 1 和 2，flip 为 1 时上下两个子块
+    const ETC2_DIFFERENTIAL: [u8; 4] = [16 << 3 | 4, 8 << 3 | 3, 2, 1 << 5 | 2 << 2 | 0b11];
+
+    #[test]
+    fn etc2_differential() {
+        let block = etc2_block(ETC2_DIFFERENTIAL, ETC2_ROW_INDICES);
+        let top = [
+            [137, 71, 5, 255],
+            [149, 83, 17, 255],
+            [127, 61, 0, 255],
+            [115, 49, 0, 255],
+        ];
+        let bottom = [
+            [108, 99, 25, 255],
+            [128, 119, 45, 255],
+            [90, 81, 7, 255],
+            [70, 61, 0, 255],
+        ];
+        let expected = std::array::from_fn(|i| if i < 8 { top[i % 4] } else { bottom[i % 4] });
+        assert_eq!(decode_etc2(&block, false), expected);
+        // 不透明位为 1 的 Etc2Rgb8A1 和 Etc2Rgb8 相同
+        assert_eq!(decode_etc2(&block, true), expected);
+    }
+
+    // T 模式，r 溢出，颜色为 (102, 0, 255) 和 (136, 136, 136)，距离索引 3 为 16
+    const ETC2_T: [u8; 4] = [0x0E, 0x0F, 0x88, 0x87];
+
+    #[test]
+    fn etc2_t_mode() {
+        // 索引随行变化，检查按列存储的顺序
+        let indices = std::array::from_fn(|i| ((i % 4 + i / 4) % 4) as u32);
+        let block = etc2_block(ETC2_T, indices);
+        let colors = [
+            [102, 0, 255, 255],
+            [152, 152, 152, 255],
+            [136, 136, 136, 255],
+            [120, 120, 120, 255],
+        ];
+        let expected = std::array::from_fn(|i| colors[indices[i] as usize]);
+        assert_eq!(decode_etc2(&block, false), expected);
+    }
+
+    #[test]
+    fn etc2_h_mode() {
+        // H 模式，g 溢出，颜色为 (255, 0, 0) 和 (0, 255, 136)，c0 >= c1 时距离索引的最低位为 1，距离为 16
+        let block = etc2_block([0x78, 0x04, 0x07, 0xC3], ETC2_ROW_INDICES);
+        let row = [
+            [255, 16, 16, 255],
+            [239, 0, 0, 255],
+            [16, 255, 152, 255],
+            [0, 239, 120, 255],
+        ];
+        assert_eq!(
+            decode_etc2(&block, false),
+            std::array::from_fn(|i| row[i % 4])
+        );
+
+        // c0 为黑色，c0 < c1 时最低位为 0，距离索引 2 为 11
+        let block = etc2_block([0x00, 0x04, 0x07, 0xC3], ETC2_ROW_INDICES);
+        let row = [
+            [11, 11, 11, 255],
+            [0, 0, 0, 255],
+            [11, 255, 147, 255],
+            [0, 244, 125, 255],
+        ];
+        assert_eq!(
+            decode_etc2(&block, false),
+            std::array::from_fn(|i| row[i % 4])
+        );
+    }
+
+    #[test]
+    fn etc2_planar() {
+        // 平面模式，b 溢出，原点 (130, 0, 0)，水平方向 (255, 0, 0)，竖直方向 (0, 255, 255)
+        let block = [0x40, 0x00, 0x04, 0x7F, 0x00, 0x00, 0x1F, 0xFF];
+        let r = [
+            [130, 161, 193, 224],
+            [98, 129, 160, 191],
+            [65, 96, 128, 159],
+            [33, 64, 95, 126],
+        ];
+        let gb = [0, 64, 128, 191];
+        let expected = std::array::from_fn(|i| {
+            let (x, y) = (i % 4, i / 4);
+            [r[y][x], gb[y], gb[y], 255]
+        });
+        assert_eq!(decode_etc2(&block, false), expected);
+        assert_eq!(decode_etc2(&block, true), expected);
+    }
+
+    #[test]
+    fn etc2_punch_through_alpha() {
+        // 不透明位为 0 时索引 0 的修改值为 0，索引 2 为透明黑色
+        let mut header = ETC2_DIFFERENTIAL;
+        header[3] &= !2;
+        let block = etc2_block(header, ETC2_ROW_INDICES);
+        let top = [
+            [132, 66, 0, 255],
+            [149, 83, 17, 255],
+            [0; 4],
+            [115, 49, 0, 255],
+        ];
+        let bottom = [
+            [99, 90, 16, 255],
+            [128, 119, 45, 255],
+            [0; 4],
+            [70, 61, 0, 255],
+        ];
+        let expected = std::array::from_fn(|i| if i < 8 { top[i % 4] } else { bottom[i % 4] });
+        assert_eq!(
+            decode_block(TextureFormat::Etc2Rgb8A1Unorm, &block),
+            expected
+        );
+
+        // T 模式中索引 2 为透明黑色
+        let mut header = ETC2_T;
+        header[3] &= !2;
+        let block = etc2_block(header, ETC2_ROW_INDICES);
+        let row = [
+            [102, 0, 255, 255],
+            [152, 152, 152, 255],
+            [0; 4],
+            [120, 120, 120, 255],
+        ];
+        let texels = decode_block(TextureFormat::Etc2Rgb8A1Unorm, &block);
+        assert_eq!(texels, std::array::from_fn(|i| row[i % 4]));
+    }
+
+    #[test]
+    fn etc2_eac_alpha() {
+        // alpha 为 8 位，128 加上修改值表 13 的 [-1, -2, -3, -10, 0, 1, 2, 9] 乘以倍数 2
+        let alpha = eac_block(128, 2, 13, std::array::from_fn(|i| i as u64 % 8));
+        let block = [&alpha[..], &etc2_block(ETC2_DIFFERENTIAL, [0; 16])].concat();
+        let values = [126, 124, 122, 108, 128, 130, 132, 146];
+        let texels = decode_block(TextureFormat::Etc2Rgba8Unorm, &block);
+        for (i, texel) in texels.iter().enumerate() {
+            let base = if i < 8 { [137, 71, 5] } else { [108, 99, 25] };
+            assert_eq!(texel[..3], base);
+            assert_eq!(texel[3], values[i % 8], "texel {i}");
+        }
+    }
+
+    // EAC 的索引按列存储，纹素 (x, y) 的 3 位索引在 48 位中的第 45 - 3 * (x * 4 + y) 位
+    fn eac_block(base: u8, multiplier: u8, table: u8, indices: [u64; 16]) -> [u8; 8] {
+        let mut bits = 0u64;
+        for (texel, index) in indices.into_iter().enumerate() {
+            let bit = (texel % 4) * 4 + texel / 4;
+            bits |= index << (45 - 3 * bit);
+        }
+        let mut block = bits.to_be_bytes();
+        block[0] = base;
+        block[1] = multiplier << 4 | table;
+        block
+    }
+
+    #[test]
+    fn eac_r11() {
+        // 基础值 128 扩展为 128 * 8 + 4 = 1028，修改值表 13 为 [-1, -2, -3, -10, 0, 1, 2, 9]，乘以倍数 2 再乘以 8
+        let block = eac_block(128, 2, 13, std::array::from_fn(|i| i as u64 % 8));
+        let values = [1012, 996, 980, 868, 1028, 1044, 1060, 1172];
+        let texels = decode_block16(TextureFormat::EacR11Unorm, &block);
+        for (i, texel) in texels.iter().enumerate() {
+            let value: u16 = values[i % 8];
+            assert_eq!(
+                *texel,
+                [value << 5 | value >> 6, 0, 0, u16::MAX],
+                "texel {i}"
+            );
+        }
+        assert_eq!(texels[3][0], 27789);
+        assert_eq!(texels[7][0], 37522);
+    }
+
+    #[test]
+    fn eac_r11_zero_multiplier() {
+        // 倍数为 0 时修改值不乘以 8，255 * 8 + 4 = 2044，修改值表 0 为 [-3, -6, -9, -15, 2, 5, 8, 14]
+        // 2044 + 14 超出 11 位被截断为 2047
+        let mut indices = [7; 16];
+        indices[1] = 4;
+        indices[2] = 3;
+        let block = eac_block(255, 0, 0, indices);
+        let texels = decode_block16(TextureFormat::EacR11Unorm, &block);
+        assert_eq!(texels[0][0], u16::MAX);
+        // 2046
+        assert_eq!(texels[1][0], 65503);
+        // 2029
+        assert_eq!(texels[2][0], 64959);
+    }
+
+    #[test]
+    fn eac_rg11() {
+        let r = eac_block(128, 2, 13, [4; 16]);
+        let g = eac_block(255, 0, 0, [7; 16]);
+        let texels = decode_block16(TextureFormat::EacRg11Unorm, &[r, g].concat());
+        assert_eq!(texels, [[32912, u16::MAX, 0, u16::MAX]; 16]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::{AddressSpace, ShaderStruct};
+
+// WGSL 的原子操作只保证 relaxed 的内存顺序
+const ORDERING: Ordering = Ordering::Relaxed;
+
+// 原子比较交换的结果，对应 WGSL 的 __atomic_compare_exchange_result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtomicCompareExchangeResult {
+    pub old_value: u32,
+    pub exchanged: bool,
+}
+
+// 存储缓冲，着色器可以读写，克隆得到的缓冲和原缓冲共享同一块数据
+// 数据按照 u32 存储，这样可以直接对每个 4 字节的字进行原子操作
+// 所有的偏移都是以字节为单位，原子操作的偏移需要 4 字节对齐
+#[derive(Clone, Default)]
+pub struct Buffer {
+    words: Arc<[AtomicU32]>,
+}
+impl Buffer {
+    // 创建 size 字节大小的缓冲，内容为 0，大小会向上对齐到 4 字节
+    pub fn new(size: usize) -> Self {
+        Self {
+            words: (0..size.div_ceil(4)).map(|_| AtomicU32::new(0)).collect(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let buffer = Self::new(bytes.len());
+        buffer.write_bytes(0, bytes);
+        buffer
+    }
+
+    // 按照 WGSL storage 的内存布局初始化
+    pub fn from_struct<T: ShaderStruct>(value: &T) -> Self {
+        Self::from_bytes(&value.encode(AddressSpace::Storage))
+    }
+
+    pub fn size(&self) -> usize {
+        self.words.len() * 4
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.read_bytes(0, self.size())
+    }
+
+    pub fn read_bytes(&self, offset: usize, len: usize) -> Vec<u8> {
+        (offset..offset + len)
+            .map(|i| self.words[i / 4].load(ORDERING).to_le_bytes()[i % 4])
+            .collect()
+    }
+
+    // 不是整个字的写入需要保留字中的其他字节
+    pub fn write_bytes(&self, offset: usize, data: &[u8]) {
+        let mut i = 0;
+        while i < data.len() {
+            let address = offset + i;
+            let word = &self.words[address / 4];
+            if address.is_multiple_of(4) && data.len() - i >= 4 {
+                word.store(
+                    u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]),
+                    ORDERING,
+                );
+                i += 4;
+            } else {
+                let byte = data[i];
+                let _ = word.fetch_update(ORDERING, ORDERING, |value| {
+                    let mut bytes = value.to_le_bytes();
+                    bytes[address % 4] = byte;
+                    Some(u32::from_le_bytes(bytes))
+                });
+                i += 1;
+            }
+        }
+    }
+
+    // 所有内容置为 0
+    pub fn clear(&self) {
+        self.words.iter().for_each(|word| word.store(0, ORDERING));
+    }
+
+    // 按照 WGSL storage 的内存布局读写结构体
+    pub fn read<T: ShaderStruct>(&self, offset: usize) -> T {
+        T::decode(
+            &self.read_bytes(offset, T::size(AddressSpace::Storage)),
+            AddressSpace::Storage,
+        )
+    }
+
+    pub fn write<T: ShaderStruct>(&self, offset: usize, value: &T) {
+        self.write_bytes(offset, &value.encode(AddressSpace::Storage));
+    }
+
+    fn word(&self, offset: usize) -> &AtomicU32 {
+        assert!(
+            offset.is_multiple_of(4),
+            "atomic offset {offset} is not aligned to 4 bytes"
+        );
+        &self.words[offset / 4]
+    }
+
+    pub fn atomic_load(&self, offset: usize) -> u32 {
+        self.word(offset).load(ORDERING)
+    }
+
+    pub fn atomic_store(&self, offset: usize, value: u32) {
+        self.word(offset).store(value, ORDERING)
+    }
+
+    // 下面的原子操作都返回修改之前的值，和 WGSL 一致
+    pub fn atomic_add(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).fetch_add(value, ORDERING)
+    }
+
+    pub fn atomic_sub(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).fetch_sub(value, ORDERING)
+    }
+
+    pub fn atomic_min(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).fetch_min(value, ORDERING)
+    }
+
+    pub fn atomic_max(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).fetch_max(value, ORDERING)
+    }
+
+    // atomic<i32> 的最小最大值需要按照有符号数比较
+    pub fn atomic_min_i32(&self, offset: usize, value: i32) -> i32 {
+        self.atomic_update(offset, |old| (old as i32).min(value) as u32) as i32
+    }
+
+    pub fn atomic_max_i32(&self, offset: usize, value: i32) -> i32 {
+        self.atomic_update(offset, |old| (old as i32).max(value) as u32) as i32
+    }
+
+    pub fn atomic_and(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).fetch_and(value, ORDERING)
+    }
+
+    pub fn atomic_or(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).fetch_or(value, ORDERING)
+    }
+
+    pub fn atomic_xor(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).fetch_xor(value, ORDERING)
+    }
+
+    pub fn atomic_exchange(&self, offset: usize, value: u32) -> u32 {
+        self.word(offset).swap(value, ORDERING)
+    }
+
+    pub fn atomic_compare_exchange_weak(
+        &self,
+        offset: usize,
+        compare: u32,
+        value: u32,
+    ) -> AtomicCompareExchangeResult {
+        match self
+            .word(offset)
+            .compare_exchange_weak(compare, value, ORDERING, ORDERING)
+        {
+            Ok(old_value) => AtomicCompareExchangeResult {
+                old_value,
+                exchanged: true,
+            },
+            Err(old_value) => AtomicCompareExchangeResult {
+                old_value,
+                exchanged: false,
+            },
+        }
+    }
+
+    fn atomic_update(&self, offset: usize, f: impl Fn(u32) -> u32) -> u32 {
+        match self
+            .word(offset)
+            .fetch_update(ORDERING, ORDERING, |old| Some(f(old)))
+        {
+            Ok(old) | Err(old) => old,
+        }
+    }
+}
+impl fmt::Debug for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Buffer")
+            .field("size", &self.size())
+            .finish()
+    }
+}
@@ -0,0 +1,517 @@
+use std::fmt;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+
+use math::UVec3;
+
+use crate::bind_group::{BindGroup, BindGroups, BindingError, PipelineLayout};
+use crate::buffer::Buffer;
+use crate::indirect::{read_indirect_args, DispatchIndirectArgs, IndirectError};
+use crate::query::{ActiveQuery, PipelineStatistics, QuerySet, QueryType};
+use crate::shader::{ComputeInput, ComputeShader};
+
+// 和 wgpu 默认的 Limits 一致
+pub const MAX_COMPUTE_INVOCATIONS_PER_WORKGROUP: u32 = 256;
+pub const MAX_COMPUTE_WORKGROUP_STORAGE_SIZE: usize = 16384;
+pub const MAX_COMPUTE_WORKGROUPS_PER_DIMENSION: u32 = 65535;
+
+// 调度时的错误，对应 wgpu 中调度命令的校验错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DispatchError {
+    // 绑定组没有设置或者和管线布局不匹配
+    Binding(BindingError),
+    // 间接参数的偏移没有对齐或者超出了缓冲的范围
+    Indirect(IndirectError),
+    // 某一维的工作组数量超过了 MAX_COMPUTE_WORKGROUPS_PER_DIMENSION
+    TooManyWorkgroups { count: [u32; 3] },
+}
+impl From<BindingError> for DispatchError {
+    fn from(value: BindingError) -> Self {
+        DispatchError::Binding(value)
+    }
+}
+impl From<IndirectError> for DispatchError {
+    fn from(value: IndirectError) -> Self {
+        DispatchError::Indirect(value)
+    }
+}
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatchError::Binding(error) => write!(f, "{error}"),
+            DispatchError::Indirect(error) => write!(f, "{error}"),
+            DispatchError::TooManyWorkgroups { count } => write!(
+                f,
+                "workgroup count {count:?} exceeds {MAX_COMPUTE_WORKGROUPS_PER_DIMENSION} per dimension"
+            ),
+        }
+    }
+}
+impl std::error::Error for DispatchError {}
+
+// 一个工作组中所有调用共享的状态，对应 WGSL 的 var<workgroup> 和 workgroupBarrier()
+pub struct Workgroup {
+    // 工作组共享内存，每个工作组开始执行时都会清零
+    pub memory: Buffer,
+    barrier: WorkgroupBarrier,
+}
+impl Workgroup {
+    fn new(memory_size: usize, invocations: u32) -> Self {
+        Self {
+            memory: Buffer::new(memory_size),
+            barrier: WorkgroupBarrier::new(invocations as usize),
+        }
+    }
+
+    // 等待工作组中的所有调用都执行到这里
+    // 和 WGSL 一样，需要在 uniform control flow 中调用，否则会死锁
+    pub fn barrier(&self) {
+        self.barrier.wait();
+    }
+}
+
+// 可以被毒化的屏障，有调用 panic 之后屏障不再等待，其他调用不会一直阻塞在屏障上
+struct WorkgroupBarrier {
+    count: usize,
+    // 已经到达的调用数量、当前的轮次以及是否被毒化
+    state: Mutex<(usize, u64, bool)>,
+    condvar: Condvar,
+}
+impl WorkgroupBarrier {
+    fn new(count: usize) -> Self {
+        Self {
+            count,
+            state: Mutex::new((0, 0, false)),
+            condvar: Condvar::new(),
+        }
+    }
+
+    fn wait(&self) {
+        let mut state = self.state.lock().unwrap();
+        let (arrived, generation, poisoned) = &mut *state;
+        if *poisoned {
+            return;
+        }
+        *arrived += 1;
+        if *arrived == self.count {
+            *arrived = 0;
+            *generation += 1;
+            self.condvar.notify_all();
+            return;
+        }
+        let generation = *generation;
+        let _state = self
+            .condvar
+            .wait_while(state, |(_, current, poisoned)| {
+                *current == generation && !*poisoned
+            })
+            .unwrap();
+    }
+
+    fn poison(&self) {
+        self.state.lock().unwrap().2 = true;
+        self.condvar.notify_all();
+    }
+
+    fn is_poisoned(&self) -> bool {
+        self.state.lock().unwrap().2
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// 常驻的工作线程，每个线程执行一个调用，多次调度复用同一组线程
+struct WorkerPool {
+    workers: Vec<(mpsc::Sender<Job>, thread::JoinHandle<()>)>,
+}
+impl WorkerPool {
+    fn new(size: usize) -> Self {
+        let workers = (0..size)
+            .map(|_| {
+                let (sender, receiver) = mpsc::channel::<Job>();
+                let handle = thread::spawn(move || {
+                    while let Ok(job) = receiver.recv() {
+                        job();
+                    }
+                });
+                (sender, handle)
+            })
+            .collect();
+        Self { workers }
+    }
+
+    // 每个任务在一个单独的工作线程上执行，所有任务结束之后才返回
+    // 任务中的 panic 在所有任务结束之后重新抛出
+    fn run<'s>(&self, jobs: Vec<Box<dyn FnOnce() + Send + 's>>) {
+        assert!(jobs.len() <= self.workers.len());
+        let job_count = jobs.len();
+        let (done_sender, done_receiver) = mpsc::channel();
+        let mut sent = 0;
+        for ((sender, _), job) in self.workers.iter().zip(jobs) {
+            // SAFETY: 返回之前会等待所有已经发送的任务执行完成，任务借用的数据在执行期间一直有效
+            let job = unsafe { mem::transmute::<Box<dyn FnOnce() + Send + 's>, Job>(job) };
+            let done_sender = done_sender.clone();
+            let job: Job = Box::new(move || {
+                let _ = done_sender.send(panic::catch_unwind(AssertUnwindSafe(job)));
+            });
+            if sender.send(job).is_err() {
+                break;
+            }
+            sent += 1;
+        }
+        let results: Vec<thread::Result<()>> = done_receiver.iter().take(sent).collect();
+        assert_eq!(sent, job_count, "compute worker thread exited");
+        if let Some(Err(payload)) = results.into_iter().find(Result::is_err) {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        for (sender, handle) in self.workers.drain(..) {
+            drop(sender);
+            let _ = handle.join();
+        }
+    }
+}
+
+pub struct ComputePipelineDescriptor<'a> {
+    // 管线布局，为 None 时不检查绑定组
+    pub layout: Option<&'a PipelineLayout>,
+    pub shader: &'a dyn ComputeShader,
+    // 对应 WGSL 的 @workgroup_size(x, y, z)
+    pub workgroup_size: [u32; 3],
+    // var<workgroup> 需要的字节数
+    pub workgroup_memory_size: usize,
+}
+
+pub struct ComputePipeline<'a> {
+    pub state: ComputePipelineDescriptor<'a>,
+    pub bind_groups: BindGroups,
+    // 计算着色器的执行次数记录在 compute_shader_invocations 中
+    pub statistics: PipelineStatistics,
+    statistics_query: Option<ActiveQuery<'a>>,
+    // 第一次调度时创建，之后的调度复用
+    workers: Option<WorkerPool>,
+}
+impl<'a> ComputePipeline<'a> {
+    pub fn new(desc: ComputePipelineDescriptor<'a>) -> Self {
+        let invocations = desc
+            .workgroup_size
+            .iter()
+            .try_fold(1u32, |acc, &size| acc.checked_mul(size));
+        assert!(
+            matches!(invocations, Some(1..=MAX_COMPUTE_INVOCATIONS_PER_WORKGROUP)),
+            "workgroup size {:?} must have between 1 and {MAX_COMPUTE_INVOCATIONS_PER_WORKGROUP} invocations",
+            desc.workgroup_size
+        );
+        assert!(
+            desc.workgroup_memory_size <= MAX_COMPUTE_WORKGROUP_STORAGE_SIZE,
+            "workgroup memory size {} exceeds {MAX_COMPUTE_WORKGROUP_STORAGE_SIZE} bytes",
+            desc.workgroup_memory_size
+        );
+        Self {
+            state: desc,
+            bind_groups: BindGroups::default(),
+            statistics: PipelineStatistics::default(),
+            statistics_query: None,
+            workers: None,
+        }
+    }
+
+    pub fn set_bind_group(
+        &mut self,
+        index: usize,
+        group: BindGroup,
+        offsets: &[u32],
+    ) -> Result<(), BindingError> {
+        self.bind_groups
+            .set_validated(self.state.layout, index, group, offsets)
+    }
+
+    pub fn begin_pipeline_statistics_query(&mut self, query_set: &'a QuerySet, query_index: u32) {
+        assert!(
+            matches!(query_set.ty, QueryType::PipelineStatistics(_)),
+            "query set is not a pipeline statistics query set"
+        );
+        assert!(
+            self.statistics_query.is_none(),
+            "a pipeline statistics query is already active"
+        );
+        self.statistics_query = Some(ActiveQuery::begin(query_set, query_index, self.statistics));
+    }
+
+    pub fn end_pipeline_statistics_query(&mut self) {
+        self.statistics_query
+            .take()
+            .expect("no pipeline statistics query is active")
+            .end(&self.statistics);
+    }
+
+    // 工作组之间没有执行顺序的保证，按照线程数量把工作组分给多个线程组执行
+    // 每个线程组的线程数等于工作组的调用数，这样屏障可以让同一个工作组的调用同步
+    // 线程组的数量让总线程数接近 CPU 核数，工作组较大时只用一个线程组
+    pub fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) -> Result<(), DispatchError> {
+        if let Some(layout) = self.state.layout {
+            layout.validate_bind_groups(&self.bind_groups)?;
+        }
+        if [x, y, z]
+            .iter()
+            .any(|&n| n > MAX_COMPUTE_WORKGROUPS_PER_DIMENSION)
+        {
+            return Err(DispatchError::TooManyWorkgroups { count: [x, y, z] });
+        }
+        // 每一维都不超过 65535，工作组总数在 u64 中不会溢出
+        let workgroup_count = x as u64 * y as u64 * z as u64;
+        if workgroup_count == 0 {
+            return Ok(());
+        }
+        let num_workgroups = UVec3::new(x, y, z);
+        let [size_x, size_y, size_z] = self.state.workgroup_size;
+        let invocations = size_x * size_y * size_z;
+        let max_teams = thread::available_parallelism()
+            .map_or(1, |n| n.get() as u32 / invocations)
+            .max(1);
+        let workers = self
+            .workers
+            .get_or_insert_with(|| WorkerPool::new((max_teams * invocations) as usize));
+        let teams = (max_teams as u64).min(workgroup_count);
+
+        // 每个线程组的工作组状态和当前正在执行的工作组
+        let teams: Vec<(Workgroup, AtomicU64)> = (0..teams)
+            .map(|_| {
+                (
+                    Workgroup::new(self.state.workgroup_memory_size, invocations),
+                    AtomicU64::new(0),
+                )
+            })
+            .collect();
+        // 下一个待执行的工作组
+        let next_workgroup = &AtomicU64::new(0);
+        let (shader, bind_groups) = (self.state.shader, &self.bind_groups);
+        let mut jobs: Vec<Box<dyn FnOnce() + Send + '_>> = Vec::new();
+        for (workgroup, current_workgroup) in &teams {
+            for local_invocation_index in 0..invocations {
+                jobs.push(Box::new(move || loop {
+                    if local_invocation_index == 0 {
+                        workgroup.memory.clear();
+                        current_workgroup.store(
+                            next_workgroup.fetch_add(1, Ordering::Relaxed),
+                            Ordering::Relaxed,
+                        );
+                    }
+                    workgroup.barrier();
+                    let index = current_workgroup.load(Ordering::Relaxed);
+                    // 有调用 panic 之后屏障不再同步，线程组中的调用都在这里退出
+                    if index >= workgroup_count || workgroup.barrier.is_poisoned() {
+                        break;
+                    }
+                    let (x, y) = (x as u64, y as u64);
+                    let workgroup_id = UVec3::new(
+                        (index % x) as u32,
+                        (index / x % y) as u32,
+                        (index / (x * y)) as u32,
+                    );
+                    let local_invocation_id = UVec3::new(
+                        local_invocation_index % size_x,
+                        local_invocation_index / size_x % size_y,
+                        local_invocation_index / (size_x * size_y),
+                    );
+                    let input = ComputeInput {
+                        global_invocation_id: UVec3::new(
+                            workgroup_id.x * size_x + local_invocation_id.x,
+                            workgroup_id.y * size_y + local_invocation_id.y,
+                            workgroup_id.z * size_z + local_invocation_id.z,
+                        ),
+                        local_invocation_id,
+                        local_invocation_index,
+                        workgroup_id,
+                        num_workgroups,
+                    };
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        shader.main(input, bind_groups, workgroup)
+                    }));
+                    if let Err(payload) = result {
+                        // 毒化屏障释放同一个工作组中等待的调用，其他线程组不再领取新的工作组
+                        next_workgroup.fetch_max(workgroup_count, Ordering::Relaxed);
+                        workgroup.barrier.poison();
+                        panic::resume_unwind(payload);
+                    }
+                    // 等待工作组所有调用执行完之后再开始下一个工作组
+                    workgroup.barrier();
+                }));
+            }
+        }
+        // 着色器的 panic 在所有调用结束之后重新抛出
+        workers.run(jobs);
+        self.statistics.compute_shader_invocations += workgroup_count * invocations as u64;
+        Ok(())
+    }
+
+    // 间接调度，工作组数量按照 DispatchIndirectArgs 的布局从缓冲中读取
+    pub fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: usize,
+    ) -> Result<(), DispatchError> {
+        let args: DispatchIndirectArgs =
+            read_indirect_args(indirect_buffer, indirect_offset, 1)?[0];
+        self.dispatch_workgroups(args.x, args.y, args.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+    use crate::bind_group::ShaderStages;
+
+    fn pipeline<'a>(
+        shader: &'a dyn ComputeShader,
+        workgroup_size: [u32; 3],
+        workgroup_memory_size: usize,
+        output: &Buffer,
+    ) -> ComputePipeline<'a> {
+        let mut pipeline = ComputePipeline::new(ComputePipelineDescriptor {
+            layout: None,
+            shader,
+            workgroup_size,
+            workgroup_memory_size,
+        });
+        pipeline
+            .set_bind_group(0, vec![output.clone().into()], &[])
+            .unwrap();
+        pipeline
+    }
+
+    // 每个工作组先把 local_invocation_index + 1 累加到共享内存中，再通过屏障做树形规约
+    // 共享内存没有在工作组之间清零时，规约的结果会包含上一个工作组的值
+    fn reduce_main(input: ComputeInput, bind_groups: &BindGroups, workgroup: &Workgroup) {
+        let output = bind_groups.storage(ShaderStages::COMPUTE, 0, 0).unwrap();
+        let local_index = input.local_invocation_index as usize;
+        workgroup
+            .memory
+            .atomic_add(local_index * 4, input.local_invocation_index + 1);
+        workgroup.barrier();
+        let mut stride = 32;
+        while stride > 0 {
+            if local_index < stride {
+                let other = workgroup.memory.atomic_load((local_index + stride) * 4);
+                workgroup.memory.atomic_add(local_index * 4, other);
+            }
+            workgroup.barrier();
+            stride /= 2;
+        }
+        if local_index == 0 {
+            let workgroup_index = input.workgroup_id.x as usize;
+            output.atomic_store(workgroup_index * 4, workgroup.memory.atomic_load(0));
+        }
+    }
+
+    #[test]
+    fn workgroup_reduction() {
+        let output = Buffer::new(16 * 4);
+        let mut pipeline = pipeline(&reduce_main, [64, 1, 1], 64 * 4, &output);
+        pipeline.dispatch_workgroups(16, 1, 1).unwrap();
+        for i in 0..16 {
+            assert_eq!(output.atomic_load(i * 4), 64 * 65 / 2, "workgroup {i}");
+        }
+    }
+
+    // 屏障之后每个调用读取相邻调用在屏障之前写入的值
+    fn rotate_main(input: ComputeInput, bind_groups: &BindGroups, workgroup: &Workgroup) {
+        let output = bind_groups.storage(ShaderStages::COMPUTE, 0, 0).unwrap();
+        let local_index = input.local_invocation_index as usize;
+        let global_index = input.global_invocation_id.x;
+        workgroup.memory.atomic_store(local_index * 4, global_index);
+        workgroup.barrier();
+        let neighbor = workgroup.memory.atomic_load((local_index + 1) % 16 * 4);
+        output.atomic_store(global_index as usize * 4, neighbor);
+    }
+
+    #[test]
+    fn barrier_makes_workgroup_memory_visible() {
+        let output = Buffer::new(8 * 16 * 4);
+        let mut pipeline = pipeline(&rotate_main, [16, 1, 1], 16 * 4, &output);
+        pipeline.dispatch_workgroups(8, 1, 1).unwrap();
+        for global_index in 0..8 * 16 {
+            let expected = global_index / 16 * 16 + (global_index + 1) % 16;
+            assert_eq!(output.atomic_load(global_index as usize * 4), expected);
+        }
+    }
+
+    // 每个调用把计数器加一，并在自己的 global_invocation_id 对应的位置加一
+    fn count_main(input: ComputeInput, bind_groups: &BindGroups, _workgroup: &Workgroup) {
+        let output = bind_groups.storage(ShaderStages::COMPUTE, 0, 0).unwrap();
+        output.atomic_add(0, 1);
+        let id = input.global_invocation_id;
+        let (size_x, size_y) = (input.num_workgroups.x * 4, input.num_workgroups.y * 2);
+        let index = (id.z * size_y + id.y) * size_x + id.x;
+        output.atomic_add(4 + index as usize * 4, 1);
+    }
+
+    #[test]
+    fn atomic_counter_counts_every_invocation() {
+        let invocations = 7 * 3 * 2 * 16;
+        let output = Buffer::new(4 + invocations * 4);
+        let mut pipeline = pipeline(&count_main, [4, 2, 2], 0, &output);
+        // 第二次调度复用第一次创建的工作线程
+        for dispatch in 1..=2 {
+            pipeline.dispatch_workgroups(7, 3, 2).unwrap();
+            assert_eq!(output.atomic_load(0), (dispatch * invocations) as u32);
+            for i in 0..invocations {
+                assert_eq!(output.atomic_load(4 + i * 4), dispatch as u32);
+            }
+            assert_eq!(
+                pipeline.statistics.compute_shader_invocations,
+                (dispatch * invocations) as u64
+            );
+        }
+    }
+
+    #[test]
+    fn too_many_workgroups() {
+        let output = Buffer::new(4);
+        let mut pipeline = pipeline(&count_main, [4, 2, 2], 0, &output);
+        assert_eq!(
+            pipeline.dispatch_workgroups(1, MAX_COMPUTE_WORKGROUPS_PER_DIMENSION + 1, 1),
+            Err(DispatchError::TooManyWorkgroups {
+                count: [1, 65536, 1]
+            })
+        );
+        assert_eq!(output.atomic_load(0), 0);
+        assert_eq!(pipeline.statistics.compute_shader_invocations, 0);
+    }
+
+    #[test]
+    fn shader_panic_releases_barrier() {
+        let should_panic = AtomicBool::new(true);
+        // 第二个工作组的第一个调用在屏障之前 panic，其他调用不能一直等在屏障上
+        let shader = |input: ComputeInput, bind_groups: &BindGroups, workgroup: &Workgroup| {
+            if input.workgroup_id.x == 1
+                && input.local_invocation_index == 0
+                && should_panic.swap(false, Ordering::Relaxed)
+            {
+                panic!("shader panic");
+            }
+            workgroup.barrier();
+            count_main(input, bind_groups, workgroup);
+        };
+        let output = Buffer::new(4 + 4 * 16 * 4);
+        let mut pipeline = pipeline(&shader, [4, 2, 2], 0, &output);
+        let result =
+            panic::catch_unwind(AssertUnwindSafe(|| pipeline.dispatch_workgroups(4, 1, 1)));
+        let payload = result.unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"shader panic"));
+        assert_eq!(pipeline.statistics.compute_shader_invocations, 0);
+
+        // panic 之后仍然可以继续调度
+        output.clear();
+        pipeline.dispatch_workgroups(4, 1, 1).unwrap();
+        assert_eq!(output.atomic_load(0), 4 * 16);
+    }
+}
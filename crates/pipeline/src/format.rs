@@ -1,47 +1,935 @@
+use half::f16;
+use math::{IVec2, IVec3, IVec4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+use crate::block_compression;
+use crate::{ShaderType, TextureSampleType};
+
+// 顶点格式，按照webgpu标准实施
+/// Corresponds to [WebGPU `GPUVertexFormat`](
+/// https://gpuweb.github.io/gpuweb/#enumdef-gpuvertexformat).
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 pub enum VertexFormat {
-    // One single-precision float (f32). `float` in shaders.
+    /// One unsigned byte (u8). `u32` in shaders.
+    Uint8,
+    /// Two unsigned bytes (u8). `vec2<u32>` in shaders.
+    Uint8x2,
+    /// Four unsigned bytes (u8). `vec4<u32>` in shaders.
+    Uint8x4,
+    /// One signed byte (i8). `i32` in shaders.
+    Sint8,
+    /// Two signed bytes (i8). `vec2<i32>` in shaders.
+    Sint8x2,
+    /// Four signed bytes (i8). `vec4<i32>` in shaders.
+    Sint8x4,
+    /// One unsigned byte (u8). [0, 255] converted to float [0, 1] `f32` in shaders.
+    Unorm8,
+    /// Two unsigned bytes (u8). [0, 255] converted to float [0, 1] `vec2<f32>` in shaders.
+    Unorm8x2,
+    /// Four unsigned bytes (u8). [0, 255] converted to float [0, 1] `vec4<f32>` in shaders.
+    Unorm8x4,
+    /// One signed byte (i8). [-127, 127] converted to float [-1, 1] `f32` in shaders.
+    Snorm8,
+    /// Two signed bytes (i8). [-127, 127] converted to float [-1, 1] `vec2<f32>` in shaders.
+    Snorm8x2,
+    /// Four signed bytes (i8). [-127, 127] converted to float [-1, 1] `vec4<f32>` in shaders.
+    Snorm8x4,
+    /// One unsigned short (u16). `u32` in shaders.
+    Uint16,
+    /// Two unsigned shorts (u16). `vec2<u32>` in shaders.
+    Uint16x2,
+    /// Four unsigned shorts (u16). `vec4<u32>` in shaders.
+    Uint16x4,
+    /// One signed short (i16). `i32` in shaders.
+    Sint16,
+    /// Two signed shorts (i16). `vec2<i32>` in shaders.
+    Sint16x2,
+    /// Four signed shorts (i16). `vec4<i32>` in shaders.
+    Sint16x4,
+    /// One unsigned short (u16). [0, 65535] converted to float [0, 1] `f32` in shaders.
+    Unorm16,
+    /// Two unsigned shorts (u16). [0, 65535] converted to float [0, 1] `vec2<f32>` in shaders.
+    Unorm16x2,
+    /// Four unsigned shorts (u16). [0, 65535] converted to float [0, 1] `vec4<f32>` in shaders.
+    Unorm16x4,
+    /// One signed short (i16). [-32767, 32767] converted to float [-1, 1] `f32` in shaders.
+    Snorm16,
+    /// Two signed shorts (i16). [-32767, 32767] converted to float [-1, 1] `vec2<f32>` in shaders.
+    Snorm16x2,
+    /// Four signed shorts (i16). [-32767, 32767] converted to float [-1, 1] `vec4<f32>` in shaders.
+    Snorm16x4,
+    /// One half-precision float (no Rust equiv). `f32` in shaders.
+    Float16,
+    /// Two half-precision floats (no Rust equiv). `vec2<f32>` in shaders.
+    Float16x2,
+    /// Four half-precision floats (no Rust equiv). `vec4<f32>` in shaders.
+    Float16x4,
+    /// One single-precision float (f32). `f32` in shaders.
     Float32,
-    /// Two single-precision floats (f32). `vec2` in shaders.
+    /// Two single-precision floats (f32). `vec2<f32>` in shaders.
     Float32x2,
-    // Three single-precision floats (f32). `vec3` in shaders.
+    /// Three single-precision floats (f32). `vec3<f32>` in shaders.
     Float32x3,
-    // Four single-precision floats (f32). `vec4` in shaders.
+    /// Four single-precision floats (f32). `vec4<f32>` in shaders.
     Float32x4,
-    /// One unsigned int (u32). `uint` in shaders.
+    /// One unsigned int (u32). `u32` in shaders.
     Uint32,
+    /// Two unsigned ints (u32). `vec2<u32>` in shaders.
+    Uint32x2,
+    /// Three unsigned ints (u32). `vec3<u32>` in shaders.
+    Uint32x3,
+    /// Four unsigned ints (u32). `vec4<u32>` in shaders.
+    Uint32x4,
+    /// One signed int (i32). `i32` in shaders.
+    Sint32,
+    /// Two signed ints (i32). `vec2<i32>` in shaders.
+    Sint32x2,
+    /// Three signed ints (i32). `vec3<i32>` in shaders.
+    Sint32x3,
+    /// Four signed ints (i32). `vec4<i32>` in shaders.
+    Sint32x4,
+    /// Three unsigned 10-bit integers and one 2-bit integer, packed into a 32-bit integer (u32). [0, 1023] ([0, 3] for alpha) converted to float [0, 1] `vec4<f32>` in shaders.
+    Unorm10_10_10_2,
+    /// Four unsigned bytes (u8) in BGRA order. [0, 255] converted to float [0, 1] `vec4<f32>` in shaders.
+    Unorm8x4Bgra,
 }
 
 impl VertexFormat {
     #[inline]
     pub fn size(&self) -> usize {
         match self {
-            VertexFormat::Float32 => 4,
-            VertexFormat::Float32x2 => 8,
-            VertexFormat::Float32x3 => 12,
-            VertexFormat::Float32x4 => 16,
-            _ => 0,
+            VertexFormat::Uint8
+            | VertexFormat::Sint8
+            | VertexFormat::Unorm8
+            | VertexFormat::Snorm8 => 1,
+            VertexFormat::Uint8x2
+            | VertexFormat::Sint8x2
+            | VertexFormat::Unorm8x2
+            | VertexFormat::Snorm8x2
+            | VertexFormat::Uint16
+            | VertexFormat::Sint16
+            | VertexFormat::Unorm16
+            | VertexFormat::Snorm16
+            | VertexFormat::Float16 => 2,
+            VertexFormat::Uint8x4
+            | VertexFormat::Sint8x4
+            | VertexFormat::Unorm8x4
+            | VertexFormat::Snorm8x4
+            | VertexFormat::Uint16x2
+            | VertexFormat::Sint16x2
+            | VertexFormat::Unorm16x2
+            | VertexFormat::Snorm16x2
+            | VertexFormat::Float16x2
+            | VertexFormat::Float32
+            | VertexFormat::Uint32
+            | VertexFormat::Sint32
+            | VertexFormat::Unorm10_10_10_2
+            | VertexFormat::Unorm8x4Bgra => 4,
+            VertexFormat::Uint16x4
+            | VertexFormat::Sint16x4
+            | VertexFormat::Unorm16x4
+            | VertexFormat::Snorm16x4
+            | VertexFormat::Float16x4
+            | VertexFormat::Float32x2
+            | VertexFormat::Uint32x2
+            | VertexFormat::Sint32x2 => 8,
+            VertexFormat::Float32x3 | VertexFormat::Uint32x3 | VertexFormat::Sint32x3 => 12,
+            VertexFormat::Float32x4 | VertexFormat::Uint32x4 | VertexFormat::Sint32x4 => 16,
+        }
+    }
+
+    // 着色器中对应的分量个数
+    pub fn components(&self) -> usize {
+        match self {
+            VertexFormat::Uint8
+            | VertexFormat::Sint8
+            | VertexFormat::Unorm8
+            | VertexFormat::Snorm8
+            | VertexFormat::Uint16
+            | VertexFormat::Sint16
+            | VertexFormat::Unorm16
+            | VertexFormat::Snorm16
+            | VertexFormat::Float16
+            | VertexFormat::Float32
+            | VertexFormat::Uint32
+            | VertexFormat::Sint32 => 1,
+            VertexFormat::Uint8x2
+            | VertexFormat::Sint8x2
+            | VertexFormat::Unorm8x2
+            | VertexFormat::Snorm8x2
+            | VertexFormat::Uint16x2
+            | VertexFormat::Sint16x2
+            | VertexFormat::Unorm16x2
+            | VertexFormat::Snorm16x2
+            | VertexFormat::Float16x2
+            | VertexFormat::Float32x2
+            | VertexFormat::Uint32x2
+            | VertexFormat::Sint32x2 => 2,
+            VertexFormat::Float32x3 | VertexFormat::Uint32x3 | VertexFormat::Sint32x3 => 3,
+            _ => 4,
+        }
+    }
+
+    // 按照顶点格式解析顶点缓冲区中的数据，转换为着色器中的值
+    // https://gpuweb.github.io/gpuweb/#vertex-formats
+    pub fn decode(&self, bytes: &[u8]) -> ShaderType {
+        if self.is_integer() {
+            return self.decode_int(bytes);
+        }
+        let components = self.components();
+        let component_size = self.size() / components;
+        let mut values = [0.; 4];
+        for (index, value) in values.iter_mut().take(components).enumerate() {
+            let c = &bytes[index * component_size..(index + 1) * component_size];
+            *value = match self {
+                VertexFormat::Unorm8
+                | VertexFormat::Unorm8x2
+                | VertexFormat::Unorm8x4
+                | VertexFormat::Unorm8x4Bgra => c[0] as f32 / 255.,
+                VertexFormat::Snorm8 | VertexFormat::Snorm8x2 | VertexFormat::Snorm8x4 => {
+                    (c[0] as i8 as f32 / 127.).max(-1.)
+                }
+                VertexFormat::Unorm16 | VertexFormat::Unorm16x2 | VertexFormat::Unorm16x4 => {
+                    u16::from_le_bytes([c[0], c[1]]) as f32 / 65535.
+                }
+                VertexFormat::Snorm16 | VertexFormat::Snorm16x2 | VertexFormat::Snorm16x4 => {
+                    (i16::from_le_bytes([c[0], c[1]]) as f32 / 32767.).max(-1.)
+                }
+                VertexFormat::Float16 | VertexFormat::Float16x2 | VertexFormat::Float16x4 => {
+                    f16::from_le_bytes([c[0], c[1]]).to_f32()
+                }
+                VertexFormat::Float32
+                | VertexFormat::Float32x2
+                | VertexFormat::Float32x3
+                | VertexFormat::Float32x4 => read_f32(c),
+                // 打包格式整体处理
+                VertexFormat::Unorm10_10_10_2 => {
+                    let packed = read_u32(bytes);
+                    let (bits, max) = if index < 3 { (10, 1023.) } else { (2, 3.) };
+                    ((packed >> (index * 10)) & ((1 << bits) - 1)) as f32 / max
+                }
+                _ => unreachable!(),
+            };
+        }
+        if *self == VertexFormat::Unorm8x4Bgra {
+            values.swap(0, 2);
+        }
+        match components {
+            1 => ShaderType::F32(values[0]),
+            2 => ShaderType::Vec2(Vec2::new(values[0], values[1])),
+            3 => ShaderType::Vec3(Vec3::new(values[0], values[1], values[2])),
+            _ => ShaderType::Vec4(values.into()),
+        }
+    }
+
+    // 整数顶点格式，着色器中对应 u32/i32 类型
+    fn decode_int(&self, bytes: &[u8]) -> ShaderType {
+        let components = self.components();
+        let component_size = self.size() / components;
+        let mut values = [0u32; 4];
+        for (index, value) in values.iter_mut().take(components).enumerate() {
+            let c = &bytes[index * component_size..(index + 1) * component_size];
+            *value = match component_size {
+                1 if self.is_signed() => c[0] as i8 as i32 as u32,
+                1 => c[0] as u32,
+                2 if self.is_signed() => i16::from_le_bytes([c[0], c[1]]) as i32 as u32,
+                2 => u16::from_le_bytes([c[0], c[1]]) as u32,
+                _ => read_u32(c),
+            };
         }
+        let [x, y, z, w] = values;
+        if self.is_signed() {
+            let [x, y, z, w] = [x as i32, y as i32, z as i32, w as i32];
+            match components {
+                1 => ShaderType::I32(x),
+                2 => ShaderType::IVec2(IVec2::new(x, y)),
+                3 => ShaderType::IVec3(IVec3::new(x, y, z)),
+                _ => ShaderType::IVec4(IVec4::new(x, y, z, w)),
+            }
+        } else {
+            match components {
+                1 => ShaderType::U32(x),
+                2 => ShaderType::UVec2(UVec2::new(x, y)),
+                3 => ShaderType::UVec3(UVec3::new(x, y, z)),
+                _ => ShaderType::UVec4(UVec4::new(x, y, z, w)),
+            }
+        }
+    }
+
+    // 着色器中对应整数类型的格式
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            VertexFormat::Uint8
+                | VertexFormat::Uint8x2
+                | VertexFormat::Uint8x4
+                | VertexFormat::Sint8
+                | VertexFormat::Sint8x2
+                | VertexFormat::Sint8x4
+                | VertexFormat::Uint16
+                | VertexFormat::Uint16x2
+                | VertexFormat::Uint16x4
+                | VertexFormat::Sint16
+                | VertexFormat::Sint16x2
+                | VertexFormat::Sint16x4
+                | VertexFormat::Uint32
+                | VertexFormat::Uint32x2
+                | VertexFormat::Uint32x3
+                | VertexFormat::Uint32x4
+                | VertexFormat::Sint32
+                | VertexFormat::Sint32x2
+                | VertexFormat::Sint32x3
+                | VertexFormat::Sint32x4
+        )
+    }
+
+    fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            VertexFormat::Sint8
+                | VertexFormat::Sint8x2
+                | VertexFormat::Sint8x4
+                | VertexFormat::Sint16
+                | VertexFormat::Sint16x2
+                | VertexFormat::Sint16x4
+                | VertexFormat::Sint32
+                | VertexFormat::Sint32x2
+                | VertexFormat::Sint32x3
+                | VertexFormat::Sint32x4
+        )
     }
 }
 
-// 按照webgpu标准实施，目前只支持rgba norm 格式
+// 按照webgpu标准实施
 /// Corresponds to [WebGPU `GPUTextureFormat`](
 /// https://gpuweb.github.io/gpuweb/#enumdef-gputextureformat).
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub enum TextureFormat {
     /// Red, green, blue, and alpha channels. 8 bit integer per channel. [0, 255] converted to/from float [0, 1] in shader.
     #[default]
     Rgba8Unorm,
+    /// Red, green, blue, and alpha channels. 8 bit integer per channel. Srgb-color [0, 255] converted to/from linear-color float [0, 1] in shader.
+    Rgba8UnormSrgb,
+    /// Blue, green, red, and alpha channels. 8 bit integer per channel. [0, 255] converted to/from float [0, 1] in shader.
+    Bgra8Unorm,
+    /// Blue, green, red, and alpha channels. 8 bit integer per channel. Srgb-color [0, 255] converted to/from linear-color float [0, 1] in shader.
+    Bgra8UnormSrgb,
+    // 非webgpu标准格式，兼容只有rgb三通道的图片
     Rgb8Unorm,
+    /// Red channel only. 8 bit integer per channel. [0, 255] converted to/from float [0, 1] in shader.
     R8Unorm,
+    /// Red and green channels. 8 bit integer per channel. [0, 255] converted to/from float [0, 1] in shader.
+    Rg8Unorm,
+    /// Red channel only. 16 bit integer per channel. [0, 65535] converted to/from float [0, 1] in shader.
+    R16Unorm,
+    /// Red and green channels. 16 bit integer per channel. [0, 65535] converted to/from float [0, 1] in shader.
+    Rg16Unorm,
+    /// Red, green, blue, and alpha channels. 16 bit integer per channel. [0, 65535] converted to/from float [0, 1] in shader.
+    Rgba16Unorm,
+    /// Red, green, blue, and alpha channels. 16 bit float per channel. Float in shader.
+    Rgba16Float,
+    /// Red, green, blue, and alpha channels. 32 bit float per channel. Float in shader.
+    Rgba32Float,
+    /// Red channel only. 32 bit float per channel. Float in shader.
+    R32Float,
+    /// Red channel only. 32 bit integer per channel. Unsigned in shader.
+    R32Uint,
+    /// Stencil format with 8 bit integer stencil.
+    Stencil8,
+    /// Special depth format with 16 bit integer depth.
+    Depth16Unorm,
+    /// Special depth format with at least 24 bit integer depth.
+    Depth24Plus,
+    /// Special depth/stencil format with at least 24 bit integer depth and 8 bits integer stencil.
+    Depth24PlusStencil8,
+    /// Special depth format with 32 bit floating point depth.
+    Depth32Float,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). 4 color + alpha pallet. 5 bit R + 6 bit G + 5 bit B + 1 bit alpha.
+    /// [0, 63] ([0, 1] for alpha) converted to/from float [0, 1] in shader.
+    Bc1RgbaUnorm,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). 4 color + alpha pallet. 5 bit R + 6 bit G + 5 bit B + 1 bit alpha.
+    /// Srgb-color [0, 63] ([0, 1] for alpha) converted to/from linear-color float [0, 1] in shader.
+    Bc1RgbaUnormSrgb,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). 4 color pallet. 5 bit R + 6 bit G + 5 bit B + 4 bit alpha.
+    /// [0, 63] ([0, 15] for alpha) converted to/from float [0, 1] in shader.
+    Bc2RgbaUnorm,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). 4 color pallet. 5 bit R + 6 bit G + 5 bit B + 4 bit alpha.
+    /// Srgb-color [0, 63] ([0, 15] for alpha) converted to/from linear-color float [0, 1] in shader.
+    Bc2RgbaUnormSrgb,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). 4 color pallet + 8 alpha pallet. 5 bit R + 6 bit G + 5 bit B + 8 bit alpha.
+    /// [0, 63] ([0, 255] for alpha) converted to/from float [0, 1] in shader.
+    Bc3RgbaUnorm,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). 4 color pallet + 8 alpha pallet. 5 bit R + 6 bit G + 5 bit B + 8 bit alpha.
+    /// Srgb-color [0, 63] ([0, 255] for alpha) converted to/from linear-color float [0, 1] in shader.
+    Bc3RgbaUnormSrgb,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). 8 color pallet. 8 bit R.
+    /// [0, 255] converted to/from float [0, 1] in shader.
+    Bc4RUnorm,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). 8 color red pallet + 8 color green pallet. 8 bit RG.
+    /// [0, 255] converted to/from float [0, 1] in shader.
+    Bc5RgUnorm,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). Variable sized pallet. 8 bit integer RGBA.
+    /// [0, 255] converted to/from float [0, 1] in shader.
+    Bc7RgbaUnorm,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). Variable sized pallet. 8 bit integer RGBA.
+    /// Srgb-color [0, 255] converted to/from linear-color float [0, 1] in shader.
+    Bc7RgbaUnormSrgb,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). Complex pallet. 8 bit integer RGB.
+    /// [0, 255] converted to/from float [0, 1] in shader.
+    Etc2Rgb8Unorm,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). Complex pallet. 8 bit integer RGB.
+    /// Srgb-color [0, 255] converted to/from linear-color float [0, 1] in shader.
+    Etc2Rgb8UnormSrgb,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). Complex pallet. 8 bit integer RGB + 1 bit alpha.
+    /// [0, 255] ([0, 1] for alpha) converted to/from float [0, 1] in shader.
+    Etc2Rgb8A1Unorm,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). Complex pallet. 8 bit integer RGB + 1 bit alpha.
+    /// Srgb-color [0, 255] ([0, 1] for alpha) converted to/from linear-color float [0, 1] in shader.
+    Etc2Rgb8A1UnormSrgb,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). Complex pallet. 8 bit integer RGB + 8 bit alpha.
+    /// [0, 255] converted to/from float [0, 1] in shader.
+    Etc2Rgba8Unorm,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). Complex pallet. 8 bit integer RGB + 8 bit alpha.
+    /// Srgb-color [0, 255] converted to/from linear-color float [0, 1] in shader.
+    Etc2Rgba8UnormSrgb,
+    /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). Complex pallet. 11 bit integer R.
+    /// [0, 2047] converted to/from float [0, 1] in shader.
+    EacR11Unorm,
+    /// 4x4 block compressed texture. 16 bytes per block (8 bit/px). Complex pallet. 11 bit integer R + 11 bit integer G.
+    /// [0, 2047] converted to/from float [0, 1] in shader.
+    EacRg11Unorm,
 }
 impl TextureFormat {
+    // 每个纹素占用的字节数，块压缩格式为每个块占用的字节数
     pub fn size(&self) -> usize {
         match self {
-            TextureFormat::Rgba8Unorm => 4,
+            TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb => 4,
             TextureFormat::R8Unorm => 1,
+            TextureFormat::Rg8Unorm | TextureFormat::R16Unorm => 2,
+            TextureFormat::Rg16Unorm => 4,
+            TextureFormat::Rgba16Unorm => 8,
             TextureFormat::Rgb8Unorm => 3,
+            TextureFormat::Rgba16Float => 8,
+            TextureFormat::Rgba32Float => 16,
+            TextureFormat::R32Float | TextureFormat::R32Uint => 4,
+            TextureFormat::Stencil8 => 1,
+            TextureFormat::Depth16Unorm => 2,
+            // depth24plus 按照 24位深度存储在4字节中，depth24plus-stencil8 高8位存储模版值
+            TextureFormat::Depth24Plus | TextureFormat::Depth24PlusStencil8 => 4,
+            TextureFormat::Depth32Float => 4,
+            TextureFormat::Bc1RgbaUnorm
+            | TextureFormat::Bc1RgbaUnormSrgb
+            | TextureFormat::Bc4RUnorm
+            | TextureFormat::Etc2Rgb8Unorm
+            | TextureFormat::Etc2Rgb8UnormSrgb
+            | TextureFormat::Etc2Rgb8A1Unorm
+            | TextureFormat::Etc2Rgb8A1UnormSrgb
+            | TextureFormat::EacR11Unorm => 8,
+            TextureFormat::Bc2RgbaUnorm
+            | TextureFormat::Bc2RgbaUnormSrgb
+            | TextureFormat::Bc3RgbaUnorm
+            | TextureFormat::Bc3RgbaUnormSrgb
+            | TextureFormat::Bc5RgUnorm
+            | TextureFormat::Bc7RgbaUnorm
+            | TextureFormat::Bc7RgbaUnormSrgb
+            | TextureFormat::Etc2Rgba8Unorm
+            | TextureFormat::Etc2Rgba8UnormSrgb
+            | TextureFormat::EacRg11Unorm => 16,
+        }
+    }
+
+    // 块的宽和高，非压缩格式为 (1, 1)
+    pub fn block_dimensions(&self) -> (u32, u32) {
+        if self.is_compressed() {
+            (4, 4)
+        } else {
+            (1, 1)
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Bc1RgbaUnorm
+                | TextureFormat::Bc1RgbaUnormSrgb
+                | TextureFormat::Bc2RgbaUnorm
+                | TextureFormat::Bc2RgbaUnormSrgb
+                | TextureFormat::Bc3RgbaUnorm
+                | TextureFormat::Bc3RgbaUnormSrgb
+                | TextureFormat::Bc4RUnorm
+                | TextureFormat::Bc5RgUnorm
+                | TextureFormat::Bc7RgbaUnorm
+                | TextureFormat::Bc7RgbaUnormSrgb
+                | TextureFormat::Etc2Rgb8Unorm
+                | TextureFormat::Etc2Rgb8UnormSrgb
+                | TextureFormat::Etc2Rgb8A1Unorm
+                | TextureFormat::Etc2Rgb8A1UnormSrgb
+                | TextureFormat::Etc2Rgba8Unorm
+                | TextureFormat::Etc2Rgba8UnormSrgb
+                | TextureFormat::EacR11Unorm
+                | TextureFormat::EacRg11Unorm
+        )
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.remove_srgb_suffix() != *self
+    }
+
+    // 对应的 srgb 格式，没有 srgb 版本的格式返回自身
+    pub fn add_srgb_suffix(&self) -> TextureFormat {
+        match self {
+            TextureFormat::Rgba8Unorm => TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Bgra8Unorm => TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Bc1RgbaUnorm => TextureFormat::Bc1RgbaUnormSrgb,
+            TextureFormat::Bc2RgbaUnorm => TextureFormat::Bc2RgbaUnormSrgb,
+            TextureFormat::Bc3RgbaUnorm => TextureFormat::Bc3RgbaUnormSrgb,
+            TextureFormat::Bc7RgbaUnorm => TextureFormat::Bc7RgbaUnormSrgb,
+            TextureFormat::Etc2Rgb8Unorm => TextureFormat::Etc2Rgb8UnormSrgb,
+            TextureFormat::Etc2Rgb8A1Unorm => TextureFormat::Etc2Rgb8A1UnormSrgb,
+            TextureFormat::Etc2Rgba8Unorm => TextureFormat::Etc2Rgba8UnormSrgb,
+            format => *format,
+        }
+    }
+
+    // 对应的线性格式，非 srgb 格式返回自身
+    pub fn remove_srgb_suffix(&self) -> TextureFormat {
+        match self {
+            TextureFormat::Rgba8UnormSrgb => TextureFormat::Rgba8Unorm,
+            TextureFormat::Bgra8UnormSrgb => TextureFormat::Bgra8Unorm,
+            TextureFormat::Bc1RgbaUnormSrgb => TextureFormat::Bc1RgbaUnorm,
+            TextureFormat::Bc2RgbaUnormSrgb => TextureFormat::Bc2RgbaUnorm,
+            TextureFormat::Bc3RgbaUnormSrgb => TextureFormat::Bc3RgbaUnorm,
+            TextureFormat::Bc7RgbaUnormSrgb => TextureFormat::Bc7RgbaUnorm,
+            TextureFormat::Etc2Rgb8UnormSrgb => TextureFormat::Etc2Rgb8Unorm,
+            TextureFormat::Etc2Rgb8A1UnormSrgb => TextureFormat::Etc2Rgb8A1Unorm,
+            TextureFormat::Etc2Rgba8UnormSrgb => TextureFormat::Etc2Rgba8Unorm,
+            format => *format,
+        }
+    }
+
+    // 解压后对应的非压缩格式，解码结果和直接采样压缩格式一致
+    // EAC 为 11 位精度，解压为 16 位格式
+    pub fn decompressed_format(&self) -> TextureFormat {
+        match self {
+            TextureFormat::Bc5RgUnorm => TextureFormat::Rg8Unorm,
+            TextureFormat::EacR11Unorm | TextureFormat::EacRg11Unorm => TextureFormat::Rgba16Unorm,
+            format if format.is_srgb() => TextureFormat::Rgba8UnormSrgb,
+            _ => TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    // 着色器中对应整数类型的格式
+    pub fn is_integer(&self) -> bool {
+        matches!(self, TextureFormat::R32Uint | TextureFormat::Stencil8)
+    }
+
+    // 采样得到的类型，软件渲染所有浮点格式都可以线性过滤
+    pub fn sample_type(&self) -> TextureSampleType {
+        if self.has_depth_aspect() {
+            TextureSampleType::Depth
+        } else if self.is_integer() {
+            TextureSampleType::Uint
+        } else {
+            TextureSampleType::Float { filterable: true }
+        }
+    }
+
+    pub fn has_depth_aspect(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Depth16Unorm
+                | TextureFormat::Depth24Plus
+                | TextureFormat::Depth24PlusStencil8
+                | TextureFormat::Depth32Float
+        )
+    }
+
+    pub fn has_stencil_aspect(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Stencil8 | TextureFormat::Depth24PlusStencil8
+        )
+    }
+
+    // 按照深度格式的精度对深度值进行量化，保证深度测试的结果和实际存储的精度一致
+    pub fn quantize_depth(&self, depth: f32) -> f32 {
+        match self {
+            TextureFormat::Depth16Unorm => (depth * 65535.).round() / 65535.,
+            TextureFormat::Depth24Plus | TextureFormat::Depth24PlusStencil8 => {
+                (depth * DEPTH24_MAX).round() / DEPTH24_MAX
+            }
+            _ => depth,
+        }
+    }
+
+    // 将着色器输出的值编码为纹素，写入附件时使用
+    // unorm 格式按照标准先clamp到[0,1]再四舍五入，浮点格式不做clamp，可以存储hdr颜色
+    pub fn encode(&self, value: Vec4, texel: &mut [u8]) {
+        let unorm8 = |v: f32| (v.clamp(0., 1.) * 255.).round() as u8;
+        let unorm16 = |v: f32| (v.clamp(0., 1.) * 65535.).round() as u16;
+        match self {
+            TextureFormat::Rgba8Unorm => {
+                texel.copy_from_slice(&[
+                    unorm8(value.x),
+                    unorm8(value.y),
+                    unorm8(value.z),
+                    unorm8(value.w),
+                ]);
+            }
+            TextureFormat::Rgba8UnormSrgb => {
+                texel.copy_from_slice(&[
+                    unorm8(linear_to_srgb(value.x)),
+                    unorm8(linear_to_srgb(value.y)),
+                    unorm8(linear_to_srgb(value.z)),
+                    unorm8(value.w),
+                ]);
+            }
+            TextureFormat::Bgra8Unorm => {
+                texel.copy_from_slice(&[
+                    unorm8(value.z),
+                    unorm8(value.y),
+                    unorm8(value.x),
+                    unorm8(value.w),
+                ]);
+            }
+            TextureFormat::Bgra8UnormSrgb => {
+                texel.copy_from_slice(&[
+                    unorm8(linear_to_srgb(value.z)),
+                    unorm8(linear_to_srgb(value.y)),
+                    unorm8(linear_to_srgb(value.x)),
+                    unorm8(value.w),
+                ]);
+            }
+            TextureFormat::Rgb8Unorm => {
+                texel.copy_from_slice(&[unorm8(value.x), unorm8(value.y), unorm8(value.z)]);
+            }
+            TextureFormat::R8Unorm => texel[0] = unorm8(value.x),
+            TextureFormat::Rg8Unorm => texel.copy_from_slice(&[unorm8(value.x), unorm8(value.y)]),
+            TextureFormat::R16Unorm => texel.copy_from_slice(&unorm16(value.x).to_le_bytes()),
+            TextureFormat::Rg16Unorm => {
+                let values = [value.x, value.y].map(unorm16);
+                texel.copy_from_slice(bytemuck::cast_slice(&values));
+            }
+            TextureFormat::Rgba16Unorm => {
+                let values = [value.x, value.y, value.z, value.w].map(unorm16);
+                texel.copy_from_slice(bytemuck::cast_slice(&values));
+            }
+            TextureFormat::Rgba16Float => {
+                let halfs = [value.x, value.y, value.z, value.w].map(f16::from_f32);
+                texel.copy_from_slice(bytemuck::cast_slice(&halfs.map(f16::to_bits)));
+            }
+            TextureFormat::Rgba32Float => {
+                texel.copy_from_slice(bytemuck::cast_slice(&[value.x, value.y, value.z, value.w]));
+            }
+            TextureFormat::R32Float => texel.copy_from_slice(&value.x.to_le_bytes()),
+            // 整数格式直接按值写入，不做归一化，保证id等数据可以精确写入
+            TextureFormat::R32Uint => texel.copy_from_slice(&(value.x as u32).to_le_bytes()),
+            // 深度格式 x 为深度，y 为模版值
+            TextureFormat::Stencil8 => texel[0] = value.y as u8,
+            TextureFormat::Depth16Unorm => {
+                let depth = (value.x.clamp(0., 1.) * 65535.).round() as u16;
+                texel.copy_from_slice(&depth.to_le_bytes());
+            }
+            TextureFormat::Depth24Plus => {
+                let depth = (value.x.clamp(0., 1.) * DEPTH24_MAX).round() as u32;
+                texel.copy_from_slice(&depth.to_le_bytes());
+            }
+            TextureFormat::Depth24PlusStencil8 => {
+                let depth = (value.x.clamp(0., 1.) * DEPTH24_MAX).round() as u32;
+                let stencil = value.y as u8 as u32;
+                texel.copy_from_slice(&(depth | stencil << 24).to_le_bytes());
+            }
+            TextureFormat::Depth32Float => texel.copy_from_slice(&value.x.to_le_bytes()),
+            format => panic!("cannot encode texels of block-compressed format {format:?}"),
+        }
+    }
+
+    // 整数格式的编码，不经过浮点转换
+    pub fn encode_uint(&self, value: UVec4, texel: &mut [u8]) {
+        match self {
+            TextureFormat::R32Uint => texel.copy_from_slice(&value.x.to_le_bytes()),
+            TextureFormat::Stencil8 => texel[0] = value.x as u8,
+            _ => panic!("{:?} is not an integer format", self),
+        }
+    }
+
+    // 整数格式的解码
+    pub fn decode_uint(&self, texel: &[u8]) -> UVec4 {
+        match self {
+            TextureFormat::R32Uint => UVec4::new(read_u32(texel), 0, 0, 1),
+            TextureFormat::Stencil8 => UVec4::new(texel[0] as u32, 0, 0, 1),
+            _ => panic!("{:?} is not an integer format", self),
+        }
+    }
+
+    // 将纹素解码为着色器中的值，采样和读取附件时使用
+    pub fn decode(&self, texel: &[u8]) -> Vec4 {
+        let unorm8 = |v: u8| v as f32 / 255.;
+        let unorm16 = |bytes: &[u8]| u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.;
+        match self {
+            TextureFormat::Rgba8Unorm => Vec4::new(
+                unorm8(texel[0]),
+                unorm8(texel[1]),
+                unorm8(texel[2]),
+                unorm8(texel[3]),
+            ),
+            TextureFormat::Rgba8UnormSrgb => Vec4::new(
+                srgb_to_linear(unorm8(texel[0])),
+                srgb_to_linear(unorm8(texel[1])),
+                srgb_to_linear(unorm8(texel[2])),
+                unorm8(texel[3]),
+            ),
+            TextureFormat::Bgra8Unorm => Vec4::new(
+                unorm8(texel[2]),
+                unorm8(texel[1]),
+                unorm8(texel[0]),
+                unorm8(texel[3]),
+            ),
+            TextureFormat::Bgra8UnormSrgb => Vec4::new(
+                srgb_to_linear(unorm8(texel[2])),
+                srgb_to_linear(unorm8(texel[1])),
+                srgb_to_linear(unorm8(texel[0])),
+                unorm8(texel[3]),
+            ),
+            TextureFormat::Rgb8Unorm => {
+                Vec4::new(unorm8(texel[0]), unorm8(texel[1]), unorm8(texel[2]), 1.)
+            }
+            // 单通道当作灰度图处理
+            TextureFormat::R8Unorm => Vec4::splat(unorm8(texel[0])),
+            TextureFormat::Rg8Unorm => Vec4::new(unorm8(texel[0]), unorm8(texel[1]), 0., 1.),
+            TextureFormat::R16Unorm => Vec4::splat(unorm16(texel)),
+            TextureFormat::Rg16Unorm => Vec4::new(unorm16(texel), unorm16(&texel[2..]), 0., 1.),
+            TextureFormat::Rgba16Unorm => {
+                let v: [f32; 4] = std::array::from_fn(|i| unorm16(&texel[2 * i..]));
+                v.into()
+            }
+            TextureFormat::Rgba16Float => {
+                let v: [f32; 4] = std::array::from_fn(|i| {
+                    f16::from_le_bytes([texel[2 * i], texel[2 * i + 1]]).to_f32()
+                });
+                v.into()
+            }
+            TextureFormat::Rgba32Float => {
+                let v: [f32; 4] = std::array::from_fn(|i| read_f32(&texel[4 * i..]));
+                v.into()
+            }
+            TextureFormat::R32Float => Vec4::new(read_f32(texel), 0., 0., 1.),
+            TextureFormat::R32Uint => Vec4::new(read_u32(texel) as f32, 0., 0., 1.),
+            TextureFormat::Stencil8 => Vec4::new(0., texel[0] as f32, 0., 1.),
+            TextureFormat::Depth16Unorm => Vec4::new(
+                u16::from_le_bytes([texel[0], texel[1]]) as f32 / 65535.,
+                0.,
+                0.,
+                1.,
+            ),
+            TextureFormat::Depth24Plus => Vec4::new(
+                (read_u32(texel) & 0xFFFFFF) as f32 / DEPTH24_MAX,
+                0.,
+                0.,
+                1.,
+            ),
+            TextureFormat::Depth24PlusStencil8 => {
+                let v = read_u32(texel);
+                Vec4::new(
+                    (v & 0xFFFFFF) as f32 / DEPTH24_MAX,
+                    (v >> 24) as f32,
+                    0.,
+                    1.,
+                )
+            }
+            TextureFormat::Depth32Float => Vec4::new(read_f32(texel), 0., 0., 1.),
+            format => panic!("{format:?} texels can only be decoded by block"),
+        }
+    }
+
+    // 解码块压缩格式的整个块，16 个纹素按行排列
+    // EAC R11 和 RG11 按 16 位解码，避免丢失精度
+    pub fn decode_block(&self, block: &[u8]) -> [Vec4; 16] {
+        match self {
+            TextureFormat::EacR11Unorm | TextureFormat::EacRg11Unorm => {
+                block_compression::decode_block16(*self, block)
+                    .map(|texel| texel.map(|c| c as f32 / 65535.).into())
+            }
+            _ => block_compression::decode_block(*self, block)
+                .map(|texel| self.decode_block_rgba8(texel)),
+        }
+    }
+
+    // 解码块压缩格式的一个块，返回块中第 index 个纹素（按行排列）的值
+    pub fn decode_block_texel(&self, block: &[u8], index: usize) -> Vec4 {
+        self.decode_block(block)[index]
+    }
+
+    // 块解码得到的 rgba8 纹素转换为着色器中的值
+    fn decode_block_rgba8(&self, texel: [u8; 4]) -> Vec4 {
+        let [r, g, b, a] = texel.map(|c| c as f32 / 255.);
+        if self.is_srgb() {
+            Vec4::new(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a)
+        } else {
+            Vec4::new(r, g, b, a)
         }
     }
 }
+
+// 24位深度能表示的最大值
+const DEPTH24_MAX: f32 = 16777215.;
+
+fn read_f32(bytes: &[u8]) -> f32 {
+    f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// srgb 编码
+// https://gpuweb.github.io/gpuweb/#texture-formats
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1. / 2.4) - 0.055
+    }
+}
+
+// srgb 解码
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(value: ShaderType) -> [f32; 4] {
+        let (x, y, z, w) = value.to_vec4().into();
+        [x, y, z, w]
+    }
+
+    #[test]
+    fn decode_snorm() {
+        // -128 和 -127 都对应 -1
+        let bytes = [0x80, 0x81, 0x7F, 0x00];
+        assert_eq!(
+            floats(VertexFormat::Snorm8x4.decode(&bytes)),
+            [-1., -1., 1., 0.]
+        );
+        assert!(matches!(
+            VertexFormat::Snorm8x2.decode(&bytes),
+            ShaderType::Vec2(_)
+        ));
+        let bytes: Vec<u8> = [i16::MIN, -16384, i16::MAX, 0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let [x, y, z, w] = floats(VertexFormat::Snorm16x4.decode(&bytes));
+        assert_eq!([x, z, w], [-1., 1., 0.]);
+        assert_eq!(y, -16384. / 32767.);
+    }
+
+    #[test]
+    fn decode_unorm() {
+        assert_eq!(
+            floats(VertexFormat::Unorm8.decode(&[51])),
+            [0.2, 0., 0., 1.]
+        );
+        let bytes = [255, 0, 51, 0];
+        assert_eq!(
+            floats(VertexFormat::Unorm8x4.decode(&bytes)),
+            [1., 0., 0.2, 0.]
+        );
+        // bgra 顺序存储，着色器中为 rgba
+        assert_eq!(
+            floats(VertexFormat::Unorm8x4Bgra.decode(&bytes)),
+            [0.2, 0., 1., 0.]
+        );
+        let bytes: Vec<u8> = [u16::MAX, 0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        assert_eq!(
+            floats(VertexFormat::Unorm16x2.decode(&bytes)),
+            [1., 0., 0., 1.]
+        );
+    }
+
+    #[test]
+    fn decode_unorm10_10_10_2() {
+        // r 在最低的 10 位，g 为 0，alpha 在最高的 2 位
+        let packed: u32 = 2 << 30 | 512 << 20 | 1023;
+        let [r, g, b, a] = floats(VertexFormat::Unorm10_10_10_2.decode(&packed.to_le_bytes()));
+        assert_eq!([r, g], [1., 0.]);
+        assert_eq!(b, 512. / 1023.);
+        assert_eq!(a, 2. / 3.);
+    }
+
+    #[test]
+    fn decode_float16() {
+        let bytes: Vec<u8> = [0x3C00u16, 0xC000, 0x3800, 0x7C00]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert_eq!(
+            floats(VertexFormat::Float16x4.decode(&bytes)),
+            [1., -2., 0.5, f32::INFINITY]
+        );
+        assert_eq!(
+            floats(VertexFormat::Float16.decode(&bytes)),
+            [1., 0., 0., 1.]
+        );
+    }
+
+    #[test]
+    fn decode_integer() {
+        assert!(matches!(
+            VertexFormat::Sint8x2.decode(&[0xFF, 0x7F]),
+            ShaderType::IVec2(IVec2 { x: -1, y: 127 })
+        ));
+        assert!(matches!(
+            VertexFormat::Uint8x4.decode(&[0xFF, 1, 2, 3]),
+            ShaderType::UVec4(UVec4 {
+                x: 255,
+                y: 1,
+                z: 2,
+                w: 3
+            })
+        ));
+        assert!(matches!(
+            VertexFormat::Sint16.decode(&i16::MIN.to_le_bytes()),
+            ShaderType::I32(-32768)
+        ));
+        // 无符号格式不进行符号扩展
+        assert!(matches!(
+            VertexFormat::Uint16.decode(&[0xFF, 0xFF]),
+            ShaderType::U32(65535)
+        ));
+        let bytes: Vec<u8> = [u32::MAX, 7, 8]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert!(matches!(
+            VertexFormat::Uint32x3.decode(&bytes),
+            ShaderType::UVec3(UVec3 {
+                x: u32::MAX,
+                y: 7,
+                z: 8
+            })
+        ));
+        let bytes: Vec<u8> = [-5i32, i32::MIN, 0, i32::MAX]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert!(matches!(
+            VertexFormat::Sint32x4.decode(&bytes),
+            ShaderType::IVec4(IVec4 {
+                x: -5,
+                y: i32::MIN,
+                z: 0,
+                w: i32::MAX
+            })
+        ));
+    }
+}
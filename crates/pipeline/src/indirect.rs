@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::buffer::Buffer;
+use crate::impl_shader_struct;
+use crate::memory_layout::{AddressSpace, ShaderStruct};
+
+// 间接绘制和间接调度的参数，布局和 WebGPU 一致，都是紧密排列的 32 位整数
+// https://gpuweb.github.io/gpuweb/#indirect-calls
+// 剔除等计算着色器可以直接用 Buffer::write 写入这些参数
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawIndirectArgs {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+impl_shader_struct!(DrawIndirectArgs {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+});
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+impl_shader_struct!(DrawIndexedIndirectArgs {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+});
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DispatchIndirectArgs {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+impl_shader_struct!(DispatchIndirectArgs {
+    x: u32,
+    y: u32,
+    z: u32,
+});
+
+// 读取间接参数时的错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndirectError {
+    // 偏移没有按照 4 字节对齐
+    Unaligned {
+        offset: usize,
+    },
+    // 参数超出了缓冲的范围
+    OutOfBounds {
+        offset: usize,
+        count: u32,
+        buffer_size: usize,
+    },
+}
+impl fmt::Display for IndirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndirectError::Unaligned { offset } => {
+                write!(f, "indirect offset {offset} is not aligned to 4 bytes")
+            }
+            IndirectError::OutOfBounds {
+                offset,
+                count,
+                buffer_size,
+            } => write!(
+                f,
+                "{count} indirect arguments at offset {offset} are out of bounds of the {buffer_size} bytes buffer"
+            ),
+        }
+    }
+}
+impl std::error::Error for IndirectError {}
+
+// 从缓冲中读取 count 个紧密排列的参数，偏移需要 4 字节对齐
+pub(crate) fn read_indirect_args<T: ShaderStruct>(
+    buffer: &Buffer,
+    offset: usize,
+    count: u32,
+) -> Result<Vec<T>, IndirectError> {
+    if !offset.is_multiple_of(4) {
+        return Err(IndirectError::Unaligned { offset });
+    }
+    let stride = T::size(AddressSpace::Storage);
+    let end = stride
+        .checked_mul(count as usize)
+        .and_then(|size| offset.checked_add(size));
+    if end.is_none_or(|end| end > buffer.size()) {
+        return Err(IndirectError::OutOfBounds {
+            offset,
+            count,
+            buffer_size: buffer.size(),
+        });
+    }
+    Ok((0..count as usize)
+        .map(|i| buffer.read(offset + i * stride))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_args() {
+        let args = [
+            DispatchIndirectArgs { x: 1, y: 2, z: 3 },
+            DispatchIndirectArgs { x: 4, y: 5, z: 6 },
+        ];
+        let buffer = Buffer::new(4 + 2 * 12);
+        buffer.write(4, &args[0]);
+        buffer.write(16, &args[1]);
+        assert_eq!(read_indirect_args(&buffer, 4, 2), Ok(args.to_vec()));
+        assert_eq!(
+            read_indirect_args::<DispatchIndirectArgs>(&buffer, 4, 0),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_args_errors() {
+        let buffer = Buffer::new(32);
+        assert_eq!(
+            read_indirect_args::<DispatchIndirectArgs>(&buffer, 2, 1),
+            Err(IndirectError::Unaligned { offset: 2 })
+        );
+        assert_eq!(
+            read_indirect_args::<DispatchIndirectArgs>(&buffer, 24, 1),
+            Err(IndirectError::OutOfBounds {
+                offset: 24,
+                count: 1,
+                buffer_size: 32
+            })
+        );
+        // offset + stride * count 溢出时也是越界
+        let offset = usize::MAX - 3;
+        assert_eq!(
+            read_indirect_args::<DrawIndirectArgs>(&buffer, offset, 1),
+            Err(IndirectError::OutOfBounds {
+                offset,
+                count: 1,
+                buffer_size: 32
+            })
+        );
+    }
+}
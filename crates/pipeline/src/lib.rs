@@ -1,11 +1,30 @@
 mod bind_group;
+mod block_compression;
+mod buffer;
+mod compute;
 mod format;
+mod indirect;
+mod memory_layout;
+mod profiler;
+mod query;
+mod queue;
 mod renderer;
+mod sampler;
 mod shader;
 mod shader_buildin_function;
+mod texture;
 
 pub use bind_group::*;
+pub use buffer::*;
+pub use compute::*;
 pub use format::*;
+pub use indirect::*;
+pub use memory_layout::*;
+pub use profiler::*;
+pub use query::*;
+pub use queue::*;
 pub use renderer::*;
+pub use sampler::*;
 pub use shader::*;
 pub use shader_buildin_function::*;
+pub use texture::*;
@@ -0,0 +1,475 @@
+use bytemuck::Pod;
+use math::{IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+// 按照 WGSL 的内存布局规则编码和解码着色器中的结构体
+// https://gpuweb.github.io/gpuweb/wgsl/#memory-layouts
+
+// 动态偏移需要按照这个值对齐，和 wgpu 默认的 min_uniform_buffer_offset_alignment 一致
+pub const UNIFORM_OFFSET_ALIGNMENT: usize = 256;
+
+/// Corresponds to [WGSL address spaces](
+/// https://gpuweb.github.io/gpuweb/wgsl/#address-spaces) that have host-shareable layouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddressSpace {
+    Uniform,
+    Storage,
+}
+impl AddressSpace {
+    // uniform 中结构体和数组的对齐至少为 16
+    pub fn struct_align(&self, align: usize) -> usize {
+        match self {
+            AddressSpace::Uniform => round_up(align, 16),
+            AddressSpace::Storage => align,
+        }
+    }
+}
+
+pub fn round_up(value: usize, align: usize) -> usize {
+    if align == 0 {
+        return value;
+    }
+    value.div_ceil(align) * align
+}
+
+// 可以按照 WGSL 内存布局编码的类型
+// 标量、向量和矩阵已经实现，结构体使用 impl_shader_struct! 实现
+pub trait ShaderStruct: Sized {
+    fn align(space: AddressSpace) -> usize;
+    fn size(space: AddressSpace) -> usize;
+    fn write(&self, writer: &mut LayoutWriter);
+    fn read(reader: &mut LayoutReader) -> Self;
+
+    fn encode(&self, space: AddressSpace) -> Vec<u8> {
+        let mut writer = LayoutWriter::new(space);
+        writer.field(self);
+        writer.into_bytes()
+    }
+
+    // bytes 长度至少为 size(space)
+    fn decode(bytes: &[u8], space: AddressSpace) -> Self {
+        LayoutReader::new(bytes, space).field()
+    }
+}
+
+pub struct LayoutWriter {
+    space: AddressSpace,
+    bytes: Vec<u8>,
+    offset: usize,
+}
+impl LayoutWriter {
+    pub fn new(space: AddressSpace) -> Self {
+        Self {
+            space,
+            bytes: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    // 按照字段类型的对齐写入，写完之后偏移移动到字段末尾
+    pub fn field<T: ShaderStruct>(&mut self, value: &T) {
+        self.offset = round_up(self.offset, T::align(self.space));
+        let start = self.offset;
+        value.write(self);
+        self.offset = start + T::size(self.space);
+        self.pad_to(self.offset);
+    }
+
+    // 写入基础类型的原始字节
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        self.pad_to(self.offset + data.len());
+        self.bytes[self.offset..self.offset + data.len()].copy_from_slice(data);
+        self.offset += data.len();
+    }
+
+    fn pad_to(&mut self, len: usize) {
+        if self.bytes.len() < len {
+            self.bytes.resize(len, 0);
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub struct LayoutReader<'a> {
+    space: AddressSpace,
+    bytes: &'a [u8],
+    offset: usize,
+}
+impl<'a> LayoutReader<'a> {
+    pub fn new(bytes: &'a [u8], space: AddressSpace) -> Self {
+        Self {
+            space,
+            bytes,
+            offset: 0,
+        }
+    }
+
+    pub fn field<T: ShaderStruct>(&mut self) -> T {
+        self.offset = round_up(self.offset, T::align(self.space));
+        let start = self.offset;
+        let value = T::read(self);
+        self.offset = start + T::size(self.space);
+        value
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> &'a [u8] {
+        let data = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        data
+    }
+}
+
+// 标量和向量直接按字节写入
+macro_rules! impl_shader_struct_pod {
+    ($($ty:ty: $align:expr),* $(,)?) => {
+        $(
+            impl ShaderStruct for $ty {
+                fn align(_space: AddressSpace) -> usize {
+                    $align
+                }
+                fn size(_space: AddressSpace) -> usize {
+                    std::mem::size_of::<$ty>()
+                }
+                fn write(&self, writer: &mut LayoutWriter) {
+                    writer.write_bytes(bytemuck::bytes_of(self));
+                }
+                fn read(reader: &mut LayoutReader) -> Self {
+                    read_pod(reader)
+                }
+            }
+        )*
+    };
+}
+impl_shader_struct_pod!(
+    f32: 4,
+    u32: 4,
+    i32: 4,
+    Vec2: 8,
+    UVec2: 8,
+    IVec2: 8,
+    Vec3: 16,
+    UVec3: 16,
+    IVec3: 16,
+    Vec4: 16,
+    UVec4: 16,
+    IVec4: 16,
+);
+
+fn read_pod<T: Pod>(reader: &mut LayoutReader) -> T {
+    bytemuck::pod_read_unaligned(reader.read_bytes(std::mem::size_of::<T>()))
+}
+
+// 矩阵按列存储，每一列按照列向量的对齐
+impl ShaderStruct for Mat2 {
+    fn align(_space: AddressSpace) -> usize {
+        8
+    }
+    fn size(_space: AddressSpace) -> usize {
+        16
+    }
+    fn write(&self, writer: &mut LayoutWriter) {
+        writer.field(&self.x_axis);
+        writer.field(&self.y_axis);
+    }
+    fn read(reader: &mut LayoutReader) -> Self {
+        Mat2 {
+            x_axis: reader.field(),
+            y_axis: reader.field(),
+        }
+    }
+}
+impl ShaderStruct for Mat3 {
+    fn align(_space: AddressSpace) -> usize {
+        16
+    }
+    // 每一列 vec3 占用 16 字节
+    fn size(_space: AddressSpace) -> usize {
+        48
+    }
+    fn write(&self, writer: &mut LayoutWriter) {
+        writer.field(&self.x_axis);
+        writer.field(&self.y_axis);
+        writer.field(&self.z_axis);
+    }
+    fn read(reader: &mut LayoutReader) -> Self {
+        Mat3 {
+            x_axis: reader.field(),
+            y_axis: reader.field(),
+            z_axis: reader.field(),
+        }
+    }
+}
+impl ShaderStruct for Mat4 {
+    fn align(_space: AddressSpace) -> usize {
+        16
+    }
+    fn size(_space: AddressSpace) -> usize {
+        64
+    }
+    fn write(&self, writer: &mut LayoutWriter) {
+        writer.field(&self.x_axis);
+        writer.field(&self.y_axis);
+        writer.field(&self.z_axis);
+        writer.field(&self.w_axis);
+    }
+    fn read(reader: &mut LayoutReader) -> Self {
+        Mat4 {
+            x_axis: reader.field(),
+            y_axis: reader.field(),
+            z_axis: reader.field(),
+            w_axis: reader.field(),
+        }
+    }
+}
+
+// 数组元素的步长，uniform 中需要是 16 的倍数
+pub fn array_stride<T: ShaderStruct>(space: AddressSpace) -> usize {
+    space.struct_align(round_up(T::size(space), T::align(space)))
+}
+impl<T: ShaderStruct + Copy + Default, const N: usize> ShaderStruct for [T; N] {
+    fn align(space: AddressSpace) -> usize {
+        space.struct_align(T::align(space))
+    }
+    fn size(space: AddressSpace) -> usize {
+        array_stride::<T>(space) * N
+    }
+    fn write(&self, writer: &mut LayoutWriter) {
+        let stride = array_stride::<T>(writer.space);
+        let start = writer.offset;
+        for (i, element) in self.iter().enumerate() {
+            writer.offset = start + i * stride;
+            writer.field(element);
+        }
+    }
+    fn read(reader: &mut LayoutReader) -> Self {
+        let stride = array_stride::<T>(reader.space);
+        let start = reader.offset;
+        let mut array = [T::default(); N];
+        for (i, element) in array.iter_mut().enumerate() {
+            reader.offset = start + i * stride;
+            *element = reader.field();
+        }
+        array
+    }
+}
+
+// 为结构体实现 ShaderStruct，字段按照声明的顺序布局
+// impl_shader_struct!(MeshUniform { model: Mat4, inverse_transpose_model: Mat4 });
+#[macro_export]
+macro_rules! impl_shader_struct {
+    ($ty:ty { $($field:ident: $field_ty:ty),* $(,)? }) => {
+        impl $crate::ShaderStruct for $ty {
+            fn align(space: $crate::AddressSpace) -> usize {
+                let align = 0;
+                $(let align = usize::max(align, <$field_ty as $crate::ShaderStruct>::align(space));)*
+                space.struct_align(align)
+            }
+            fn size(space: $crate::AddressSpace) -> usize {
+                let offset = 0;
+                $(
+                    let offset = $crate::round_up(
+                        offset,
+                        <$field_ty as $crate::ShaderStruct>::align(space),
+                    ) + <$field_ty as $crate::ShaderStruct>::size(space);
+                )*
+                $crate::round_up(offset, <Self as $crate::ShaderStruct>::align(space))
+            }
+            fn write(&self, writer: &mut $crate::LayoutWriter) {
+                $(writer.field::<$field_ty>(&self.$field);)*
+            }
+            fn read(reader: &mut $crate::LayoutReader) -> Self {
+                Self {
+                    $($field: reader.field::<$field_ty>(),)*
+                }
+            }
+        }
+    };
+}
+
+// 把多个同类型的 uniform 写到同一个缓冲中，每个 uniform 按照动态偏移的对齐存放
+// 类似 bevy 的 DynamicUniformBuffer
+pub struct DynamicUniformBuffer<T: ShaderStruct> {
+    bytes: Vec<u8>,
+    len: usize,
+    _marker: std::marker::PhantomData<T>,
+}
+impl<T: ShaderStruct> Default for DynamicUniformBuffer<T> {
+    fn default() -> Self {
+        Self {
+            bytes: Vec::new(),
+            len: 0,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+impl<T: ShaderStruct> DynamicUniformBuffer<T> {
+    // 返回写入的 uniform 的动态偏移
+    pub fn push(&mut self, value: &T) -> u32 {
+        let offset = round_up(self.bytes.len(), UNIFORM_OFFSET_ALIGNMENT);
+        self.bytes.resize(offset, 0);
+        self.bytes.extend(value.encode(AddressSpace::Uniform));
+        self.len += 1;
+        offset as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Default)]
+    struct Vec3Scalar {
+        position: Vec3,
+        radius: f32,
+    }
+    crate::impl_shader_struct!(Vec3Scalar {
+        position: Vec3,
+        radius: f32,
+    });
+
+    #[derive(Clone, Copy, Default)]
+    struct Mat3Scalar {
+        rotation: Mat3,
+        scale: f32,
+    }
+    crate::impl_shader_struct!(Mat3Scalar {
+        rotation: Mat3,
+        scale: f32,
+    });
+
+    #[derive(Clone, Copy, Default)]
+    struct TrailingPadding {
+        color: Vec4,
+        intensity: f32,
+    }
+    crate::impl_shader_struct!(TrailingPadding {
+        color: Vec4,
+        intensity: f32,
+    });
+
+    #[derive(Clone, Copy, Default)]
+    struct Scalar {
+        value: f32,
+    }
+    crate::impl_shader_struct!(Scalar { value: f32 });
+
+    fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+        bytemuck::pod_read_unaligned(&bytes[offset..offset + 4])
+    }
+
+    #[test]
+    fn vec3_followed_by_scalar() {
+        for space in [AddressSpace::Uniform, AddressSpace::Storage] {
+            assert_eq!(Vec3Scalar::align(space), 16);
+            assert_eq!(Vec3Scalar::size(space), 16);
+        }
+        let value = Vec3Scalar {
+            position: Vec3::new(1., 2., 3.),
+            radius: 4.,
+        };
+        let bytes = value.encode(AddressSpace::Uniform);
+        assert_eq!(bytes.len(), 16);
+        // 标量放在 vec3 后面剩余的 4 个字节中
+        assert_eq!(f32_at(&bytes, 8), 3.);
+        assert_eq!(f32_at(&bytes, 12), 4.);
+        let decoded = Vec3Scalar::decode(&bytes, AddressSpace::Uniform);
+        assert_eq!(decoded.radius, 4.);
+        assert_eq!(decoded.position.z, 3.);
+    }
+
+    #[test]
+    fn mat3_columns_are_padded() {
+        assert_eq!(Mat3::size(AddressSpace::Uniform), 48);
+        assert_eq!(Mat3::align(AddressSpace::Uniform), 16);
+        let value = Mat3Scalar {
+            rotation: Mat3::from_cols(
+                Vec3::new(1., 2., 3.),
+                Vec3::new(4., 5., 6.),
+                Vec3::new(7., 8., 9.),
+            ),
+            scale: 10.,
+        };
+        let bytes = value.encode(AddressSpace::Uniform);
+        assert_eq!(Mat3Scalar::size(AddressSpace::Uniform), 64);
+        assert_eq!(bytes.len(), 64);
+        // 每一列从 16 的倍数开始，标量在矩阵之后
+        assert_eq!(f32_at(&bytes, 16), 4.);
+        assert_eq!(f32_at(&bytes, 32), 7.);
+        assert_eq!(f32_at(&bytes, 48), 10.);
+        let decoded = Mat3Scalar::decode(&bytes, AddressSpace::Uniform);
+        assert_eq!(decoded.rotation.z_axis.y, 8.);
+        assert_eq!(decoded.scale, 10.);
+    }
+
+    #[test]
+    fn uniform_array_stride() {
+        assert_eq!(array_stride::<f32>(AddressSpace::Uniform), 16);
+        assert_eq!(array_stride::<f32>(AddressSpace::Storage), 4);
+        assert_eq!(array_stride::<Vec3>(AddressSpace::Uniform), 16);
+        assert_eq!(array_stride::<Vec2>(AddressSpace::Uniform), 16);
+        assert_eq!(array_stride::<Vec2>(AddressSpace::Storage), 8);
+        assert_eq!(<[f32; 4]>::size(AddressSpace::Uniform), 64);
+        assert_eq!(<[f32; 4]>::size(AddressSpace::Storage), 16);
+        assert_eq!(<[f32; 4]>::align(AddressSpace::Uniform), 16);
+
+        let bytes = [1f32, 2., 3., 4.].encode(AddressSpace::Uniform);
+        assert_eq!(bytes.len(), 64);
+        assert_eq!(f32_at(&bytes, 16), 2.);
+        assert_eq!(f32_at(&bytes, 48), 4.);
+        assert_eq!(
+            <[f32; 4]>::decode(&bytes, AddressSpace::Uniform),
+            [1., 2., 3., 4.]
+        );
+    }
+
+    #[test]
+    fn struct_trailing_padding() {
+        for space in [AddressSpace::Uniform, AddressSpace::Storage] {
+            assert_eq!(TrailingPadding::align(space), 16);
+            assert_eq!(TrailingPadding::size(space), 32);
+        }
+        // uniform 中结构体的对齐至少为 16，storage 中按照成员的最大对齐
+        assert_eq!(Scalar::align(AddressSpace::Uniform), 16);
+        assert_eq!(Scalar::size(AddressSpace::Uniform), 16);
+        assert_eq!(Scalar::align(AddressSpace::Storage), 4);
+        assert_eq!(Scalar::size(AddressSpace::Storage), 4);
+        assert_eq!(array_stride::<Scalar>(AddressSpace::Uniform), 16);
+        assert_eq!(<[TrailingPadding; 2]>::size(AddressSpace::Uniform), 64);
+    }
+
+    #[test]
+    fn dynamic_uniform_buffer_offsets() {
+        let mut buffer = DynamicUniformBuffer::<TrailingPadding>::default();
+        assert!(buffer.is_empty());
+        let offsets: Vec<u32> = (0..3)
+            .map(|i| {
+                buffer.push(&TrailingPadding {
+                    color: Vec4::ONE,
+                    intensity: i as f32,
+                })
+            })
+            .collect();
+        assert_eq!(buffer.len(), 3);
+        for offset in &offsets {
+            assert_eq!(*offset as usize % UNIFORM_OFFSET_ALIGNMENT, 0);
+        }
+        assert_eq!(offsets, [0, 256, 512]);
+        let bytes = buffer.into_bytes();
+        assert_eq!(f32_at(&bytes, 256 + 16), 1.);
+        assert_eq!(f32_at(&bytes, 512 + 16), 2.);
+    }
+}
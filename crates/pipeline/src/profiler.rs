@@ -0,0 +1,158 @@
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+// 时间戳查询和性能分析共用同一个时钟，时间都是相对第一次使用时钟的时刻
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+// 当前时刻的时间戳，单位纳秒
+pub fn timestamp_now() -> u64 {
+    epoch().elapsed().as_nanos() as u64
+}
+
+#[derive(Clone, Debug)]
+pub struct ProfileEvent {
+    pub name: &'static str,
+    // 事件的类别，draw 或者 dispatch 的各个阶段为 "stage"
+    pub category: &'static str,
+    // 相对时钟起点的开始时间
+    pub start: Duration,
+    pub duration: Duration,
+    pub args: Vec<(&'static str, u64)>,
+}
+
+// 记录每次绘制各个阶段耗时的分析器，可以导出为 Chrome trace event 格式
+// 在 chrome://tracing 或者 https://ui.perfetto.dev 中打开
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    pub events: Vec<ProfileEvent>,
+    // 已经记录的绘制数量，作为每个事件的 draw 参数
+    draw_count: u64,
+}
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.draw_count = 0;
+    }
+
+    // 事件开始的时刻，保证时钟起点早于第一个事件
+    pub(crate) fn now() -> Instant {
+        epoch();
+        Instant::now()
+    }
+
+    // 开始一次新的绘制，返回绘制的编号
+    pub(crate) fn next_draw(&mut self) -> u64 {
+        self.draw_count += 1;
+        self.draw_count - 1
+    }
+
+    // 当前正在进行的绘制的编号
+    pub(crate) fn current_draw(&self) -> u64 {
+        self.draw_count.saturating_sub(1)
+    }
+
+    pub fn record(
+        &mut self,
+        name: &'static str,
+        category: &'static str,
+        start: Instant,
+        duration: Duration,
+        args: Vec<(&'static str, u64)>,
+    ) {
+        self.events.push(ProfileEvent {
+            name,
+            category,
+            start: start.saturating_duration_since(epoch()),
+            duration,
+            args,
+        });
+    }
+
+    // 每个事件名字的总耗时和次数，按照总耗时从大到小排序
+    pub fn summary(&self) -> Vec<(&'static str, Duration, usize)> {
+        let mut summary: Vec<(&'static str, Duration, usize)> = Vec::new();
+        for event in &self.events {
+            match summary.iter_mut().find(|(name, ..)| *name == event.name) {
+                Some((_, total, count)) => {
+                    *total += event.duration;
+                    *count += 1;
+                }
+                None => summary.push((event.name, event.duration, 1)),
+            }
+        }
+        summary.sort_by_key(|(_, total, _)| std::cmp::Reverse(*total));
+        summary
+    }
+
+    // 文本格式的汇总报告
+    pub fn report(&self) -> String {
+        let mut report = format!("{:<24}{:>14}{:>10}\n", "stage", "total (ms)", "count");
+        for (name, total, count) in self.summary() {
+            let _ = writeln!(
+                report,
+                "{:<24}{:>14.3}{:>10}",
+                name,
+                total.as_secs_f64() * 1000.,
+                count
+            );
+        }
+        report
+    }
+
+    // Chrome trace event 格式的 JSON，每个事件都是一个 "X"(complete) 事件，时间单位为微秒
+    // https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+    pub fn to_chrome_trace(&self) -> String {
+        let mut json = String::from("{\"traceEvents\":[");
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":0,\"args\":{{",
+                escape_json(event.name),
+                escape_json(event.category),
+                event.start.as_nanos() as f64 / 1000.,
+                event.duration.as_nanos() as f64 / 1000.,
+            );
+            for (j, (key, value)) in event.args.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                let _ = write!(json, "\"{}\":{}", escape_json(key), value);
+            }
+            json.push_str("}}");
+        }
+        json.push_str("],\"displayTimeUnit\":\"ms\"}");
+        json
+    }
+
+    pub fn write_chrome_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_chrome_trace())
+    }
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,182 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::buffer::Buffer;
+use crate::profiler::timestamp_now;
+
+bitflags::bitflags! {
+    /// Flags for which pipeline data should be recorded.
+    ///
+    /// The results of a pipeline statistics query are written in the order of the flags,
+    /// one `u64` per enabled statistic.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct PipelineStatisticsTypes: u8 {
+        // 顶点着色器执行的次数
+        const VERTEX_SHADER_INVOCATIONS = 1 << 0;
+        // 进入裁剪阶段的图元数量
+        const CLIPPER_INVOCATIONS = 1 << 1;
+        // 裁剪之后剩下的图元数量，和 CLIPPER_INVOCATIONS 的差就是被裁剪掉的图元
+        const CLIPPER_PRIMITIVES_OUT = 1 << 2;
+        // 片元着色器执行的次数
+        const FRAGMENT_SHADER_INVOCATIONS = 1 << 3;
+        // 计算着色器执行的次数
+        const COMPUTE_SHADER_INVOCATIONS = 1 << 4;
+    }
+}
+
+// 查询的类型，对应 wgpu 的 QueryType
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QueryType {
+    // 通过深度测试的采样数量
+    Occlusion,
+    PipelineStatistics(PipelineStatisticsTypes),
+    // 写入时刻的时间戳，单位纳秒，和 Profiler 使用同一个时钟
+    Timestamp,
+}
+impl QueryType {
+    // 每个查询结果占用的 u64 数量
+    pub fn result_count(&self) -> usize {
+        match self {
+            QueryType::Occlusion | QueryType::Timestamp => 1,
+            QueryType::PipelineStatistics(types) => types.bits().count_ones() as usize,
+        }
+    }
+}
+
+// 管线各个阶段的计数器，渲染器和计算管线会一直累加
+// 查询开始和结束时各记录一次，两次的差就是查询的结果
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    pub vertex_shader_invocations: u64,
+    pub clipper_invocations: u64,
+    pub clipper_primitives_out: u64,
+    pub fragment_shader_invocations: u64,
+    pub compute_shader_invocations: u64,
+    // 通过深度测试的采样数量，用于遮挡查询
+    pub occlusion_samples: u64,
+}
+impl PipelineStatistics {
+    // 按照 types 中标志位的顺序返回 self - begin
+    fn resolve(&self, begin: &PipelineStatistics, types: PipelineStatisticsTypes) -> Vec<u64> {
+        let values = [
+            (
+                PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS,
+                self.vertex_shader_invocations - begin.vertex_shader_invocations,
+            ),
+            (
+                PipelineStatisticsTypes::CLIPPER_INVOCATIONS,
+                self.clipper_invocations - begin.clipper_invocations,
+            ),
+            (
+                PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT,
+                self.clipper_primitives_out - begin.clipper_primitives_out,
+            ),
+            (
+                PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS,
+                self.fragment_shader_invocations - begin.fragment_shader_invocations,
+            ),
+            (
+                PipelineStatisticsTypes::COMPUTE_SHADER_INVOCATIONS,
+                self.compute_shader_invocations - begin.compute_shader_invocations,
+            ),
+        ];
+        values
+            .into_iter()
+            .filter(|(flag, _)| types.contains(*flag))
+            .map(|(_, value)| value)
+            .collect()
+    }
+}
+
+pub struct QuerySetDescriptor {
+    pub ty: QueryType,
+    pub count: u32,
+}
+
+// 查询集，存储 count 个查询的结果
+// 结果使用原子变量存储，这样渲染器只需要持有不可变引用
+pub struct QuerySet {
+    pub ty: QueryType,
+    pub count: u32,
+    results: Box<[AtomicU64]>,
+}
+impl QuerySet {
+    pub fn new(desc: QuerySetDescriptor) -> Self {
+        let len = desc.ty.result_count() * desc.count as usize;
+        Self {
+            ty: desc.ty,
+            count: desc.count,
+            results: (0..len).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    fn query_results(&self, index: u32) -> &[AtomicU64] {
+        assert!(
+            index < self.count,
+            "query index {index} is out of range of the query set with {} queries",
+            self.count
+        );
+        let stride = self.ty.result_count();
+        &self.results[index as usize * stride..(index as usize + 1) * stride]
+    }
+
+    // 读取单个查询的结果
+    pub fn get(&self, index: u32) -> Vec<u64> {
+        self.query_results(index)
+            .iter()
+            .map(|value| value.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    // 把查询结果按照 u64 写入缓冲，对应 wgpu 的 CommandEncoder::resolve_query_set
+    pub fn resolve(&self, queries: Range<u32>, destination: &Buffer, destination_offset: usize) {
+        let bytes: Vec<u8> = queries
+            .flat_map(|index| self.get(index))
+            .flat_map(u64::to_le_bytes)
+            .collect();
+        destination.write_bytes(destination_offset, &bytes);
+    }
+
+    // 写入当前时刻的时间戳，对应 wgpu 的 write_timestamp
+    pub fn write_timestamp(&self, index: u32) {
+        assert!(
+            self.ty == QueryType::Timestamp,
+            "query set is not a timestamp query set"
+        );
+        self.write(index, &[timestamp_now()]);
+    }
+
+    fn write(&self, index: u32, values: &[u64]) {
+        self.query_results(index)
+            .iter()
+            .zip(values)
+            .for_each(|(result, &value)| result.store(value, Ordering::Relaxed));
+    }
+}
+
+// 正在进行的查询，记录开始时的计数器
+pub(crate) struct ActiveQuery<'a> {
+    query_set: &'a QuerySet,
+    index: u32,
+    begin: PipelineStatistics,
+}
+impl<'a> ActiveQuery<'a> {
+    pub(crate) fn begin(query_set: &'a QuerySet, index: u32, begin: PipelineStatistics) -> Self {
+        // 提前检查索引
+        query_set.query_results(index);
+        Self {
+            query_set,
+            index,
+            begin,
+        }
+    }
+
+    pub(crate) fn end(self, end: &PipelineStatistics) {
+        let values = match self.query_set.ty {
+            QueryType::Occlusion => vec![end.occlusion_samples - self.begin.occlusion_samples],
+            QueryType::PipelineStatistics(types) => end.resolve(&self.begin, types),
+            QueryType::Timestamp => unreachable!("timestamp queries are written directly"),
+        };
+        self.query_set.write(self.index, &values);
+    }
+}
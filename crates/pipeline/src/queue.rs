@@ -0,0 +1,705 @@
+use std::fmt;
+
+use crate::{Buffer, Texture, TextureFormat};
+
+// 和 wgpu 一致，缓冲拷贝的偏移和大小需要 4 字节对齐
+pub const COPY_BUFFER_ALIGNMENT: usize = 4;
+// 纹理和缓冲之间拷贝时，缓冲中每行的字节数需要 256 字节对齐
+pub const COPY_BYTES_PER_ROW_ALIGNMENT: u32 = 256;
+
+// 拷贝区域在纹理中的起点，三维纹理的 z 为深度，其他纹理的 z 为数组层
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Origin3d {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+// 拷贝区域的大小，三维纹理的 depth_or_array_layers 为深度，其他纹理为数组层数
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Extent3d {
+    pub width: u32,
+    pub height: u32,
+    pub depth_or_array_layers: u32,
+}
+impl Default for Extent3d {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
+// 纹理数据在缓冲或者字节数组中的布局，对应 wgpu 的 ImageDataLayout
+// bytes_per_row 和 rows_per_image 为 None 时按照拷贝区域紧密排列
+// 块压缩格式的一行为一行块，rows_per_image 也是以块为单位的行数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ImageDataLayout {
+    pub offset: usize,
+    pub bytes_per_row: Option<u32>,
+    pub rows_per_image: Option<u32>,
+}
+
+// 拷贝的纹理和区域，对应 wgpu 的 ImageCopyTexture
+// 作为源时 T 为 &Texture，作为目标时 T 为 &mut Texture
+#[derive(Debug)]
+pub struct ImageCopyTexture<T> {
+    pub texture: T,
+    pub mip_level: u32,
+    pub origin: Origin3d,
+}
+
+// 拷贝的缓冲和数据布局，对应 wgpu 的 ImageCopyBuffer
+#[derive(Clone, Copy, Debug)]
+pub struct ImageCopyBuffer<'a> {
+    pub buffer: &'a Buffer,
+    pub layout: ImageDataLayout,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CopyError {
+    // 偏移或者大小没有按照要求对齐
+    Unaligned {
+        value: usize,
+        alignment: usize,
+    },
+    // 读写的范围超出了缓冲或者数据的大小
+    BufferOutOfBounds {
+        offset: usize,
+        size: usize,
+        buffer_size: usize,
+    },
+    InvalidMipLevel {
+        mip_level: u32,
+        mip_level_count: u32,
+    },
+    // 拷贝区域超出了纹理的大小
+    TextureOutOfBounds {
+        origin: Origin3d,
+        size: Extent3d,
+        texture_size: Extent3d,
+    },
+    // 深度和模版格式只能拷贝整个子资源
+    PartialDepthStencilCopy,
+    // 两个纹理的格式不一致，只有是否为 srgb 的区别时可以拷贝
+    FormatMismatch {
+        source: TextureFormat,
+        destination: TextureFormat,
+    },
+    // bytes_per_row 小于一行纹素的大小
+    BytesPerRowTooSmall {
+        bytes_per_row: u32,
+        required: usize,
+    },
+    // rows_per_image 小于拷贝区域的高度
+    RowsPerImageTooSmall {
+        rows_per_image: u32,
+        required: u32,
+    },
+}
+impl fmt::Display for CopyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyError::Unaligned { value, alignment } => {
+                write!(f, "{value} is not a multiple of {alignment}")
+            }
+            CopyError::BufferOutOfBounds {
+                offset,
+                size,
+                buffer_size,
+            } => write!(
+                f,
+                "copy of {size} bytes at offset {offset} overruns the {buffer_size} bytes buffer"
+            ),
+            CopyError::InvalidMipLevel {
+                mip_level,
+                mip_level_count,
+            } => write!(
+                f,
+                "mip level {mip_level} is out of range of the texture with {mip_level_count} levels"
+            ),
+            CopyError::TextureOutOfBounds {
+                origin,
+                size,
+                texture_size,
+            } => write!(
+                f,
+                "copy of {size:?} at {origin:?} overruns the texture of size {texture_size:?}"
+            ),
+            CopyError::PartialDepthStencilCopy => write!(
+                f,
+                "depth and stencil textures can only be copied as whole subresources"
+            ),
+            CopyError::FormatMismatch {
+                source,
+                destination,
+            } => write!(
+                f,
+                "cannot copy from a {source:?} texture to a {destination:?} texture"
+            ),
+            CopyError::BytesPerRowTooSmall {
+                bytes_per_row,
+                required,
+            } => write!(
+                f,
+                "bytes per row {bytes_per_row} is less than the {required} bytes of a row"
+            ),
+            CopyError::RowsPerImageTooSmall {
+                rows_per_image,
+                required,
+            } => write!(
+                f,
+                "rows per image {rows_per_image} is less than the copy height {required}"
+            ),
+        }
+    }
+}
+impl std::error::Error for CopyError {}
+
+// 写入缓冲，对应 wgpu 的 Queue::write_buffer
+pub fn write_buffer(buffer: &Buffer, offset: usize, data: &[u8]) -> Result<(), CopyError> {
+    check_alignment(offset, COPY_BUFFER_ALIGNMENT)?;
+    check_alignment(data.len(), COPY_BUFFER_ALIGNMENT)?;
+    check_buffer_range(offset, data.len(), buffer.size())?;
+    buffer.write_bytes(offset, data);
+    Ok(())
+}
+
+// 把 data 按照 layout 写入纹理的一个区域，对应 wgpu 的 Queue::write_texture
+// 和 wgpu 一样，data 中的 bytes_per_row 不需要 256 字节对齐
+pub fn write_texture(
+    texture: ImageCopyTexture<&mut Texture>,
+    data: &[u8],
+    layout: ImageDataLayout,
+    size: Extent3d,
+) -> Result<(), CopyError> {
+    let ImageCopyTexture {
+        texture,
+        mip_level,
+        origin,
+    } = texture;
+    check_texture_copy(texture, mip_level, origin, size)?;
+    let (bytes_per_row, rows_per_image) =
+        check_data_layout(layout, size, texture.format, data.len())?;
+    copy_rows(size, texture.format, |z, y, row_size| {
+        let src = layout.offset + (z * rows_per_image + y) * bytes_per_row;
+        let dst = texture.copy_offset(mip_level, origin, z as u32, y as u32);
+        texture.data[dst..dst + row_size].copy_from_slice(&data[src..src + row_size]);
+    });
+    Ok(())
+}
+
+// 缓冲之间的拷贝，对应 wgpu 的 CommandEncoder::copy_buffer_to_buffer
+pub fn copy_buffer_to_buffer(
+    source: &Buffer,
+    source_offset: usize,
+    destination: &Buffer,
+    destination_offset: usize,
+    size: usize,
+) -> Result<(), CopyError> {
+    for value in [source_offset, destination_offset, size] {
+        check_alignment(value, COPY_BUFFER_ALIGNMENT)?;
+    }
+    check_buffer_range(source_offset, size, source.size())?;
+    check_buffer_range(destination_offset, size, destination.size())?;
+    // 先读出来再写入，源和目标是同一个缓冲并且范围重叠时也能得到正确的结果
+    destination.write_bytes(destination_offset, &source.read_bytes(source_offset, size));
+    Ok(())
+}
+
+// 对应 wgpu 的 CommandEncoder::copy_buffer_to_texture
+pub fn copy_buffer_to_texture(
+    source: ImageCopyBuffer,
+    destination: ImageCopyTexture<&mut Texture>,
+    size: Extent3d,
+) -> Result<(), CopyError> {
+    check_buffer_layout(source.layout, size, destination.texture.format)?;
+    let data = source.buffer.to_bytes();
+    write_texture(destination, &data, source.layout, size)
+}
+
+// 对应 wgpu 的 CommandEncoder::copy_texture_to_buffer
+// 可以配合 Renderer::copy_frame_buffer_to_texture 读取渲染结果
+pub fn copy_texture_to_buffer(
+    source: ImageCopyTexture<&Texture>,
+    destination: ImageCopyBuffer,
+    size: Extent3d,
+) -> Result<(), CopyError> {
+    let ImageCopyTexture {
+        texture,
+        mip_level,
+        origin,
+    } = source;
+    check_texture_copy(texture, mip_level, origin, size)?;
+    check_buffer_layout(destination.layout, size, texture.format)?;
+    let (bytes_per_row, rows_per_image) = check_data_layout(
+        destination.layout,
+        size,
+        texture.format,
+        destination.buffer.size(),
+    )?;
+    copy_rows(size, texture.format, |z, y, row_size| {
+        let src = texture.copy_offset(mip_level, origin, z as u32, y as u32);
+        let dst = destination.layout.offset + (z * rows_per_image + y) * bytes_per_row;
+        destination
+            .buffer
+            .write_bytes(dst, &texture.data[src..src + row_size]);
+    });
+    Ok(())
+}
+
+// 纹理之间的拷贝，对应 wgpu 的 CommandEncoder::copy_texture_to_texture
+// 两个纹理的格式需要一致，只有是否为 srgb 的区别时按照字节直接拷贝
+pub fn copy_texture_to_texture(
+    source: ImageCopyTexture<&Texture>,
+    destination: ImageCopyTexture<&mut Texture>,
+    size: Extent3d,
+) -> Result<(), CopyError> {
+    let (src_format, dst_format) = (source.texture.format, destination.texture.format);
+    if src_format.remove_srgb_suffix() != dst_format.remove_srgb_suffix() {
+        return Err(CopyError::FormatMismatch {
+            source: src_format,
+            destination: dst_format,
+        });
+    }
+    check_texture_copy(source.texture, source.mip_level, source.origin, size)?;
+    check_texture_copy(
+        destination.texture,
+        destination.mip_level,
+        destination.origin,
+        size,
+    )?;
+    copy_rows(size, src_format, |z, y, row_size| {
+        let (z, y) = (z as u32, y as u32);
+        let src = source
+            .texture
+            .copy_offset(source.mip_level, source.origin, z, y);
+        let dst = destination
+            .texture
+            .copy_offset(destination.mip_level, destination.origin, z, y);
+        destination.texture.data[dst..dst + row_size]
+            .copy_from_slice(&source.texture.data[src..src + row_size]);
+    });
+    Ok(())
+}
+
+// 按行拷贝，f 的参数为拷贝区域中的 z、y 和一行的字节数，块压缩格式按块的行拷贝
+fn copy_rows(size: Extent3d, format: TextureFormat, mut f: impl FnMut(usize, usize, usize)) {
+    let (row_size, rows) = copy_row_size(size, format);
+    for z in 0..size.depth_or_array_layers as usize {
+        for y in 0..rows as usize {
+            f(z, y, row_size);
+        }
+    }
+}
+
+fn check_alignment(value: usize, alignment: usize) -> Result<(), CopyError> {
+    if !value.is_multiple_of(alignment) {
+        return Err(CopyError::Unaligned { value, alignment });
+    }
+    Ok(())
+}
+
+fn check_buffer_range(offset: usize, size: usize, buffer_size: usize) -> Result<(), CopyError> {
+    if offset.checked_add(size).is_none_or(|end| end > buffer_size) {
+        return Err(CopyError::BufferOutOfBounds {
+            offset,
+            size,
+            buffer_size,
+        });
+    }
+    Ok(())
+}
+
+// 拷贝区域每行的字节数和每个图像的行数，每行的字节数在 usize 中计算，不会溢出
+fn copy_row_size(size: Extent3d, format: TextureFormat) -> (usize, u32) {
+    let (block_width, block_height) = format.block_dimensions();
+    (
+        size.width.div_ceil(block_width) as usize * format.size(),
+        size.height.div_ceil(block_height),
+    )
+}
+
+// 缓冲中的纹理数据有多行时，每行的字节数需要 256 字节对齐
+fn check_buffer_layout(
+    layout: ImageDataLayout,
+    size: Extent3d,
+    format: TextureFormat,
+) -> Result<(), CopyError> {
+    let (row_size, rows) = copy_row_size(size, format);
+    if rows > 1 || size.depth_or_array_layers > 1 {
+        let bytes_per_row = layout.bytes_per_row.map_or(row_size, |b| b as usize);
+        check_alignment(bytes_per_row, COPY_BYTES_PER_ROW_ALIGNMENT as usize)?;
+    }
+    Ok(())
+}
+
+// 检查数据布局，返回每行的字节数和每个图像的行数
+fn check_data_layout(
+    layout: ImageDataLayout,
+    size: Extent3d,
+    format: TextureFormat,
+    data_size: usize,
+) -> Result<(usize, usize), CopyError> {
+    let (row_size, rows) = copy_row_size(size, format);
+    let bytes_per_row = match layout.bytes_per_row {
+        Some(bytes_per_row) if (bytes_per_row as usize) < row_size => {
+            return Err(CopyError::BytesPerRowTooSmall {
+                bytes_per_row,
+                required: row_size,
+            });
+        }
+        Some(bytes_per_row) => bytes_per_row as usize,
+        None => row_size,
+    };
+    let rows_per_image = layout.rows_per_image.unwrap_or(rows);
+    if rows_per_image < rows {
+        return Err(CopyError::RowsPerImageTooSmall {
+            rows_per_image,
+            required: rows,
+        });
+    }
+    let rows_per_image = rows_per_image as usize;
+    // 最后一行只需要 row_size 个字节，溢出时一定超出了数据的范围
+    let required = if size.width == 0 || size.height == 0 || size.depth_or_array_layers == 0 {
+        Some(0)
+    } else {
+        rows_per_image
+            .checked_mul(size.depth_or_array_layers as usize - 1)
+            .and_then(|rows_before| rows_before.checked_add(rows as usize - 1))
+            .and_then(|rows_before| rows_before.checked_mul(bytes_per_row))
+            .and_then(|bytes| bytes.checked_add(row_size))
+    };
+    check_buffer_range(layout.offset, required.unwrap_or(usize::MAX), data_size)?;
+    Ok((bytes_per_row, rows_per_image))
+}
+
+fn check_texture_copy(
+    texture: &Texture,
+    mip_level: u32,
+    origin: Origin3d,
+    size: Extent3d,
+) -> Result<(), CopyError> {
+    if mip_level >= texture.mip_level_count {
+        return Err(CopyError::InvalidMipLevel {
+            mip_level,
+            mip_level_count: texture.mip_level_count,
+        });
+    }
+    // 块压缩格式的拷贝区域需要按块对齐，层级的大小向上取整到块的整数倍
+    let format = texture.format;
+    let (block_width, block_height) = format.block_dimensions();
+    for (value, alignment) in [
+        (origin.x, block_width),
+        (origin.y, block_height),
+        (size.width, block_width),
+        (size.height, block_height),
+    ] {
+        check_alignment(value as usize, alignment as usize)?;
+    }
+    let mut texture_size = texture.copy_extent(mip_level);
+    texture_size.width = texture_size.width.next_multiple_of(block_width);
+    texture_size.height = texture_size.height.next_multiple_of(block_height);
+    let exceeds =
+        |origin: u32, size: u32, limit: u32| origin.checked_add(size).is_none_or(|end| end > limit);
+    if exceeds(origin.x, size.width, texture_size.width)
+        || exceeds(origin.y, size.height, texture_size.height)
+        || exceeds(
+            origin.z,
+            size.depth_or_array_layers,
+            texture_size.depth_or_array_layers,
+        )
+    {
+        return Err(CopyError::TextureOutOfBounds {
+            origin,
+            size,
+            texture_size,
+        });
+    }
+    if (format.has_depth_aspect() || format.has_stencil_aspect())
+        && (origin.x != 0
+            || origin.y != 0
+            || size.width != texture_size.width
+            || size.height != texture_size.height)
+    {
+        return Err(CopyError::PartialDepthStencilCopy);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextureViewDimension;
+
+    fn texture(format: TextureFormat, width: u32, height: u32, layers: u32) -> Texture {
+        let mut texture = Texture {
+            width,
+            height,
+            depth_or_array_layers: layers,
+            dimension: if layers > 1 {
+                TextureViewDimension::D2Array
+            } else {
+                TextureViewDimension::D2
+            },
+            format,
+            mip_level_count: 1,
+            data: Vec::new(),
+        };
+        texture.data = vec![0; texture.size_in_bytes()];
+        texture
+    }
+
+    fn copy_texture<T>(texture: T, origin: Origin3d) -> ImageCopyTexture<T> {
+        ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin,
+        }
+    }
+
+    fn extent(width: u32, height: u32, depth_or_array_layers: u32) -> Extent3d {
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers,
+        }
+    }
+
+    #[test]
+    fn write_texture_with_padded_layout() {
+        let mut dst = texture(TextureFormat::Rgba8Unorm, 4, 4, 2);
+        // 2x2 的区域，每行 8 字节填充到 12 字节，每个图像 2 行填充到 3 行
+        let layout = ImageDataLayout {
+            offset: 4,
+            bytes_per_row: Some(12),
+            rows_per_image: Some(3),
+        };
+        let data: Vec<u8> = (0..4 + 12 * 4 + 8).map(|i| i as u8).collect();
+        let origin = Origin3d { x: 1, y: 2, z: 0 };
+        let size = extent(2, 2, 2);
+        write_texture(copy_texture(&mut dst, origin), &data, layout, size).unwrap();
+        for layer in 0..2 {
+            let level = dst.mip_level_data(layer, 0);
+            for y in 0..4 {
+                for x in 0..4 {
+                    let texel = &level[(y * 4 + x) * 4..][..4];
+                    if (1..3).contains(&x) && (2..4).contains(&y) {
+                        let src = 4 + (layer as usize * 3 + y - 2) * 12 + (x - 1) * 4;
+                        assert_eq!(texel, &data[src..src + 4], "({x}, {y}, {layer})");
+                    } else {
+                        assert_eq!(texel, [0; 4], "({x}, {y}, {layer})");
+                    }
+                }
+            }
+        }
+
+        // 最后一行只需要 8 个字节
+        let short = &data[..data.len() - 1];
+        assert_eq!(
+            write_texture(copy_texture(&mut dst, origin), short, layout, size),
+            Err(CopyError::BufferOutOfBounds {
+                offset: 4,
+                size: 56,
+                buffer_size: 59
+            })
+        );
+    }
+
+    #[test]
+    fn block_compressed_copy() {
+        // 8x8 的 BC1 纹理有 2x2 个块，每个块 8 字节
+        let mut src = texture(TextureFormat::Bc1RgbaUnorm, 8, 8, 1);
+        let block = [1, 2, 3, 4, 5, 6, 7, 8];
+        let origin = Origin3d { x: 4, y: 4, z: 0 };
+        let layout = ImageDataLayout::default();
+        write_texture(
+            copy_texture(&mut src, origin),
+            &block,
+            layout,
+            extent(4, 4, 1),
+        )
+        .unwrap();
+        assert_eq!(src.data[24..], block);
+        assert!(src.data[..24].iter().all(|&b| b == 0));
+
+        // 拷贝区域需要按块对齐
+        let unaligned = Origin3d { x: 2, y: 4, z: 0 };
+        assert_eq!(
+            write_texture(
+                copy_texture(&mut src, unaligned),
+                &block,
+                layout,
+                extent(4, 4, 1)
+            ),
+            Err(CopyError::Unaligned {
+                value: 2,
+                alignment: 4
+            })
+        );
+
+        // 只有 srgb 区别的压缩格式之间可以拷贝
+        let mut dst = texture(TextureFormat::Bc1RgbaUnormSrgb, 8, 8, 1);
+        let dst_origin = Origin3d { x: 0, y: 4, z: 0 };
+        copy_texture_to_texture(
+            copy_texture(&src, origin),
+            copy_texture(&mut dst, dst_origin),
+            extent(4, 4, 1),
+        )
+        .unwrap();
+        assert_eq!(dst.data[16..24], block);
+    }
+
+    #[test]
+    fn srgb_compatible_texture_copy() {
+        let mut src = texture(TextureFormat::Rgba8UnormSrgb, 2, 2, 1);
+        src.data = (0..16).collect();
+        let mut dst = texture(TextureFormat::Rgba8Unorm, 2, 2, 1);
+        copy_texture_to_texture(
+            copy_texture(&src, Origin3d::default()),
+            copy_texture(&mut dst, Origin3d::default()),
+            extent(2, 2, 1),
+        )
+        .unwrap();
+        // 按照字节拷贝，不做 srgb 转换
+        assert_eq!(dst.data, src.data);
+
+        let mut dst = texture(TextureFormat::Bgra8Unorm, 2, 2, 1);
+        assert_eq!(
+            copy_texture_to_texture(
+                copy_texture(&src, Origin3d::default()),
+                copy_texture(&mut dst, Origin3d::default()),
+                extent(2, 2, 1),
+            ),
+            Err(CopyError::FormatMismatch {
+                source: TextureFormat::Rgba8UnormSrgb,
+                destination: TextureFormat::Bgra8Unorm,
+            })
+        );
+    }
+
+    #[test]
+    fn buffer_copy_errors() {
+        let buffer = Buffer::new(8);
+        assert_eq!(
+            write_buffer(&buffer, 2, &[0; 4]),
+            Err(CopyError::Unaligned {
+                value: 2,
+                alignment: 4
+            })
+        );
+        assert_eq!(
+            write_buffer(&buffer, 8, &[0; 4]),
+            Err(CopyError::BufferOutOfBounds {
+                offset: 8,
+                size: 4,
+                buffer_size: 8
+            })
+        );
+        // offset + size 溢出
+        let offset = usize::MAX - 3;
+        assert_eq!(
+            copy_buffer_to_buffer(&buffer, 0, &buffer, offset, 8),
+            Err(CopyError::BufferOutOfBounds {
+                offset,
+                size: 8,
+                buffer_size: 8
+            })
+        );
+    }
+
+    #[test]
+    fn texture_copy_errors() {
+        let mut dst = texture(TextureFormat::Rgba8Unorm, 4, 4, 1);
+        let data = [0; 64];
+        let layout = ImageDataLayout::default();
+        let mut write = |mip_level, origin, layout, size| {
+            let texture = ImageCopyTexture {
+                texture: &mut dst,
+                mip_level,
+                origin,
+            };
+            write_texture(texture, &data, layout, size)
+        };
+        assert_eq!(
+            write(1, Origin3d::default(), layout, extent(1, 1, 1)),
+            Err(CopyError::InvalidMipLevel {
+                mip_level: 1,
+                mip_level_count: 1
+            })
+        );
+        // origin.x + width 溢出
+        let origin = Origin3d {
+            x: u32::MAX,
+            y: 0,
+            z: 0,
+        };
+        assert_eq!(
+            write(0, origin, layout, extent(1, 1, 1)),
+            Err(CopyError::TextureOutOfBounds {
+                origin,
+                size: extent(1, 1, 1),
+                texture_size: extent(4, 4, 1)
+            })
+        );
+        let padded = |bytes_per_row, rows_per_image| ImageDataLayout {
+            offset: 0,
+            bytes_per_row,
+            rows_per_image,
+        };
+        assert_eq!(
+            write(
+                0,
+                Origin3d::default(),
+                padded(Some(4), None),
+                extent(2, 2, 1)
+            ),
+            Err(CopyError::BytesPerRowTooSmall {
+                bytes_per_row: 4,
+                required: 8
+            })
+        );
+        assert_eq!(
+            write(
+                0,
+                Origin3d::default(),
+                padded(None, Some(1)),
+                extent(2, 2, 1)
+            ),
+            Err(CopyError::RowsPerImageTooSmall {
+                rows_per_image: 1,
+                required: 2
+            })
+        );
+
+        // bytes_per_row * rows_per_image 溢出
+        let mut dst = texture(TextureFormat::Rgba8Unorm, 4, 4, 3);
+        let destination = copy_texture(&mut dst, Origin3d::default());
+        assert_eq!(
+            write_texture(
+                destination,
+                &data,
+                padded(Some(u32::MAX), Some(u32::MAX)),
+                extent(1, 1, 3)
+            ),
+            Err(CopyError::BufferOutOfBounds {
+                offset: 0,
+                size: usize::MAX,
+                buffer_size: 64
+            })
+        );
+
+        let mut depth = texture(TextureFormat::Depth32Float, 4, 4, 1);
+        assert_eq!(
+            write_texture(
+                copy_texture(&mut depth, Origin3d::default()),
+                &data,
+                layout,
+                extent(2, 2, 1)
+            ),
+            Err(CopyError::PartialDepthStencilCopy)
+        );
+    }
+}
@@ -1,63 +1,252 @@
-use crate::bind_group::BindGroup;
+use crate::bind_group::{BindGroup, BindGroups, BindingError, PipelineLayout};
+use crate::buffer::Buffer;
 use crate::format::{TextureFormat, VertexFormat};
-use crate::shader::{FragmentInput, FragmentShader, ShaderType, VertexInput, VertexShader};
+use crate::indirect::{
+    read_indirect_args, DrawIndexedIndirectArgs, DrawIndirectArgs, IndirectError,
+};
+use crate::profiler::Profiler;
+use crate::query::{ActiveQuery, PipelineStatistics, QuerySet, QueryType};
+use crate::shader::{
+    FragmentInput, FragmentShader, ShaderType, VertexInput, VertexShader, MAX_CLIP_DISTANCES,
+};
+use crate::texture::{Texture, TextureViewDimension};
 use crate::VertexOutput;
-use bytemuck::cast_slice;
-use math::{Vec2, Vec4};
+use math::{Vec2, Vec3, Vec4};
+use std::fmt;
 use std::ops::Range;
+use std::time::{Duration, Instant};
 
 pub struct Renderer<'a> {
     pub state: RendererDescriptor<'a>,
     pub frame_buffer: Vec<u8>,
+    // 深度缓冲，存储的是已经按照深度格式量化后的值
     pub depth_buffer: Vec<f32>,
-    // 绑定组，感觉好像在软渲染中不太需要
-    pub bind_groups: Vec<BindGroup>,
+    // 模版缓冲，只有深度格式带模版时才会分配
+    pub stencil_buffer: Vec<u8>,
+    // 绑定组，着色器通过不可变引用访问
+    pub bind_groups: BindGroups,
     // 顶点缓冲区
     pub vertex_buffer: &'a [u8],
     // 顶点索引
     pub index_buffer: &'a [u32],
+    // 管线各个阶段的计数器
+    pub statistics: PipelineStatistics,
+    occlusion_query: Option<ActiveQuery<'a>>,
+    statistics_query: Option<ActiveQuery<'a>>,
+    // 为 Some 时记录每次绘制各个阶段的耗时
+    pub profiler: Option<Profiler>,
 }
 
 pub struct VertexState<'a> {
-    pub shader: VertexShader,
+    pub shader: &'a dyn VertexShader,
     // 这里直接简化掉顶点布局，layout数组表示顶点数据自定义的location的数量，每个location的长度由VertexFormta决定
     pub layout: &'a [VertexFormat],
 }
 
-pub struct FragmentState {
-    pub shader: FragmentShader,
+pub struct FragmentState<'a> {
+    pub shader: &'a dyn FragmentShader,
+    // 着色器是否读取 location_dpdx 和 location_dpdy，例如使用 texture_sample_grad 选择 mip 层级
+    // 为 false 时不计算导数，两者为空，省去每个片元两次额外的插值
+    pub derivatives: bool,
+}
+
+// 比较函数，用于深度测试和比较采样
+/// Corresponds to [WebGPU `GPUCompareFunction`](
+/// https://gpuweb.github.io/gpuweb/#enumdef-gpucomparefunction).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+impl CompareFunction {
+    // value 为新的值，reference 为已经存储的值
+    pub fn compare(&self, value: f32, reference: f32) -> bool {
+        match self {
+            CompareFunction::Never => false,
+            CompareFunction::Less => value < reference,
+            CompareFunction::Equal => value == reference,
+            CompareFunction::LessEqual => value <= reference,
+            CompareFunction::Greater => value > reference,
+            CompareFunction::NotEqual => value != reference,
+            CompareFunction::GreaterEqual => value >= reference,
+            CompareFunction::Always => true,
+        }
+    }
+}
+
+// 图元状态，对应 wgpu 的 PrimitiveState
+// 暂时只支持 triangle-list，不支持剔除
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PrimitiveState {
+    // 保守光栅化（overestimate），像素和三角形有任何重叠都会生成片元
+    // 三角形外的片元的插值结果是外推得到的
+    pub conservative: bool,
+}
+
+// 深度模版状态，对应 wgpu 的 DepthStencilState
+// 暂时只支持深度测试，模版测试未实施，但是会为带模版的格式分配模版缓冲
+pub struct DepthStencilState {
+    pub format: TextureFormat,
+    pub depth_write_enabled: bool,
+    pub depth_compare: CompareFunction,
+    // 深度缓冲的清除值，对应 wgpu RenderPassDepthStencilAttachment 中 depth_ops 的 LoadOp::Clear
+    // 使用 Less 和 LessEqual 时一般清除为 1.0
+    pub depth_clear_value: f32,
+}
+impl Default for DepthStencilState {
+    fn default() -> Self {
+        // bevy 使用 reverse z，越大越近，清除为最远的 0
+        Self {
+            format: TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::Greater,
+            depth_clear_value: 0.,
+        }
+    }
+}
+
+// 纹理的读取范围，对应 webgpu 的 GPUTextureAspect
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextureAspect {
+    #[default]
+    All,
+    StencilOnly,
+    DepthOnly,
 }
 
 pub struct RenderSurface {
     pub width: usize,
     pub height: usize,
     pub format: TextureFormat,
+    pub alpha_mode: CompositeAlphaMode,
 }
+
+// 表面的 alpha 合成方式，对应 wgpu 的 CompositeAlphaMode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CompositeAlphaMode {
+    // 忽略着色器输出的 alpha，颜色附件的 alpha 总是写入 1
+    #[default]
+    Opaque,
+    // 保留着色器输出的 alpha，颜色已经乘以 alpha
+    PreMultiplied,
+    // 保留着色器输出的 alpha，颜色没有乘以 alpha
+    PostMultiplied,
+}
+// 绘制时的错误，对应 wgpu 中绘制命令的校验错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DrawError {
+    // 绑定组没有设置或者和管线布局不匹配
+    Binding(BindingError),
+    // 间接参数的偏移没有对齐或者超出了缓冲的范围
+    Indirect(IndirectError),
+    // 片元着色器的输出不能写入颜色附件，例如没有输出或者输出了矩阵类型
+    InvalidFragmentOutput { location: usize },
+}
+impl From<BindingError> for DrawError {
+    fn from(value: BindingError) -> Self {
+        DrawError::Binding(value)
+    }
+}
+impl From<IndirectError> for DrawError {
+    fn from(value: IndirectError) -> Self {
+        DrawError::Indirect(value)
+    }
+}
+impl fmt::Display for DrawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawError::Binding(error) => write!(f, "{error}"),
+            DrawError::Indirect(error) => write!(f, "{error}"),
+            DrawError::InvalidFragmentOutput { location } => write!(
+                f,
+                "fragment output at location {location} can not be written to the color attachment"
+            ),
+        }
+    }
+}
+impl std::error::Error for DrawError {}
+
+// 图元组装和裁剪之后交给光栅化的三角形
+#[derive(Clone, Debug)]
+pub struct Primitive {
+    // 三个顶点在顶点输出中的下标，第一个顶点是 provoking vertex
+    pub indices: [u32; 3],
+    // 裁剪之前的三角形，用于计算重心坐标，没有被裁剪时和 indices 相同
+    pub triangle: [u32; 3],
+    // 图元在当前绘制实例中的序号，一个三角形被裁剪成多个三角形时序号相同
+    pub primitive_index: u32,
+    // provoking vertex 的 vertex_index
+    pub vertex_index: u32,
+}
+
 // 当前暂时先就不区分pipeline 和 renderpass
 pub struct RendererDescriptor<'a> {
     pub surface: RenderSurface,
+    // 管线布局，为 None 时不检查绑定组
+    pub layout: Option<&'a PipelineLayout>,
     pub vertex: VertexState<'a>,
-    pub fragment: FragmentState,
+    pub fragment: FragmentState<'a>,
+    pub primitive: PrimitiveState,
+    // 为 None 时不进行深度测试
+    pub depth_stencil: Option<DepthStencilState>,
+    // begin_occlusion_query 使用的查询集
+    pub occlusion_query_set: Option<&'a QuerySet>,
 }
 impl<'a> Renderer<'a> {
     pub fn new(desc: RendererDescriptor<'a>) -> Self {
         let pixel_count = desc.surface.height * desc.surface.width;
-        Renderer {
+        let stencil_count = match &desc.depth_stencil {
+            Some(depth_stencil) if depth_stencil.format.has_stencil_aspect() => pixel_count,
+            _ => 0,
+        };
+        let mut renderer = Renderer {
             frame_buffer: vec![0; pixel_count * desc.surface.format.size()],
             depth_buffer: vec![0.; pixel_count],
-            bind_groups: vec![vec![]; 10],
+            stencil_buffer: vec![0; stencil_count],
+            bind_groups: BindGroups::default(),
             vertex_buffer: &[],
             index_buffer: &[],
+            statistics: PipelineStatistics::default(),
+            occlusion_query: None,
+            statistics_query: None,
+            profiler: None,
             state: desc,
-        }
+        };
+        renderer.clear_depth_stencil();
+        renderer
+    }
+
+    // 把深度缓冲清除为 depth_clear_value，模版缓冲清除为 0
+    // 清除值和片元深度一样限制在 0-1 并按照深度格式量化
+    pub fn clear_depth_stencil(&mut self) {
+        let depth = match &self.state.depth_stencil {
+            Some(depth_stencil) => depth_stencil
+                .format
+                .quantize_depth(depth_stencil.depth_clear_value.clamp(0.0, 1.0)),
+            None => 0.,
+        };
+        self.depth_buffer.fill(depth);
+        self.stencil_buffer.fill(0);
     }
 
     pub fn set_vertex_buffer(&mut self, vertex_buffer: &'a [u8]) {
         self.vertex_buffer = vertex_buffer;
     }
 
-    pub fn set_bind_group(&mut self, index: usize, group: BindGroup) {
-        self.bind_groups.insert(index, group);
+    pub fn set_bind_group(
+        &mut self,
+        index: usize,
+        group: BindGroup,
+        offsets: &[u32],
+    ) -> Result<(), BindingError> {
+        self.bind_groups
+            .set_validated(self.state.layout, index, group, offsets)
     }
 
     pub fn set_index_buffer(&mut self, index_buffer: &'a [u32]) {
@@ -67,23 +256,218 @@ impl<'a> Renderer<'a> {
     // 按照WebGpu标准，渲染算法包括下面步骤
     // 索引解析 -- 顶点解析 -- 顶点处理 -- 图元组装 -- 图元裁剪 -- 光栅化 -- 片元解析 -- 深度解析 --绘制像素
     // https://gpuweb.github.io/gpuweb/#rendering-operations
-    pub fn draw(&mut self, vertices: Range<u32>) {
+    pub fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) -> Result<(), DrawError> {
+        self.validate_bind_groups()?;
+        let draw = self.begin_profiled_draw();
         let vertices: Vec<u32> = vertices.collect();
-        // 顶点处理，这里直接对所有顶点进行计算
-        let vertex_shader_outputs = self.vertex_processing(&vertices);
-        // 图元组装和裁剪
-        let primitive_index_list =
-            self.primitive_assembly_clipping(&vertices, &vertex_shader_outputs);
-        // 光栅化
-        self.rasterization(vertex_shader_outputs, primitive_index_list);
+        self.draw_vertices(&vertices, instances.clone())?;
+        self.end_profiled_draw(draw, vertices.len(), instances);
+        Ok(())
+    }
+
+    // base_vertex 会加到索引缓冲中读取的每个索引上
+    pub fn draw_indexed(
+        &mut self,
+        indices: Range<u32>,
+        base_vertex: i32,
+        instances: Range<u32>,
+    ) -> Result<(), DrawError> {
+        self.validate_bind_groups()?;
+        let draw = self.begin_profiled_draw();
+        let vertices: Vec<u32> = self.profile_stage("index_resolution", |renderer| {
+            renderer
+                .index_resolution(indices)
+                .into_iter()
+                .map(|index| index.wrapping_add_signed(base_vertex))
+                .collect()
+        });
+        self.draw_vertices(&vertices, instances.clone())?;
+        self.end_profiled_draw(draw, vertices.len(), instances);
+        Ok(())
+    }
+
+    // 间接绘制，绘制参数按照 DrawIndirectArgs 的布局从缓冲的 indirect_offset 处读取
+    pub fn draw_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: usize,
+    ) -> Result<(), DrawError> {
+        self.multi_draw_indirect(indirect_buffer, indirect_offset, 1)
+    }
+
+    pub fn draw_indexed_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: usize,
+    ) -> Result<(), DrawError> {
+        self.multi_draw_indexed_indirect(indirect_buffer, indirect_offset, 1)
+    }
+
+    // 连续执行 count 个间接绘制，参数在缓冲中紧密排列
+    // 顶点或者实例范围溢出 u32 的绘制和 wgpu 的间接参数校验一样被跳过
+    pub fn multi_draw_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: usize,
+        count: u32,
+    ) -> Result<(), DrawError> {
+        for args in read_indirect_args::<DrawIndirectArgs>(indirect_buffer, indirect_offset, count)?
+        {
+            let (first_vertex, first_instance) = (args.first_vertex, args.first_instance);
+            let (Some(vertex_end), Some(instance_end)) = (
+                first_vertex.checked_add(args.vertex_count),
+                first_instance.checked_add(args.instance_count),
+            ) else {
+                continue;
+            };
+            self.draw(first_vertex..vertex_end, first_instance..instance_end)?;
+        }
+        Ok(())
+    }
+
+    pub fn multi_draw_indexed_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: usize,
+        count: u32,
+    ) -> Result<(), DrawError> {
+        for args in
+            read_indirect_args::<DrawIndexedIndirectArgs>(indirect_buffer, indirect_offset, count)?
+        {
+            let (first_index, first_instance) = (args.first_index, args.first_instance);
+            let (Some(index_end), Some(instance_end)) = (
+                first_index.checked_add(args.index_count),
+                first_instance.checked_add(args.instance_count),
+            ) else {
+                continue;
+            };
+            self.draw_indexed(
+                first_index..index_end,
+                args.base_vertex,
+                first_instance..instance_end,
+            )?;
+        }
+        Ok(())
+    }
+
+    // 开始遮挡查询，统计之后的绘制中通过深度测试的采样数量
+    pub fn begin_occlusion_query(&mut self, query_index: u32) {
+        let query_set = self
+            .state
+            .occlusion_query_set
+            .expect("occlusion_query_set is not set in the renderer descriptor");
+        assert!(
+            query_set.ty == QueryType::Occlusion,
+            "occlusion_query_set is not an occlusion query set"
+        );
+        assert!(
+            self.occlusion_query.is_none(),
+            "an occlusion query is already active"
+        );
+        self.occlusion_query = Some(ActiveQuery::begin(query_set, query_index, self.statistics));
+    }
+
+    pub fn end_occlusion_query(&mut self) {
+        self.occlusion_query
+            .take()
+            .expect("no occlusion query is active")
+            .end(&self.statistics);
     }
 
-    pub fn draw_indexed(&mut self, indices: Range<u32>) {
-        let vertices = self.index_resolution(indices);
-        let vertex_shader_outputs = self.vertex_processing(&vertices);
-        let primitive_index_list =
-            self.primitive_assembly_clipping(&vertices, &vertex_shader_outputs);
-        self.rasterization(vertex_shader_outputs, primitive_index_list);
+    // 开始管线统计查询，统计之后的绘制中各个阶段的执行次数
+    pub fn begin_pipeline_statistics_query(&mut self, query_set: &'a QuerySet, query_index: u32) {
+        assert!(
+            matches!(query_set.ty, QueryType::PipelineStatistics(_)),
+            "query set is not a pipeline statistics query set"
+        );
+        assert!(
+            self.statistics_query.is_none(),
+            "a pipeline statistics query is already active"
+        );
+        self.statistics_query = Some(ActiveQuery::begin(query_set, query_index, self.statistics));
+    }
+
+    pub fn end_pipeline_statistics_query(&mut self) {
+        self.statistics_query
+            .take()
+            .expect("no pipeline statistics query is active")
+            .end(&self.statistics);
+    }
+
+    // 每个实例都会重新执行顶点处理、图元组装和光栅化
+    fn draw_vertices(&mut self, vertices: &[u32], instances: Range<u32>) -> Result<(), DrawError> {
+        for instance_index in instances {
+            // 顶点处理
+            let mut vertex_shader_outputs = self.profile_stage("vertex_processing", |renderer| {
+                renderer.vertex_processing(vertices, instance_index)
+            });
+            // 图元组装和裁剪
+            let primitive_count = vertices.len() / 3;
+            let primitive_index_list = self.profile_stage("clipping", |renderer| {
+                renderer.primitive_assembly_clipping(vertices, &mut vertex_shader_outputs)
+            });
+            self.statistics.clipper_invocations += primitive_count as u64;
+            self.statistics.clipper_primitives_out += primitive_index_list.len() as u64;
+            // 光栅化
+            // 光栅化自己记录耗时，片元着色和输出合并从光栅化的耗时中分出来
+            self.rasterization(vertex_shader_outputs, primitive_index_list)?;
+        }
+        Ok(())
+    }
+
+    // 在查询集中写入当前时刻的时间戳，可以在绘制之间调用来测量每次绘制的耗时
+    pub fn write_timestamp(&self, query_set: &QuerySet, query_index: u32) {
+        query_set.write_timestamp(query_index);
+    }
+
+    // 没有开启性能分析时不计时，返回绘制的编号和开始时刻
+    fn begin_profiled_draw(&mut self) -> Option<(u64, Instant)> {
+        let profiler = self.profiler.as_mut()?;
+        Some((profiler.next_draw(), Profiler::now()))
+    }
+
+    fn end_profiled_draw(
+        &mut self,
+        draw: Option<(u64, Instant)>,
+        vertex_count: usize,
+        instances: Range<u32>,
+    ) {
+        if let (Some(profiler), Some((draw, start))) = (&mut self.profiler, draw) {
+            profiler.record(
+                "draw",
+                "draw",
+                start,
+                start.elapsed(),
+                vec![
+                    ("draw", draw),
+                    ("vertex_count", vertex_count as u64),
+                    ("instance_count", instances.len() as u64),
+                ],
+            );
+        }
+    }
+
+    // 执行 f 并把耗时记录为 name 阶段
+    fn profile_stage<R>(&mut self, name: &'static str, f: impl FnOnce(&mut Self) -> R) -> R {
+        if self.profiler.is_none() {
+            return f(self);
+        }
+        let start = Profiler::now();
+        let result = f(self);
+        let duration = start.elapsed();
+        if let Some(profiler) = &mut self.profiler {
+            let draw = profiler.current_draw();
+            profiler.record(name, "stage", start, duration, vec![("draw", draw)]);
+        }
+        result
+    }
+
+    // 绘制前检查管线布局需要的绑定组是否都已经设置
+    fn validate_bind_groups(&self) -> Result<(), BindingError> {
+        match self.state.layout {
+            Some(layout) => layout.validate_bind_groups(&self.bind_groups),
+            None => Ok(()),
+        }
     }
 
     // 索引解析，返回代处理的顶点
@@ -94,8 +478,13 @@ impl<'a> Renderer<'a> {
         })
     }
 
-    //顶点处理
-    pub fn vertex_processing(&mut self, _vertices: &[u32]) -> Vec<VertexOutput> {
+    // 顶点处理，返回的输出和 vertices 一一对应
+    // 同一个顶点被多次引用时只执行一次顶点着色器
+    pub fn vertex_processing(
+        &mut self,
+        vertices: &[u32],
+        instance_index: u32,
+    ) -> Vec<VertexOutput> {
         let vertex_len = self
             .state
             .vertex
@@ -105,93 +494,94 @@ impl<'a> Renderer<'a> {
             .sum::<usize>();
 
         let vertex_count = self.vertex_buffer.len() / vertex_len;
-        // 顶点着色器输出
-        let mut vertex_shader_outputs = Vec::new();
-        for i in 0..vertex_count {
-            // 该顶点在顶点缓冲区中的索引
-            let mut vertex_buffer_offset = i as usize * vertex_len;
-            let mut vertex_locations: Vec<ShaderType> = Vec::new();
-
-            // 按照顶点的布局解析顶点用户自定义输入数据
-            for format in self.state.vertex.layout {
-                let format_value: ShaderType = match format {
-                    VertexFormat::Float32 => ShaderType::F32(
-                        cast_slice::<_, f32>(
-                            &self.vertex_buffer
-                                [vertex_buffer_offset..vertex_buffer_offset + format.size()],
-                        )[0],
-                    ),
-                    VertexFormat::Float32x2 => ShaderType::Vec2(
-                        std::convert::TryInto::<[f32; 2]>::try_into(cast_slice::<_, f32>(
-                            &self.vertex_buffer
-                                [vertex_buffer_offset..vertex_buffer_offset + format.size()],
-                        ))
-                        .unwrap()
-                        .into(),
-                    ),
-                    VertexFormat::Float32x3 => ShaderType::Vec3(
-                        std::convert::TryInto::<[f32; 3]>::try_into(cast_slice::<_, f32>(
-                            &self.vertex_buffer
-                                [vertex_buffer_offset..vertex_buffer_offset + format.size()],
-                        ))
-                        .unwrap()
-                        .into(),
-                    ),
-                    VertexFormat::Float32x4 => ShaderType::Vec4(
-                        std::convert::TryInto::<[f32; 4]>::try_into(cast_slice::<_, f32>(
-                            &self.vertex_buffer
-                                [vertex_buffer_offset..vertex_buffer_offset + format.size()],
-                        ))
-                        .unwrap()
-                        .into(),
-                    ),
-                    _ => panic!(""),
-                };
-                vertex_locations.push(format_value);
-                vertex_buffer_offset += format.size();
-            }
-
-            // 创建顶点着色器输入
-            let vertex_shader_input = VertexInput {
-                vertex_index: i as u32,
-                // 暂时不支持实例化渲染，永远为0
-                instance_index: 0,
-                location: vertex_locations,
-            };
+        let mut cache: Vec<Option<VertexOutput>> = vec![None; vertex_count];
+        let vertex_shader_outputs = vertices
+            .iter()
+            .map(|&vertex_index| {
+                cache[vertex_index as usize]
+                    .get_or_insert_with(|| {
+                        self.vertex_shading(vertex_index, instance_index, vertex_len)
+                    })
+                    .clone()
+            })
+            .collect();
+        self.statistics.vertex_shader_invocations += cache.iter().flatten().count() as u64;
+        vertex_shader_outputs
+    }
 
-            //执行顶点着色器
-            let vertex_shader_ouput =
-                (self.state.vertex.shader)(vertex_shader_input, &mut self.bind_groups);
+    // 解析单个顶点并执行顶点着色器
+    fn vertex_shading(
+        &self,
+        vertex_index: u32,
+        instance_index: u32,
+        vertex_len: usize,
+    ) -> VertexOutput {
+        // 该顶点在顶点缓冲区中的索引
+        let mut vertex_buffer_offset = vertex_index as usize * vertex_len;
+        let mut vertex_locations: Vec<ShaderType> = Vec::new();
 
-            vertex_shader_outputs.push(vertex_shader_ouput)
+        // 按照顶点的布局解析顶点用户自定义输入数据
+        for format in self.state.vertex.layout {
+            let format_value = format.decode(
+                &self.vertex_buffer[vertex_buffer_offset..vertex_buffer_offset + format.size()],
+            );
+            vertex_locations.push(format_value);
+            vertex_buffer_offset += format.size();
         }
-        vertex_shader_outputs
+
+        // 创建顶点着色器输入
+        let vertex_shader_input = VertexInput {
+            vertex_index,
+            instance_index,
+            location: vertex_locations,
+        };
+
+        //执行顶点着色器
+        self.state
+            .vertex
+            .shader
+            .main(vertex_shader_input, &self.bind_groups)
     }
 
     // 图元组装和裁剪
+    // vertex_shader_outputs 和 vertices 一一对应
+    // 裁剪产生的新顶点追加在 vertex_shader_outputs 的末尾
     pub fn primitive_assembly_clipping(
         &self,
         vertices: &[u32],
-        vertex_shader_outputs: &[VertexOutput],
-    ) -> Vec<Vec<u32>> {
+        vertex_shader_outputs: &mut Vec<VertexOutput>,
+    ) -> Vec<Primitive> {
         let mut primitive_list = Vec::new();
         for i in 0..vertices.len() / 3 {
             // 图元组装 我们这里只支持基础的三角形 "triangle-list"
-            let primitive = [
-                &vertex_shader_outputs[vertices[i * 3] as usize],
-                &vertex_shader_outputs[vertices[i * 3 + 1] as usize],
-                &vertex_shader_outputs[vertices[i * 3 + 2] as usize],
-            ];
+            let first = i as u32 * 3;
+            let triangle = [first, first + 1, first + 2];
+            let primitive = triangle.to_vec();
             // 图元裁剪
             // 顶点着色器会有输出 position(x,y,z,w)，我们在这里进行裁剪（其实就是齐次空间的视锥裁剪）
             // −p.w ≤ p.x ≤ p.w
             // −p.w ≤ p.y ≤ p.w
             // 0 ≤ p.z ≤ p.w (depth clipping)
             // tips ：按照标准 这里可以会产生新的顶点，但是 暂时未支持，图元有一个顶点在视锥范围外直接抛弃
-            if primitive_clipping(primitive.iter().map(|x| x.position).collect()) {
+            if primitive_clipping(
+                primitive
+                    .iter()
+                    .map(|&v| vertex_shader_outputs[v as usize].position)
+                    .collect(),
+            ) {
                 continue;
             }
-            primitive_list.push(vertices[i * 3..i * 3 + 3].to_vec());
+            // 用户裁剪平面
+            // 裁剪得到的凸多边形按照扇形拆分成三角形，第一个顶点保持为 provoking vertex
+            let polygon = clip_distances_clipping(vertex_shader_outputs, primitive);
+            for k in 1..polygon.len().saturating_sub(1) {
+                primitive_list.push(Primitive {
+                    indices: [polygon[0], polygon[k], polygon[k + 1]],
+                    triangle,
+                    primitive_index: i as u32,
+                    vertex_index: vertices[first as usize],
+                });
+            }
         }
         primitive_list
     }
@@ -200,23 +590,18 @@ impl<'a> Renderer<'a> {
     pub fn rasterization(
         &mut self,
         vertex_shader_outputs: Vec<VertexOutput>,
-        primitive_index_list: Vec<Vec<u32>>,
-    ) {
-        // 首先要得到vertex shader的输出布局，要映射到fragment shader的输入
-        let vertex_shader_ouput_layouts: Vec<VertexFormat> = vertex_shader_outputs[0]
-            .location
-            .iter()
-            .map(|&ty| match ty {
-                ShaderType::F32(_v) => VertexFormat::Float32,
-                ShaderType::Vec2(_v) => VertexFormat::Float32x2,
-                ShaderType::Vec3(_v) => VertexFormat::Float32x3,
-                ShaderType::Vec4(_v) => VertexFormat::Float32x4,
-            })
-            .collect();
+        primitive_index_list: Vec<Primitive>,
+    ) -> Result<(), DrawError> {
+        // 片元着色和输出合并穿插在每个片元中执行，只能累加耗时
+        // 记录时光栅化只包含三角形设置和覆盖测试，两个阶段依次放在光栅化之后，总耗时不重复计算
+        let raster_start = self.profiler.is_some().then(Profiler::now);
+        let mut fragment_shading = Duration::ZERO;
+        let mut output_merging = Duration::ZERO;
         // 得到当前图元的 index 索引
-        for primitive_index in primitive_index_list {
+        for primitive_info in primitive_index_list {
             // 拿到光栅图元
-            let mut primitive: Vec<VertexOutput> = primitive_index
+            let mut primitive: Vec<VertexOutput> = primitive_info
+                .indices
                 .iter()
                 .map(|&v| vertex_shader_outputs[v as usize].clone())
                 .collect();
@@ -245,12 +630,40 @@ impl<'a> Renderer<'a> {
             // 多边形光栅
             // cw 顺时针标准 ，area > 0 说明是正面，可以用来作为背面剔除的判断条件
             let area: f32 = calculate_polygon_area(&frame_buffer_coordinates);
+            // 面积为 0 的三角形不覆盖任何采样
+            if area == 0. {
+                continue;
+            }
 
             // 背面剔除
             // if area < 0. {
             //     continue;
             // }
 
+            // 裁剪之前的三角形的帧缓冲坐标，用于计算片元相对原三角形的重心坐标
+            let (triangle_coordinates, triangle_divisors): (Vec<Vec2>, Vec<f32>) = primitive_info
+                .triangle
+                .iter()
+                .map(|&v| self.viewport_transform(vertex_shader_outputs[v as usize].position))
+                .unzip();
+            let triangle_area = calculate_polygon_area(&triangle_coordinates);
+
+            // 保守光栅化时每条边向外平移半个像素的对角线投影，这样和三角形相交的像素中心都在平移后的三角形内
+            // 重心参数是边函数除以两倍面积，所以平移量也要除以两倍面积，对应的是边对面的顶点
+            let coverage_margins: Vec<f32> = if self.state.primitive.conservative {
+                let len = frame_buffer_coordinates.len();
+                let mut margins = vec![0.; len];
+                for i in 0..len {
+                    let edge =
+                        frame_buffer_coordinates[(i + 1) % len] - frame_buffer_coordinates[i];
+                    margins[(len - 1 + i) % len] =
+                        0.5 * (edge.x.abs() + edge.y.abs()) / (2. * area.abs());
+                }
+                margins
+            } else {
+                vec![0.; frame_buffer_coordinates.len()]
+            };
+
             // aabb 包围盒，左上 右下
             let aabb = calculate_polygon_aabb(&frame_buffer_coordinates);
 
@@ -267,7 +680,11 @@ impl<'a> Renderer<'a> {
                         area,
                     );
                     // 验证当前像素点是否在多边形里面
-                    if barycenter.iter().any(|&v| v < 0.) {
+                    if barycenter
+                        .iter()
+                        .zip(&coverage_margins)
+                        .any(|(&v, &margin)| v < -margin)
+                    {
                         continue;
                     }
 
@@ -284,22 +701,58 @@ impl<'a> Renderer<'a> {
 
                     // 这里可以直接执行early z
 
-                    // 对顶点着色器的用户自定义输入location进行插值给fragment shader 这里默认使用透视插值，暂时不支持其他插值
-                    let fragment_input_locations: Vec<ShaderType> = {
-                        let fragment_location_vec4 = primitive.iter().enumerate().fold(
-                            vec![Vec4::ZERO; vertex_shader_ouput_layouts.len()],
-                            |mut acc, (vindex, v)| {
-                                self.shader_to_vec4(&v.location)
-                                    .into_iter()
-                                    .enumerate()
-                                    .for_each(|(index, v)| {
-                                        acc[index] += v * correct_barycenter[vindex]
-                                    });
-                                acc
-                            },
-                        );
+                    // 没有被裁剪的三角形直接使用上面的重心参数
+                    let (barycentric_no_perspective, barycentric) =
+                        if primitive_info.indices == primitive_info.triangle {
+                            (barycenter.clone(), correct_barycenter.clone())
+                        } else {
+                            let barycenter = calculate_polygon_barycenter(
+                                fragment_position,
+                                &triangle_coordinates,
+                                triangle_area,
+                            );
+                            let correct_barycenter =
+                                perspective_correct(&barycenter, &triangle_divisors);
+                            (barycenter, correct_barycenter)
+                        };
 
-                        self.vec4_to_shader(fragment_location_vec4, &vertex_shader_ouput_layouts)
+                    // 对顶点着色器的用户自定义输入location进行插值给fragment shader
+                    // 浮点类型默认使用透视插值，整数和布尔类型按照WGSL标准使用flat插值
+                    let interpolate_locations = |weights: &[f32]| -> Vec<ShaderType> {
+                        (0..primitive[0].location.len())
+                            .map(|index| {
+                                let values: Vec<ShaderType> =
+                                    primitive.iter().map(|v| v.location[index]).collect();
+                                ShaderType::interpolate(&values, weights)
+                            })
+                            .collect()
+                    };
+                    let fragment_input_locations = interpolate_locations(&correct_barycenter);
+                    // 导数是相邻片元 (x+1,y) 和 (x,y+1) 的插值和当前片元的差，对应 WGSL 的 dpdx 和 dpdy
+                    // 插值是关于重心参数线性的，所以直接用重心参数的差插值
+                    let location_derivative = |offset: Vec2| {
+                        let neighbor = perspective_correct(
+                            &calculate_polygon_barycenter(
+                                fragment_position + offset,
+                                &frame_buffer_coordinates,
+                                area,
+                            ),
+                            &divisors,
+                        );
+                        let weights: Vec<f32> = neighbor
+                            .iter()
+                            .zip(&correct_barycenter)
+                            .map(|(neighbor, current)| neighbor - current)
+                            .collect();
+                        interpolate_locations(&weights)
+                    };
+                    let (location_dpdx, location_dpdy) = if self.state.fragment.derivatives {
+                        (
+                            location_derivative(Vec2::new(1., 0.)),
+                            location_derivative(Vec2::new(0., 1.)),
+                        )
+                    } else {
+                        (Vec::new(), Vec::new())
                     };
                     // 创建fragment shader输入
                     let fragment_input = FragmentInput {
@@ -312,44 +765,221 @@ impl<'a> Renderer<'a> {
                         ),
                         sample_index: 0, //暂时没有超采样
                         sample_mask: 0,
+                        primitive_index: primitive_info.primitive_index,
+                        vertex_index: primitive_info.vertex_index,
+                        barycentric: Vec3::new(barycentric[0], barycentric[1], barycentric[2]),
+                        barycentric_no_perspective: Vec3::new(
+                            barycentric_no_perspective[0],
+                            barycentric_no_perspective[1],
+                            barycentric_no_perspective[2],
+                        ),
                         location: fragment_input_locations,
+                        location_dpdx,
+                        location_dpdy,
                     };
                     // 顶点着色器执行
-                    let fragment_output =
-                        (self.state.fragment.shader)(fragment_input, &mut self.bind_groups);
+                    let shading_start = raster_start.map(|_| Instant::now());
+                    let fragment_output = self
+                        .state
+                        .fragment
+                        .shader
+                        .main(fragment_input, &self.bind_groups);
+                    self.statistics.fragment_shader_invocations += 1;
+                    let merging_start = raster_start.map(|_| Instant::now());
+                    if let (Some(shading_start), Some(merging_start)) =
+                        (shading_start, merging_start)
+                    {
+                        fragment_shading += merging_start - shading_start;
+                    }
+                    // 颜色附件只能写入标量和向量，矩阵输出在写入之前拒绝，和 wgpu 创建管线时的校验对应
+                    let color = match fragment_output.location.first() {
+                        Some(ShaderType::Mat3(_) | ShaderType::Mat4(_)) | None => {
+                            return Err(DrawError::InvalidFragmentOutput { location: 0 });
+                        }
+                        Some(&color) => color,
+                    };
 
                     let fragment_depth = fragment_output
                         .frag_depth
                         .unwrap_or(fragment_depth_perspective_interpolated)
                         .clamp(0.0, 1.0);
                     // 深度测试
-                    // z 值从 0-1 ,这里使用bevy（因为bevy使用reverse z）的标准，默认越大越近
-                    // 当然这里理论上应该是可以配置的，对应wgpu配置项  depth_compare: CompareFunction::Greater,
-                    if fragment_depth <= self.depth_buffer[y * self.state.surface.width + x] {
-                        continue;
+                    // z 值从 0-1 ,默认使用bevy（因为bevy使用reverse z）的标准，越大越近，对应 CompareFunction::Greater
+                    // 深度值先按照深度格式的精度量化，再和深度缓冲比较
+                    if let Some(depth_stencil) = &self.state.depth_stencil {
+                        let pixel_index = y * self.state.surface.width + x;
+                        let fragment_depth = depth_stencil.format.quantize_depth(fragment_depth);
+                        if !depth_stencil
+                            .depth_compare
+                            .compare(fragment_depth, self.depth_buffer[pixel_index])
+                        {
+                            if let Some(merging_start) = merging_start {
+                                output_merging += merging_start.elapsed();
+                            }
+                            continue;
+                        }
+                        // 深度写入
+                        if depth_stencil.depth_write_enabled {
+                            self.depth_buffer[pixel_index] = fragment_depth;
+                        }
                     }
-                    // 深度写入
-                    self.depth_buffer[y * self.state.surface.width + x] = fragment_depth;
-                    // 着色器输出loaction(0)是对应的color
-                    let fragment_color = fragment_output.location[0];
-                    let color = match fragment_color {
-                        ShaderType::Vec4(v) => v,
-                        _ => panic!("error fragment output location format"),
-                    };
+                    // 通过深度测试的采样，用于遮挡查询
+                    self.statistics.occlusion_samples += 1;
+                    // 着色器输出loaction(0)是对应的color，按照附件格式写入
                     // 还有模版测试 颜色混合等未实施
-                    self.draw_pixel(x, y, color);
+                    self.write_attachment(x, y, color);
+                    if let Some(merging_start) = merging_start {
+                        output_merging += merging_start.elapsed();
+                    }
                 }
             }
         }
+
+        if let (Some(profiler), Some(start)) = (&mut self.profiler, raster_start) {
+            let draw = profiler.current_draw();
+            let rasterization = start
+                .elapsed()
+                .saturating_sub(fragment_shading + output_merging);
+            profiler.record(
+                "rasterization",
+                "stage",
+                start,
+                rasterization,
+                vec![("draw", draw)],
+            );
+            profiler.record(
+                "fragment_shading",
+                "stage",
+                start + rasterization,
+                fragment_shading,
+                vec![("draw", draw)],
+            );
+            profiler.record(
+                "output_merging",
+                "stage",
+                start + rasterization + fragment_shading,
+                output_merging,
+                vec![("draw", draw)],
+            );
+        }
+        Ok(())
     }
 
-    pub fn draw_pixel(&mut self, x: usize, y: usize, color: Vec4) {
+    // 透视除法和视口变换，返回帧缓冲坐标和 1/w
+    fn viewport_transform(&self, position: Vec4) -> (Vec2, f32) {
+        let ndc = position.xy() / position.w;
+        (
+            Vec2::new(
+                self.state.surface.width as f32 * 0.5 * (ndc.x + 1.),
+                self.state.surface.height as f32 * 0.5 * (ndc.y + 1.),
+            ),
+            1. / position.w,
+        )
+    }
+
+    // 将片元着色器的输出写入帧缓冲，整数格式直接写入整数值，保证精确
+    fn write_attachment(&mut self, x: usize, y: usize, value: ShaderType) {
+        let format = self.state.surface.format;
+        if format.is_integer() {
+            let offset = (self.state.surface.width * y + x) * format.size();
+            format.encode_uint(
+                value.to_uvec4(),
+                &mut self.frame_buffer[offset..offset + format.size()],
+            );
+        } else {
+            self.draw_pixel(x, y, value.to_vec4());
+        }
+    }
+
+    // 按照帧缓冲的格式写入像素，Opaque 的表面 alpha 写入 1
+    pub fn draw_pixel(&mut self, x: usize, y: usize, mut color: Vec4) {
         let width = self.state.surface.width;
+        let format = self.state.surface.format;
+        if self.state.surface.alpha_mode == CompositeAlphaMode::Opaque {
+            color.w = 1.;
+        }
+        let offset = (width * y + x) * format.size();
+        format.encode(
+            color,
+            &mut self.frame_buffer[offset..offset + format.size()],
+        );
+    }
 
-        self.frame_buffer[(width * y + x) * 4] = (color.x * 255.) as u8;
-        self.frame_buffer[((width * y + x) * 4) + 1] = (color.y * 255.) as u8;
-        self.frame_buffer[((width * y + x) * 4) + 2] = (color.z * 255.) as u8;
-        self.frame_buffer[((width * y + x) * 4) + 3] = 255;
+    // 读取帧缓冲中的像素，按照格式解码
+    pub fn read_pixel(&self, x: usize, y: usize) -> Vec4 {
+        let width = self.state.surface.width;
+        let format = self.state.surface.format;
+        let offset = (width * y + x) * format.size();
+        format.decode(&self.frame_buffer[offset..offset + format.size()])
+    }
+
+    // 将帧缓冲复制到纹理中，可以作为下一个渲染通道的输入，或者用 copy_texture_to_buffer 读取其中的一部分
+    pub fn copy_frame_buffer_to_texture(&self) -> Texture {
+        Texture {
+            width: self.state.surface.width as u32,
+            height: self.state.surface.height as u32,
+            depth_or_array_layers: 1,
+            dimension: TextureViewDimension::D2,
+            format: self.state.surface.format,
+            mip_level_count: 1,
+            data: self.frame_buffer.clone(),
+        }
+    }
+
+    // 将深度缓冲复制到纹理中，返回的纹理可以直接绑定到绑定组中采样
+    // 按照webgpu标准，带模版的格式需要指定 aspect 分别复制深度和模版
+    pub fn copy_depth_to_texture(&self, aspect: TextureAspect) -> Texture {
+        let depth_format = self
+            .state
+            .depth_stencil
+            .as_ref()
+            .map(|depth_stencil| depth_stencil.format)
+            .unwrap_or(TextureFormat::Depth32Float);
+        let format = match (depth_format, aspect) {
+            (TextureFormat::Depth24PlusStencil8, TextureAspect::DepthOnly) => {
+                TextureFormat::Depth24Plus
+            }
+            (_, TextureAspect::StencilOnly) => {
+                assert!(
+                    depth_format.has_stencil_aspect(),
+                    "depth format {:?} has no stencil aspect",
+                    depth_format
+                );
+                TextureFormat::Stencil8
+            }
+            (format, _) => format,
+        };
+
+        let texel_size = format.size();
+        let mut data = vec![0; self.depth_buffer.len() * texel_size];
+        for (index, texel) in data.chunks_exact_mut(texel_size).enumerate() {
+            let stencil = self.stencil_buffer.get(index).copied().unwrap_or(0);
+            format.encode(
+                Vec4::new(self.depth_buffer[index], stencil as f32, 0., 1.),
+                texel,
+            );
+        }
+        Texture {
+            width: self.state.surface.width as u32,
+            height: self.state.surface.height as u32,
+            depth_or_array_layers: 1,
+            dimension: TextureViewDimension::D2,
+            format,
+            mip_level_count: 1,
+            data,
+        }
+    }
+
+    // 将深度缓冲线性化后导出为灰度图（每个像素一个字节），近处为黑色，远处为白色
+    // 深度按照 reverse z 透视投影还原：linear = near * far / (near + depth * (far - near))
+    pub fn depth_to_linear_grayscale(&self, near: f32, far: f32) -> Vec<u8> {
+        self.depth_buffer
+            .iter()
+            .map(|&depth| {
+                let linear = near * far / (near + depth * (far - near));
+                (((linear - near) / (far - near)).clamp(0., 1.) * 255.).round() as u8
+            })
+            .collect()
     }
 
     pub fn draw_line(&mut self, line: (Vec2, Vec2)) -> Option<()> {
@@ -408,32 +1038,6 @@ impl<'a> Renderer<'a> {
         }
         None
     }
-
-    fn vec4_to_shader(&self, vecs: Vec<Vec4>, vertex_layouts: &[VertexFormat]) -> Vec<ShaderType> {
-        vertex_layouts
-            .iter()
-            .enumerate()
-            .map(|(index, format)| match format {
-                VertexFormat::Float32 => ShaderType::F32(vecs[index].x()),
-                VertexFormat::Float32x2 => ShaderType::Vec2(vecs[index].xy()),
-                VertexFormat::Float32x3 => ShaderType::Vec3(vecs[index].xyz()),
-                VertexFormat::Float32x4 => ShaderType::Vec4(vecs[index]),
-                _ => panic!(""),
-            })
-            .collect()
-    }
-
-    fn shader_to_vec4(&self, shaders: &Vec<ShaderType>) -> Vec<Vec4> {
-        shaders
-            .iter()
-            .map(|&shader| match shader {
-                ShaderType::F32(val) => Vec4::new(val, 0., 0., 0.),
-                ShaderType::Vec2(val) => Vec4::new(val.x, val.y, 0., 0.),
-                ShaderType::Vec3(val) => Vec4::new(val.x, val.y, val.z, 0.),
-                ShaderType::Vec4(val) => val,
-            })
-            .collect()
-    }
 }
 
 const INSIDE: u8 = 0; // 0000
@@ -507,6 +1111,87 @@ fn primitive_clipping(vertex_positions: Vec<Vec4>) -> bool {
         .any(|v| v.x > v.w || v.x < -v.w || v.y > v.w || v.y < -v.w || v.z > v.w || v.z < 0.)
 }
 
+// 按照顶点的 clip_distances 依次用每个平面裁剪多边形（Sutherland–Hodgman）
+// 在齐次裁剪空间中线性插值得到新顶点，透视除法之前插值所以结果是透视正确的
+// https://gpuweb.github.io/gpuweb/#clip-distances
+fn clip_distances_clipping(
+    vertex_shader_outputs: &mut Vec<VertexOutput>,
+    polygon: Vec<u32>,
+) -> Vec<u32> {
+    let provoking = polygon[0] as usize;
+    let plane_count = vertex_shader_outputs[provoking].clip_distances.len();
+    assert!(
+        plane_count <= MAX_CLIP_DISTANCES,
+        "vertex output has {plane_count} clip distances, at most {MAX_CLIP_DISTANCES} are supported"
+    );
+    let mut polygon = polygon;
+    for plane in 0..plane_count {
+        let distance = |outputs: &[VertexOutput], v: u32| outputs[v as usize].clip_distances[plane];
+        if polygon
+            .iter()
+            .all(|&v| distance(vertex_shader_outputs, v) >= 0.)
+        {
+            continue;
+        }
+        let mut clipped = Vec::new();
+        for (i, &current) in polygon.iter().enumerate() {
+            let next = polygon[(i + 1) % polygon.len()];
+            let d0 = distance(vertex_shader_outputs, current);
+            let d1 = distance(vertex_shader_outputs, next);
+            if d0 >= 0. {
+                clipped.push(current);
+            }
+            // 边严格穿过平面时在交点处生成新的顶点，顶点正好在平面上时不会重复生成
+            if (d0 > 0. && d1 < 0.) || (d0 < 0. && d1 > 0.) {
+                let vertex = lerp_vertex_output(
+                    &vertex_shader_outputs[current as usize],
+                    &vertex_shader_outputs[next as usize],
+                    d0 / (d0 - d1),
+                    &vertex_shader_outputs[provoking],
+                );
+                vertex_shader_outputs.push(vertex);
+                clipped.push(vertex_shader_outputs.len() as u32 - 1);
+            }
+        }
+        if clipped.len() < 3 {
+            return Vec::new();
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+// 顶点输出的线性插值，flat 插值的值保持原图元 provoking vertex 的值
+fn lerp_vertex_output(
+    a: &VertexOutput,
+    b: &VertexOutput,
+    t: f32,
+    provoking: &VertexOutput,
+) -> VertexOutput {
+    VertexOutput {
+        position: a.position + (b.position - a.position) * t,
+        location: a
+            .location
+            .iter()
+            .zip(&b.location)
+            .zip(&provoking.location)
+            .map(|((&a, &b), &provoking)| {
+                if provoking.is_flat() {
+                    provoking
+                } else {
+                    ShaderType::interpolate(&[a, b], &[1. - t, t])
+                }
+            })
+            .collect(),
+        clip_distances: a
+            .clip_distances
+            .iter()
+            .zip(&b.clip_distances)
+            .map(|(&a, &b)| a + (b - a) * t)
+            .collect(),
+    }
+}
+
 // 多边形面积计算
 fn calculate_polygon_area(coordinates: &[Vec2]) -> f32 {
     let mut area = 0.;
@@ -531,11 +1216,12 @@ fn calculate_polygon_aabb(coordinates: &[Vec2]) -> [usize; 4] {
 
 // 计算点p对于平面多边形的重心坐标
 // https://gpuweb.github.io/gpuweb/#barycentric-coordinates
+// 叉积是子三角形面积的两倍，area 是 calculate_polygon_area 返回的面积，所以除以两倍面积使权重之和为 1
 fn calculate_polygon_barycenter(p: Vec2, polygon: &[Vec2], area: f32) -> Vec<f32> {
     let polygon_len = polygon.len();
     let mut res = vec![0.; polygon_len];
     for i in 0..polygon_len {
-        let lamda = (p - polygon[i]).cross(p - polygon[(i + 1) % polygon_len]) / area;
+        let lamda = (p - polygon[i]).cross(p - polygon[(i + 1) % polygon_len]) / (2. * area);
         res[(polygon_len - 1 + i) % polygon_len] = lamda;
     }
     res
@@ -553,3 +1239,349 @@ pub fn interpolate(val: &[f32], weights: &[f32]) -> f32 {
     assert!(val.len() == weights.len());
     (0..val.len()).fold(0., |acc, index| acc + val[index] * weights[index])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FragmentOutput;
+
+    // 深度为 0.5 的两个三角形覆盖整个 4x4 的表面
+    const QUAD: [[f32; 3]; 6] = [
+        [-1., -1., 0.5],
+        [1., -1., 0.5],
+        [1., 1., 0.5],
+        [-1., -1., 0.5],
+        [1., 1., 0.5],
+        [-1., 1., 0.5],
+    ];
+
+    // location(0) 为裁剪空间的 xy
+    fn vertex_main(vertex_input: VertexInput, _bind_groups: &BindGroups) -> VertexOutput {
+        let position: Vec3 = vertex_input.location[0].into();
+        VertexOutput {
+            location: vec![ShaderType::Vec2(Vec2::new(position.x, position.y))],
+            position: position.extend(1.),
+            clip_distances: Vec::new(),
+        }
+    }
+
+    fn fragment_main(_input: FragmentInput, _bind_groups: &BindGroups) -> FragmentOutput {
+        FragmentOutput {
+            frag_depth: None,
+            sample_mask: 0,
+            location: vec![ShaderType::Vec4(Vec4::ONE)],
+        }
+    }
+
+    fn quad_renderer(
+        fragment: FragmentState<'static>,
+        depth_stencil: Option<DepthStencilState>,
+        format: TextureFormat,
+        alpha_mode: CompositeAlphaMode,
+    ) -> Renderer<'static> {
+        let mut renderer = Renderer::new(RendererDescriptor {
+            surface: RenderSurface {
+                width: 4,
+                height: 4,
+                format,
+                alpha_mode,
+            },
+            layout: None,
+            vertex: VertexState {
+                shader: &vertex_main,
+                layout: &[VertexFormat::Float32x3],
+            },
+            fragment,
+            primitive: PrimitiveState::default(),
+            depth_stencil,
+            occlusion_query_set: None,
+        });
+        renderer.set_vertex_buffer(bytemuck::cast_slice(&QUAD));
+        renderer
+    }
+
+    fn draw_quad(
+        fragment: FragmentState<'static>,
+        depth_stencil: Option<DepthStencilState>,
+        format: TextureFormat,
+        alpha_mode: CompositeAlphaMode,
+    ) -> Renderer<'static> {
+        let mut renderer = quad_renderer(fragment, depth_stencil, format, alpha_mode);
+        renderer.draw(0..6, 0..1).unwrap();
+        renderer
+    }
+
+    // 返回被绘制的像素数量和深度缓冲
+    fn draw_depth_quad(depth_stencil: DepthStencilState) -> (usize, Vec<f32>) {
+        let fragment = FragmentState {
+            shader: &fragment_main,
+            derivatives: false,
+        };
+        let renderer = draw_quad(
+            fragment,
+            Some(depth_stencil),
+            TextureFormat::Rgba8Unorm,
+            CompositeAlphaMode::Opaque,
+        );
+        let drawn = renderer
+            .frame_buffer
+            .chunks_exact(4)
+            .filter(|texel| texel[0] == 255)
+            .count();
+        (drawn, renderer.depth_buffer)
+    }
+
+    #[test]
+    fn depth_less_with_clear_value() {
+        let (drawn, depth) = draw_depth_quad(DepthStencilState {
+            depth_compare: CompareFunction::Less,
+            depth_clear_value: 1.,
+            ..Default::default()
+        });
+        assert_eq!(drawn, 16);
+        assert!(depth.iter().all(|&d| d == 0.5));
+
+        // 默认清除为 0，Less 不会通过深度测试
+        let (drawn, depth) = draw_depth_quad(DepthStencilState {
+            depth_compare: CompareFunction::Less,
+            ..Default::default()
+        });
+        assert_eq!(drawn, 0);
+        assert!(depth.iter().all(|&d| d == 0.));
+
+        let (drawn, _) = draw_depth_quad(DepthStencilState::default());
+        assert_eq!(drawn, 16);
+    }
+
+    #[test]
+    fn depth_clear_value_is_quantized() {
+        let (_, depth) = draw_depth_quad(DepthStencilState {
+            format: TextureFormat::Depth16Unorm,
+            depth_compare: CompareFunction::Never,
+            depth_clear_value: 0.3,
+            ..Default::default()
+        });
+        assert!(depth
+            .iter()
+            .all(|&d| d == (0.3f32 * 65535.).round() / 65535.));
+    }
+
+    #[test]
+    fn polygon_barycenter_weights_sum_to_one() {
+        let triangle = [Vec2::new(10., 2.), Vec2::new(3., 17.), Vec2::new(25., 12.)];
+        let area = calculate_polygon_area(&triangle);
+        for p in [Vec2::new(12., 10.), Vec2::new(0., 0.), Vec2::new(30., 20.)] {
+            let barycenter = calculate_polygon_barycenter(p, &triangle, area);
+            assert!((barycenter.iter().sum::<f32>() - 1.).abs() < 1e-5);
+            // 重心参数插值顶点坐标得到原来的点
+            let x = interpolate(&triangle.map(|v| v.x), &barycenter);
+            let y = interpolate(&triangle.map(|v| v.y), &barycenter);
+            assert!((x - p.x).abs() < 1e-4 && (y - p.y).abs() < 1e-4);
+        }
+        // 顺时针的三角形面积为负，重心参数不变
+        let reversed = [triangle[0], triangle[2], triangle[1]];
+        let area = calculate_polygon_area(&reversed);
+        let barycenter = calculate_polygon_barycenter(Vec2::new(12., 10.), &reversed, area);
+        assert!((barycenter.iter().sum::<f32>() - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn polygon_barycenter_at_vertex() {
+        let triangle = [Vec2::new(10., 2.), Vec2::new(3., 17.), Vec2::new(25., 12.)];
+        let area = calculate_polygon_area(&triangle);
+        for (i, &vertex) in triangle.iter().enumerate() {
+            let barycenter = calculate_polygon_barycenter(vertex, &triangle, area);
+            for (j, &weight) in barycenter.iter().enumerate() {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((weight - expected).abs() < 1e-6, "{barycenter:?}");
+            }
+        }
+    }
+
+    fn translucent_fragment_main(
+        _input: FragmentInput,
+        _bind_groups: &BindGroups,
+    ) -> FragmentOutput {
+        FragmentOutput {
+            frag_depth: None,
+            sample_mask: 0,
+            location: vec![ShaderType::Vec4(Vec4::new(1., 0.5, 0., 0.5))],
+        }
+    }
+
+    #[test]
+    fn surface_alpha_mode() {
+        let fragment = || FragmentState {
+            shader: &translucent_fragment_main,
+            derivatives: false,
+        };
+        // 默认的 Opaque 忽略着色器输出的 alpha
+        let format = TextureFormat::Rgba8Unorm;
+        let renderer = draw_quad(fragment(), None, format, CompositeAlphaMode::Opaque);
+        assert_eq!(renderer.frame_buffer[..4], [255, 128, 0, 255]);
+        let renderer = draw_quad(fragment(), None, format, CompositeAlphaMode::PostMultiplied);
+        assert_eq!(renderer.frame_buffer[..4], [255, 128, 0, 128]);
+
+        let format = TextureFormat::Rgba16Float;
+        let renderer = draw_quad(fragment(), None, format, CompositeAlphaMode::Opaque);
+        assert_eq!(renderer.read_pixel(0, 0).w, 1.);
+        let renderer = draw_quad(fragment(), None, format, CompositeAlphaMode::PreMultiplied);
+        assert_eq!(renderer.read_pixel(0, 0).w, 0.5);
+    }
+
+    // 导数为空时输出 -1，否则输出 location(0) 的 dpdx 和 dpdy
+    fn derivative_fragment_main(input: FragmentInput, _bind_groups: &BindGroups) -> FragmentOutput {
+        let color = match (input.location_dpdx.first(), input.location_dpdy.first()) {
+            (Some(&dpdx), Some(&dpdy)) => {
+                let dpdx: Vec2 = dpdx.into();
+                let dpdy: Vec2 = dpdy.into();
+                Vec4::new(dpdx.x, dpdx.y, dpdy.x, dpdy.y)
+            }
+            _ => Vec4::splat(-1.),
+        };
+        FragmentOutput {
+            frag_depth: None,
+            sample_mask: 0,
+            location: vec![ShaderType::Vec4(color)],
+        }
+    }
+
+    #[test]
+    fn derivatives_only_when_requested() {
+        let fragment = FragmentState {
+            shader: &derivative_fragment_main,
+            derivatives: false,
+        };
+        let renderer = draw_quad(
+            fragment,
+            None,
+            TextureFormat::Rgba32Float,
+            CompositeAlphaMode::PostMultiplied,
+        );
+        assert_eq!(renderer.read_pixel(1, 2).x, -1.);
+
+        // 4 个像素覆盖裁剪空间的 [-1, 1]，视口变换没有翻转 y 轴
+        let fragment = FragmentState {
+            shader: &derivative_fragment_main,
+            derivatives: true,
+        };
+        let renderer = draw_quad(
+            fragment,
+            None,
+            TextureFormat::Rgba32Float,
+            CompositeAlphaMode::PostMultiplied,
+        );
+        let derivative = renderer.read_pixel(1, 2);
+        assert!((derivative - Vec4::new(0.5, 0., 0., 0.5)).length() < 1e-5);
+    }
+
+    fn matrix_fragment_main(_input: FragmentInput, _bind_groups: &BindGroups) -> FragmentOutput {
+        FragmentOutput {
+            frag_depth: None,
+            sample_mask: 0,
+            location: vec![ShaderType::Mat4(math::Mat4::IDENTITY)],
+        }
+    }
+
+    #[test]
+    fn matrix_fragment_output_is_rejected() {
+        let fragment = || FragmentState {
+            shader: &matrix_fragment_main,
+            derivatives: false,
+        };
+        // 浮点和整数附件都不能写入矩阵
+        for format in [TextureFormat::Rgba8Unorm, TextureFormat::R32Uint] {
+            let mut renderer = quad_renderer(fragment(), None, format, CompositeAlphaMode::Opaque);
+            assert_eq!(
+                renderer.draw(0..6, 0..1),
+                Err(DrawError::InvalidFragmentOutput { location: 0 })
+            );
+        }
+    }
+
+    #[test]
+    fn multi_draw_indirect_skips_overflowing_draws() {
+        let fragment = || FragmentState {
+            shader: &fragment_main,
+            derivatives: false,
+        };
+        let format = TextureFormat::Rgba8Unorm;
+        let expected = draw_quad(fragment(), None, format, CompositeAlphaMode::Opaque);
+        let mut renderer = quad_renderer(fragment(), None, format, CompositeAlphaMode::Opaque);
+        let draws = [
+            DrawIndirectArgs {
+                vertex_count: 6,
+                instance_count: 1,
+                first_vertex: u32::MAX,
+                first_instance: 0,
+            },
+            DrawIndirectArgs {
+                vertex_count: 6,
+                instance_count: 1,
+                first_vertex: 0,
+                first_instance: u32::MAX,
+            },
+            DrawIndirectArgs {
+                vertex_count: 6,
+                instance_count: 1,
+                first_vertex: 0,
+                first_instance: 0,
+            },
+        ];
+        let buffer = Buffer::new(draws.len() * 16);
+        for (i, draw) in draws.iter().enumerate() {
+            buffer.write(i * 16, draw);
+        }
+        renderer.multi_draw_indirect(&buffer, 0, 3).unwrap();
+        // 只执行了最后一个绘制
+        assert_eq!(
+            renderer.statistics.fragment_shader_invocations,
+            expected.statistics.fragment_shader_invocations
+        );
+
+        assert_eq!(
+            renderer.multi_draw_indirect(&buffer, 2, 1),
+            Err(DrawError::Indirect(IndirectError::Unaligned { offset: 2 }))
+        );
+        assert_eq!(
+            renderer.draw_indirect(&buffer, 48),
+            Err(DrawError::Indirect(IndirectError::OutOfBounds {
+                offset: 48,
+                count: 1,
+                buffer_size: 48
+            }))
+        );
+    }
+
+    #[test]
+    fn profiled_stages_do_not_overlap() {
+        let fragment = FragmentState {
+            shader: &fragment_main,
+            derivatives: false,
+        };
+        let format = TextureFormat::Rgba8Unorm;
+        let mut renderer = quad_renderer(fragment, None, format, CompositeAlphaMode::Opaque);
+        renderer.profiler = Some(Profiler::new());
+        renderer.draw(0..6, 0..1).unwrap();
+        let events = renderer.profiler.unwrap().events;
+        let stages: Vec<_> = events.iter().filter(|e| e.category == "stage").collect();
+        let names: Vec<_> = stages.iter().map(|e| e.name).collect();
+        assert_eq!(
+            names,
+            [
+                "vertex_processing",
+                "clipping",
+                "rasterization",
+                "fragment_shading",
+                "output_merging"
+            ]
+        );
+        // 各个阶段依次执行，阶段的总耗时不超过整个绘制的耗时
+        for pair in stages.windows(2) {
+            assert!(pair[0].start + pair[0].duration <= pair[1].start);
+        }
+        let draw = events.iter().find(|e| e.name == "draw").unwrap();
+        let total: Duration = stages.iter().map(|e| e.duration).sum();
+        assert!(total <= draw.duration);
+    }
+}
@@ -0,0 +1,169 @@
+use math::Vec4;
+
+use crate::CompareFunction;
+
+/// How edges should be handled in texture addressing.
+///
+/// Corresponds to [WebGPU `GPUAddressMode`](
+/// https://gpuweb.github.io/gpuweb/#enumdef-gpuaddressmode).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AddressMode {
+    // 超出范围的坐标取边缘的纹素
+    #[default]
+    ClampToEdge,
+    // 重复纹理
+    Repeat,
+    // 重复纹理，每次重复时镜像翻转
+    MirrorRepeat,
+    // 超出范围的坐标取 border_color
+    ClampToBorder,
+}
+impl AddressMode {
+    // 把纹素坐标映射到 [0, size) 范围内，ClampToBorder 超出范围时返回 None
+    pub fn apply(&self, coord: i64, size: u32) -> Option<u32> {
+        let size = size as i64;
+        let coord = match self {
+            AddressMode::ClampToEdge => coord.clamp(0, size - 1),
+            AddressMode::Repeat => coord.rem_euclid(size),
+            AddressMode::MirrorRepeat => {
+                let coord = coord.rem_euclid(2 * size);
+                if coord < size {
+                    coord
+                } else {
+                    2 * size - 1 - coord
+                }
+            }
+            AddressMode::ClampToBorder => {
+                if coord < 0 || coord >= size {
+                    return None;
+                }
+                coord
+            }
+        };
+        Some(coord as u32)
+    }
+}
+
+/// Texel mixing mode when sampling between texels.
+///
+/// Corresponds to [WebGPU `GPUFilterMode`](
+/// https://gpuweb.github.io/gpuweb/#enumdef-gpufiltermode).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FilterMode {
+    // 取最近的一个纹素
+    #[default]
+    Nearest,
+    // 相邻纹素之间线性插值
+    Linear,
+}
+
+// 对应 wgpu 的 SamplerBorderColor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SamplerBorderColor {
+    #[default]
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+    // 所有分量都为 0，这里没有区分格式，和 TransparentBlack 一样
+    Zero,
+}
+impl SamplerBorderColor {
+    pub fn to_vec4(&self) -> Vec4 {
+        match self {
+            SamplerBorderColor::TransparentBlack | SamplerBorderColor::Zero => Vec4::ZERO,
+            SamplerBorderColor::OpaqueBlack => Vec4::new(0., 0., 0., 1.),
+            SamplerBorderColor::OpaqueWhite => Vec4::ONE,
+        }
+    }
+}
+
+// 采样器描述，对应 wgpu 的 SamplerDescriptor，默认值也和 wgpu 一致
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerDescriptor {
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    pub address_mode_w: AddressMode,
+    // 放大（lod <= 0）时使用的过滤方式
+    pub mag_filter: FilterMode,
+    // 缩小（lod > 0）时使用的过滤方式
+    pub min_filter: FilterMode,
+    // mip 层级之间的过滤方式
+    pub mipmap_filter: FilterMode,
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+    // 不为 None 时是比较采样器，只能用于 texture_sample_compare
+    pub compare: Option<CompareFunction>,
+    // 各向异性过滤的最大采样数，为 1 时不开启，大于 1 时三个过滤方式都需要是 Linear
+    pub anisotropy_clamp: u16,
+    // 只在 address mode 为 ClampToBorder 时使用，为 None 时使用 TransparentBlack
+    pub border_color: Option<SamplerBorderColor>,
+}
+impl Default for SamplerDescriptor {
+    fn default() -> Self {
+        Self {
+            address_mode_u: AddressMode::default(),
+            address_mode_v: AddressMode::default(),
+            address_mode_w: AddressMode::default(),
+            mag_filter: FilterMode::default(),
+            min_filter: FilterMode::default(),
+            mipmap_filter: FilterMode::default(),
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sampler {
+    pub state: SamplerDescriptor,
+}
+impl Sampler {
+    pub fn new(desc: SamplerDescriptor) -> Self {
+        assert!(
+            desc.lod_min_clamp >= 0. && desc.lod_max_clamp >= desc.lod_min_clamp,
+            "invalid lod clamp {}..{}",
+            desc.lod_min_clamp,
+            desc.lod_max_clamp
+        );
+        assert!(
+            desc.anisotropy_clamp <= 1
+                || (desc.mag_filter == FilterMode::Linear
+                    && desc.min_filter == FilterMode::Linear
+                    && desc.mipmap_filter == FilterMode::Linear),
+            "anisotropic filtering requires all filter modes to be linear"
+        );
+        Self { state: desc }
+    }
+
+    pub fn border_color(&self) -> Vec4 {
+        self.state.border_color.unwrap_or_default().to_vec4()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_mode_edges() {
+        // 大小为 4 时，依次为 -5 -1 0 3 4 7 8 的映射结果
+        let coords = [-5, -1, 0, 3, 4, 7, 8];
+        let apply = |mode: AddressMode| coords.map(|coord| mode.apply(coord, 4));
+        assert_eq!(
+            apply(AddressMode::ClampToEdge),
+            [0, 0, 0, 3, 3, 3, 3].map(Some)
+        );
+        assert_eq!(apply(AddressMode::Repeat), [3, 3, 0, 3, 0, 3, 0].map(Some));
+        assert_eq!(
+            apply(AddressMode::MirrorRepeat),
+            [3, 0, 0, 3, 3, 0, 0].map(Some)
+        );
+        assert_eq!(
+            apply(AddressMode::ClampToBorder),
+            [None, None, Some(0), Some(3), None, None, None]
+        );
+    }
+}
@@ -1,13 +1,118 @@
-use math::{Vec2, Vec3, Vec4};
+use math::{IVec2, IVec3, IVec4, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
 
-use crate::bind_group::BindGroup;
+use crate::bind_group::BindGroups;
+use crate::compute::Workgroup;
 
-#[derive(Clone, Copy)]
+// 着色器中阶段间传递和顶点输入的值的类型
+// 按照WGSL标准，整数和布尔类型不进行插值，使用 flat 插值（取图元第一个顶点的值）
+// https://www.w3.org/TR/WGSL/#interpolation
+#[derive(Clone, Copy, Debug)]
 pub enum ShaderType {
     F32(f32),
     Vec2(Vec2),
     Vec3(Vec3),
     Vec4(Vec4),
+    U32(u32),
+    UVec2(UVec2),
+    UVec3(UVec3),
+    UVec4(UVec4),
+    I32(i32),
+    IVec2(IVec2),
+    IVec3(IVec3),
+    IVec4(IVec4),
+    Bool(bool),
+    // 矩阵类型只用于顶点输入，会占用多个连续的location（每一列一个）
+    Mat3(Mat3),
+    Mat4(Mat4),
+}
+impl ShaderType {
+    // 是否需要使用 flat 插值
+    pub fn is_flat(&self) -> bool {
+        !matches!(
+            self,
+            ShaderType::F32(_)
+                | ShaderType::Vec2(_)
+                | ShaderType::Vec3(_)
+                | ShaderType::Vec4(_)
+                | ShaderType::Mat3(_)
+                | ShaderType::Mat4(_)
+        )
+    }
+
+    // 对图元各个顶点的值进行插值，整数类型直接使用第一个顶点（provoking vertex）的值
+    pub fn interpolate(values: &[ShaderType], weights: &[f32]) -> ShaderType {
+        assert!(values.len() == weights.len());
+        let provoking = values[0];
+        if provoking.is_flat() {
+            return provoking;
+        }
+        let sum = |f: fn(&ShaderType) -> Vec4| {
+            values
+                .iter()
+                .zip(weights)
+                .fold(Vec4::ZERO, |acc, (v, &w)| acc + f(v) * w)
+        };
+        match provoking {
+            ShaderType::F32(_) => ShaderType::F32(sum(|v| v.to_vec4()).x),
+            ShaderType::Vec2(_) => ShaderType::Vec2(sum(|v| v.to_vec4()).xy()),
+            ShaderType::Vec3(_) => ShaderType::Vec3(sum(|v| v.to_vec4()).xyz()),
+            ShaderType::Vec4(_) => ShaderType::Vec4(sum(|v| v.to_vec4())),
+            ShaderType::Mat3(_) => ShaderType::Mat3(
+                values
+                    .iter()
+                    .zip(weights)
+                    .fold(Mat3::ZERO, |acc, (v, &w)| acc + Mat3::from(*v) * w),
+            ),
+            ShaderType::Mat4(_) => ShaderType::Mat4(
+                values
+                    .iter()
+                    .zip(weights)
+                    .fold(Mat4::ZERO, |acc, (v, &w)| acc + Mat4::from(*v) * w),
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    // 转换为浮点的四维向量，缺少的分量补齐为(0,0,0,1)
+    pub fn to_vec4(&self) -> Vec4 {
+        match *self {
+            ShaderType::F32(v) => Vec4::new(v, 0., 0., 1.),
+            ShaderType::Vec2(v) => Vec4::new(v.x, v.y, 0., 1.),
+            ShaderType::Vec3(v) => v.extend(1.),
+            ShaderType::Vec4(v) => v,
+            ShaderType::U32(v) => Vec4::new(v as f32, 0., 0., 1.),
+            ShaderType::UVec2(v) => Vec4::new(v.x as f32, v.y as f32, 0., 1.),
+            ShaderType::UVec3(v) => Vec4::new(v.x as f32, v.y as f32, v.z as f32, 1.),
+            ShaderType::UVec4(v) => Vec4::new(v.x as f32, v.y as f32, v.z as f32, v.w as f32),
+            ShaderType::I32(v) => Vec4::new(v as f32, 0., 0., 1.),
+            ShaderType::IVec2(v) => Vec4::new(v.x as f32, v.y as f32, 0., 1.),
+            ShaderType::IVec3(v) => Vec4::new(v.x as f32, v.y as f32, v.z as f32, 1.),
+            ShaderType::IVec4(v) => Vec4::new(v.x as f32, v.y as f32, v.z as f32, v.w as f32),
+            ShaderType::Bool(v) => Vec4::new(v as u32 as f32, 0., 0., 1.),
+            ShaderType::Mat3(_) | ShaderType::Mat4(_) => {
+                panic!("matrix can not be converted to vec4")
+            }
+        }
+    }
+
+    // 转换为无符号整数的四维向量，用于写入整数格式的附件，缺少的分量补齐为(0,0,0,1)
+    pub fn to_uvec4(&self) -> UVec4 {
+        match *self {
+            ShaderType::U32(v) => UVec4::new(v, 0, 0, 1),
+            ShaderType::UVec2(v) => UVec4::new(v.x, v.y, 0, 1),
+            ShaderType::UVec3(v) => UVec4::new(v.x, v.y, v.z, 1),
+            ShaderType::UVec4(v) => v,
+            ShaderType::I32(v) => UVec4::new(v as u32, 0, 0, 1),
+            ShaderType::IVec2(v) => UVec4::new(v.x as u32, v.y as u32, 0, 1),
+            ShaderType::IVec3(v) => UVec4::new(v.x as u32, v.y as u32, v.z as u32, 1),
+            ShaderType::IVec4(v) => UVec4::new(v.x as u32, v.y as u32, v.z as u32, v.w as u32),
+            ShaderType::Bool(v) => UVec4::new(v as u32, 0, 0, 1),
+            _ => {
+                let v = self.to_vec4();
+                UVec4::new(v.x as u32, v.y as u32, v.z as u32, v.w as u32)
+            }
+        }
+    }
 }
 impl From<Vec4> for ShaderType {
     fn from(value: Vec4) -> Self {
@@ -59,6 +164,158 @@ impl TryFrom<ShaderType> for f32 {
         }
     }
 }
+impl From<UVec2> for ShaderType {
+    fn from(value: UVec2) -> Self {
+        ShaderType::UVec2(value)
+    }
+}
+impl From<ShaderType> for UVec2 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::UVec2(v) => v,
+            _ => UVec2::ZERO,
+        }
+    }
+}
+impl From<UVec3> for ShaderType {
+    fn from(value: UVec3) -> Self {
+        ShaderType::UVec3(value)
+    }
+}
+impl From<ShaderType> for UVec3 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::UVec3(v) => v,
+            _ => UVec3::ZERO,
+        }
+    }
+}
+impl From<UVec4> for ShaderType {
+    fn from(value: UVec4) -> Self {
+        ShaderType::UVec4(value)
+    }
+}
+impl From<ShaderType> for UVec4 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::UVec4(v) => v,
+            _ => UVec4::ZERO,
+        }
+    }
+}
+impl From<IVec2> for ShaderType {
+    fn from(value: IVec2) -> Self {
+        ShaderType::IVec2(value)
+    }
+}
+impl From<ShaderType> for IVec2 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::IVec2(v) => v,
+            _ => IVec2::ZERO,
+        }
+    }
+}
+impl From<IVec3> for ShaderType {
+    fn from(value: IVec3) -> Self {
+        ShaderType::IVec3(value)
+    }
+}
+impl From<ShaderType> for IVec3 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::IVec3(v) => v,
+            _ => IVec3::ZERO,
+        }
+    }
+}
+impl From<IVec4> for ShaderType {
+    fn from(value: IVec4) -> Self {
+        ShaderType::IVec4(value)
+    }
+}
+impl From<ShaderType> for IVec4 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::IVec4(v) => v,
+            _ => IVec4::ZERO,
+        }
+    }
+}
+impl From<Mat3> for ShaderType {
+    fn from(value: Mat3) -> Self {
+        ShaderType::Mat3(value)
+    }
+}
+impl From<ShaderType> for Mat3 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::Mat3(v) => v,
+            _ => Mat3::ZERO,
+        }
+    }
+}
+impl From<Mat4> for ShaderType {
+    fn from(value: Mat4) -> Self {
+        ShaderType::Mat4(value)
+    }
+}
+impl From<ShaderType> for Mat4 {
+    fn from(value: ShaderType) -> Self {
+        match value {
+            ShaderType::Mat4(v) => v,
+            _ => Mat4::ZERO,
+        }
+    }
+}
+impl From<u32> for ShaderType {
+    fn from(value: u32) -> Self {
+        ShaderType::U32(value)
+    }
+}
+impl TryFrom<ShaderType> for u32 {
+    type Error = ();
+
+    fn try_from(value: ShaderType) -> Result<Self, Self::Error> {
+        if let ShaderType::U32(v) = value {
+            Ok(v)
+        } else {
+            Err(())
+        }
+    }
+}
+impl From<i32> for ShaderType {
+    fn from(value: i32) -> Self {
+        ShaderType::I32(value)
+    }
+}
+impl TryFrom<ShaderType> for i32 {
+    type Error = ();
+
+    fn try_from(value: ShaderType) -> Result<Self, Self::Error> {
+        if let ShaderType::I32(v) = value {
+            Ok(v)
+        } else {
+            Err(())
+        }
+    }
+}
+impl From<bool> for ShaderType {
+    fn from(value: bool) -> Self {
+        ShaderType::Bool(value)
+    }
+}
+impl TryFrom<ShaderType> for bool {
+    type Error = ();
+
+    fn try_from(value: ShaderType) -> Result<Self, Self::Error> {
+        if let ShaderType::Bool(v) = value {
+            Ok(v)
+        } else {
+            Err(())
+        }
+    }
+}
 // 着色器输入包括 build-in input value（由上游生成，自动传递给着色器） 和 用户自定义的输入
 // 按照webgpu标准实施
 // https://www.w3.org/TR/WGSL/#built-in-output-value
@@ -66,16 +323,42 @@ impl TryFrom<ShaderType> for f32 {
 pub struct VertexInput {
     // build-in input
     pub vertex_index: u32,   // 顶点的索引
-    pub instance_index: u32, // 实例化渲染的索引，暂时未支持,这里永远设置为0
+    pub instance_index: u32, // 实例化渲染的索引
     // user-defined input
     // Each input-output location can store a value up to 16 bytes in size
     pub location: Vec<ShaderType>,
 }
+impl VertexInput {
+    // 矩阵顶点属性占用多个连续的location，每个location存储矩阵的一列
+    pub fn mat3(&self, location: usize) -> Mat3 {
+        Mat3::from_cols(
+            self.location[location].into(),
+            self.location[location + 1].into(),
+            self.location[location + 2].into(),
+        )
+    }
+
+    pub fn mat4(&self, location: usize) -> Mat4 {
+        Mat4::from_cols(
+            self.location[location].into(),
+            self.location[location + 1].into(),
+            self.location[location + 2].into(),
+            self.location[location + 3].into(),
+        )
+    }
+}
+
+// 和 wgpu 的 Limits::max_clip_distances 一致
+pub const MAX_CLIP_DISTANCES: usize = 8;
 
 #[derive(Clone)]
 pub struct VertexOutput {
     // build-in
     pub position: Vec4,
+    // 对应 WGSL 的 @builtin(clip_distances)，最多 MAX_CLIP_DISTANCES 个
+    // 每个值是顶点到一个用户裁剪平面的距离，图元中距离小于 0 的部分会被裁掉
+    // 同一次绘制中所有顶点输出的数量需要一致，不需要时为空
+    pub clip_distances: Vec<f32>,
     //user-define
     pub location: Vec<ShaderType>,
 }
@@ -86,8 +369,21 @@ pub struct FragmentInput {
     pub sample_index: u32,
     pub sample_mask: u32,
     pub front_facing: bool,
+    // 图元在当前绘制实例中的序号，对应 @builtin(primitive_index)
+    pub primitive_index: u32,
+    // 图元 provoking vertex（第一个顶点）的 vertex_index
+    pub vertex_index: u32,
+    // 片元相对三角形三个顶点的重心坐标，被裁剪的三角形仍然相对裁剪之前的三个顶点
+    // barycentric 经过透视矫正，barycentric_no_perspective 在屏幕空间线性变化
+    pub barycentric: Vec3,
+    pub barycentric_no_perspective: Vec3,
     //user-define
     pub location: Vec<ShaderType>,
+    // location 在屏幕 x 和 y 方向上的导数，用于 texture_sample_grad 计算 lod
+    // 只有 FragmentState 的 derivatives 为 true 时计算，否则为空
+    // flat 插值的整数类型没有导数，值没有意义
+    pub location_dpdx: Vec<ShaderType>,
+    pub location_dpdy: Vec<ShaderType>,
 }
 pub struct FragmentOutput {
     pub frag_depth: Option<f32>,
@@ -95,6 +391,52 @@ pub struct FragmentOutput {
     pub location: Vec<ShaderType>,
 }
 
-type BindGroups = Vec<BindGroup>;
-pub type VertexShader = fn(VertexInput, &mut BindGroups) -> VertexOutput;
-pub type FragmentShader = fn(FragmentInput, &mut BindGroups) -> FragmentOutput;
+// 计算着色器只有 build-in input
+#[derive(Clone, Copy, Debug)]
+pub struct ComputeInput {
+    pub global_invocation_id: UVec3,
+    pub local_invocation_id: UVec3,
+    // 工作组内线性化的 local_invocation_id
+    pub local_invocation_index: u32,
+    pub workgroup_id: UVec3,
+    pub num_workgroups: UVec3,
+}
+
+// 着色器只能通过不可变引用访问绑定组，这样着色器可以并发执行
+// 普通函数和闭包都实现了着色器trait
+pub trait VertexShader: Sync {
+    fn main(&self, input: VertexInput, bind_groups: &BindGroups) -> VertexOutput;
+}
+impl<F> VertexShader for F
+where
+    F: Fn(VertexInput, &BindGroups) -> VertexOutput + Sync,
+{
+    fn main(&self, input: VertexInput, bind_groups: &BindGroups) -> VertexOutput {
+        self(input, bind_groups)
+    }
+}
+
+pub trait FragmentShader: Sync {
+    fn main(&self, input: FragmentInput, bind_groups: &BindGroups) -> FragmentOutput;
+}
+impl<F> FragmentShader for F
+where
+    F: Fn(FragmentInput, &BindGroups) -> FragmentOutput + Sync,
+{
+    fn main(&self, input: FragmentInput, bind_groups: &BindGroups) -> FragmentOutput {
+        self(input, bind_groups)
+    }
+}
+
+// 同一个工作组的调用会在不同的线程上并发执行，通过 workgroup 访问共享内存和屏障
+pub trait ComputeShader: Sync {
+    fn main(&self, input: ComputeInput, bind_groups: &BindGroups, workgroup: &Workgroup);
+}
+impl<F> ComputeShader for F
+where
+    F: Fn(ComputeInput, &BindGroups, &Workgroup) + Sync,
+{
+    fn main(&self, input: ComputeInput, bind_groups: &BindGroups, workgroup: &Workgroup) {
+        self(input, bind_groups, workgroup)
+    }
+}
@@ -1,42 +1,818 @@
-use math::{Vec2, Vec3, Vec4};
-
-use crate::{Sampler, Texture, TextureFormat};
-
-// 纹理采样
-pub fn texture_sample(texture: &Texture, _sampler: &Sampler, mut coords: Vec2) -> Vec4 {
-    if coords.x >= 1.0 {
-        coords.x -= coords.x.floor();
-    }
-    if coords.y >= 1.0 {
-        coords.y -= coords.y.floor();
-    }
-    let x = (coords.x * texture.width as f32) as usize;
-    let y = (coords.y * texture.height as f32) as usize;
-
-    match texture.format {
-        TextureFormat::R8Unorm => {
-            let index = (y * texture.width as usize) + x;
-            [texture.data[index] as f32 / 255.; 4].into()
-        }
-        TextureFormat::Rgb8Unorm => {
-            let index = 3 * ((y * texture.width as usize) + x);
-            [
-                texture.data[index] as f32 / 255.,
-                texture.data[index + 1] as f32 / 255.,
-                texture.data[index + 2] as f32 / 255.,
-                1.,
-            ]
-            .into()
-        }
-        TextureFormat::Rgba8Unorm => {
-            let index = 4 * ((y * texture.width as usize) + x);
-            [
-                texture.data[index] as f32 / 255.,
-                texture.data[index + 1] as f32 / 255.,
-                texture.data[index + 2] as f32 / 255.,
-                texture.data[index + 3] as f32 / 255.,
-            ]
-            .into()
+use math::{IVec2, IVec3, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+use crate::{
+    AddressMode, FilterMode, Sampler, SamplerDescriptor, StorageTexture, Texture, TextureFormat,
+    TextureViewDimension,
+};
+
+// 纹理采样，对应 WGSL 的 textureSample
+// 片元着色器不是按照 2x2 的片元组执行的，没有隐式导数，lod 固定为 0
+// 需要 mipmap 时使用 texture_sample_grad，导数可以从 FragmentInput 的 location_dpdx 和 location_dpdy 得到
+// 管线的 FragmentState 需要设置 derivatives
+// 二维纹理使用第 0 层，数组纹理、立方体贴图和三维纹理需要使用下面对应的函数
+pub fn texture_sample(texture: &Texture, sampler: &Sampler, coords: Vec2) -> Vec4 {
+    sample_lod(texture, sampler, coords.extend(0.), 0, 0.)
+}
+
+// 对应 WGSL 的 textureSampleLevel，直接指定 lod
+pub fn texture_sample_level(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    level: f32,
+) -> Vec4 {
+    sample_lod(texture, sampler, coords.extend(0.), 0, level)
+}
+
+// 对应 WGSL 的 textureSampleGrad，ddx 和 ddy 是纹理坐标在屏幕 x 和 y 方向上的导数
+pub fn texture_sample_grad(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    ddx: Vec2,
+    ddy: Vec2,
+) -> Vec4 {
+    sample_grad(
+        texture,
+        sampler,
+        coords.extend(0.),
+        0,
+        ddx.extend(0.),
+        ddy.extend(0.),
+        0.,
+    )
+}
+
+// 对应 WGSL 的 textureSampleBias，bias 加在由导数计算出的 lod 上
+// WGSL 中使用的是隐式导数，这里需要显式传入
+pub fn texture_sample_bias(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    ddx: Vec2,
+    ddy: Vec2,
+    bias: f32,
+) -> Vec4 {
+    sample_grad(
+        texture,
+        sampler,
+        coords.extend(0.),
+        0,
+        ddx.extend(0.),
+        ddy.extend(0.),
+        bias,
+    )
+}
+
+// 二维数组纹理采样，array_index 超出范围时取最近的一层
+pub fn texture_sample_array(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    array_index: i32,
+) -> Vec4 {
+    texture_sample_array_level(texture, sampler, coords, array_index, 0.)
+}
+
+pub fn texture_sample_array_level(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    array_index: i32,
+    level: f32,
+) -> Vec4 {
+    let layer = array_layer(texture.array_layer_count(), array_index);
+    sample_lod(texture, sampler, coords.extend(0.), layer, level)
+}
+
+pub fn texture_sample_array_grad(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    array_index: i32,
+    ddx: Vec2,
+    ddy: Vec2,
+) -> Vec4 {
+    let layer = array_layer(texture.array_layer_count(), array_index);
+    sample_grad(
+        texture,
+        sampler,
+        coords.extend(0.),
+        layer,
+        ddx.extend(0.),
+        ddy.extend(0.),
+        0.,
+    )
+}
+
+// 三维纹理采样，z 方向使用采样器的 address_mode_w
+pub fn texture_sample_3d(texture: &Texture, sampler: &Sampler, coords: Vec3) -> Vec4 {
+    sample_lod(texture, sampler, coords, 0, 0.)
+}
+
+pub fn texture_sample_3d_level(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec3,
+    level: f32,
+) -> Vec4 {
+    sample_lod(texture, sampler, coords, 0, level)
+}
+
+pub fn texture_sample_3d_grad(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec3,
+    ddx: Vec3,
+    ddy: Vec3,
+) -> Vec4 {
+    sample_grad(texture, sampler, coords, 0, ddx, ddy, 0.)
+}
+
+// 立方体贴图采样，direction 不需要归一化
+pub fn texture_sample_cube(texture: &Texture, sampler: &Sampler, direction: Vec3) -> Vec4 {
+    texture_sample_cube_array_level(texture, sampler, direction, 0, 0.)
+}
+
+pub fn texture_sample_cube_level(
+    texture: &Texture,
+    sampler: &Sampler,
+    direction: Vec3,
+    level: f32,
+) -> Vec4 {
+    texture_sample_cube_array_level(texture, sampler, direction, 0, level)
+}
+
+pub fn texture_sample_cube_array(
+    texture: &Texture,
+    sampler: &Sampler,
+    direction: Vec3,
+    array_index: i32,
+) -> Vec4 {
+    texture_sample_cube_array_level(texture, sampler, direction, array_index, 0.)
+}
+
+// 先按照方向选择立方体的面，再在这个面上做二维采样
+// 每个面单独过滤，面的边缘处不会和相邻的面混合（没有实现 seamless cube map）
+pub fn texture_sample_cube_array_level(
+    texture: &Texture,
+    sampler: &Sampler,
+    direction: Vec3,
+    array_index: i32,
+    level: f32,
+) -> Vec4 {
+    let cube = array_layer(texture.array_layer_count() / 6, array_index);
+    let (face, coords) = cube_face(direction);
+    let sampler = cube_sampler(sampler);
+    sample_lod(texture, &sampler, coords.extend(0.), cube * 6 + face, level)
+}
+
+// 选择方向绝对值最大的分量对应的面，返回面的索引和面上的纹理坐标
+// 面的顺序为 +X -X +Y -Y +Z -Z，和 Vulkan、D3D 的约定一致
+// https://registry.khronos.org/vulkan/specs/1.3-extensions/html/chap16.html#_cube_map_face_selection
+pub fn cube_face(direction: Vec3) -> (u32, Vec2) {
+    let Vec3 { x, y, z } = direction;
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    // (面, sc, tc, ma)
+    let (face, sc, tc, ma) = if ax >= ay && ax >= az {
+        if x >= 0. {
+            (0, -z, -y, ax)
+        } else {
+            (1, z, -y, ax)
+        }
+    } else if ay >= az {
+        if y >= 0. {
+            (2, x, z, ay)
+        } else {
+            (3, x, -z, ay)
+        }
+    } else if z >= 0. {
+        (4, x, -y, az)
+    } else {
+        (5, -x, -y, az)
+    };
+    if ma == 0. {
+        return (0, Vec2::splat(0.5));
+    }
+    (face, Vec2::new((sc / ma + 1.) * 0.5, (tc / ma + 1.) * 0.5))
+}
+
+// 立方体贴图总是使用 ClampToEdge
+fn cube_sampler(sampler: &Sampler) -> Sampler {
+    Sampler {
+        state: SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            ..sampler.state
+        },
+    }
+}
+
+// 数组索引超出范围时取最近的一层，和 WGSL 的行为一致
+fn array_layer(count: u32, array_index: i32) -> u32 {
+    array_index.clamp(0, count.max(1) as i32 - 1) as u32
+}
+
+// 深度纹理的比较采样，对应 WGSL 的 textureSampleCompare，返回 [0,1] 范围内通过比较的比例
+// 采样器需要设置 compare，depth_ref 为参考值，比较方式为 depth_ref compare 纹素的深度
+// 和 WGSL 的 textureSampleCompareLevel 一样只采样第 0 个 mip 层级
+pub fn texture_sample_compare(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    depth_ref: f32,
+) -> f32 {
+    sample_compare(texture, sampler, coords, 0, depth_ref)
+}
+
+pub fn texture_sample_compare_array(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    array_index: i32,
+    depth_ref: f32,
+) -> f32 {
+    let layer = array_layer(texture.array_layer_count(), array_index);
+    sample_compare(texture, sampler, coords, layer, depth_ref)
+}
+
+// 立方体深度纹理的比较采样，用于点光源的阴影
+pub fn texture_sample_compare_cube(
+    texture: &Texture,
+    sampler: &Sampler,
+    direction: Vec3,
+    depth_ref: f32,
+) -> f32 {
+    let (face, coords) = cube_face(direction);
+    let sampler = cube_sampler(sampler);
+    sample_compare(texture, &sampler, coords, face, depth_ref)
+}
+
+// 读取纹素，对应 WGSL 的 textureLoad，不经过采样器的过滤和 address mode
+// 坐标、数组层或者 mip 层级超出范围时返回 0
+pub fn texture_load(texture: &Texture, coords: IVec2, level: u32) -> Vec4 {
+    load_texel(texture, IVec3::new(coords.x, coords.y, 0), 0, level)
+        .map_or(Vec4::ZERO, |coords| texture.texel(coords, 0, level))
+}
+
+pub fn texture_load_array(texture: &Texture, coords: IVec2, array_index: i32, level: u32) -> Vec4 {
+    if array_index < 0 {
+        return Vec4::ZERO;
+    }
+    load_texel(
+        texture,
+        IVec3::new(coords.x, coords.y, 0),
+        array_index as u32,
+        level,
+    )
+    .map_or(Vec4::ZERO, |coords| {
+        texture.texel(coords, array_index as u32, level)
+    })
+}
+
+pub fn texture_load_3d(texture: &Texture, coords: IVec3, level: u32) -> Vec4 {
+    load_texel(texture, coords, 0, level)
+        .map_or(Vec4::ZERO, |coords| texture.texel(coords, 0, level))
+}
+
+// 整数格式的纹素，不经过浮点转换
+pub fn texture_load_uint(texture: &Texture, coords: IVec2, level: u32) -> UVec4 {
+    load_texel(texture, IVec3::new(coords.x, coords.y, 0), 0, level)
+        .map_or(UVec4::ZERO, |coords| texture.texel_uint(coords, 0, level))
+}
+
+// 对应 WGSL 的 textureDimensions，返回第 level 个 mip 层级的大小
+pub fn texture_dimensions(texture: &Texture, level: u32) -> UVec2 {
+    let size = texture.mip_level_size(level);
+    UVec2::new(size.x, size.y)
+}
+
+pub fn texture_dimensions_3d(texture: &Texture, level: u32) -> UVec3 {
+    texture.mip_level_size(level)
+}
+
+// 对应 WGSL 的 textureNumLevels
+pub fn texture_num_levels(texture: &Texture) -> u32 {
+    texture.mip_level_count
+}
+
+// 对应 WGSL 的 textureNumLayers，立方体贴图数组返回立方体的数量
+pub fn texture_num_layers(texture: &Texture) -> u32 {
+    match texture.dimension {
+        TextureViewDimension::CubeArray => texture.array_layer_count() / 6,
+        _ => texture.array_layer_count(),
+    }
+}
+
+// 写入存储纹理，对应 WGSL 的 textureStore，坐标超出范围时不写入
+pub fn texture_store(texture: &StorageTexture, coords: IVec2, value: Vec4) {
+    if let Some(coords) = texel_coords(IVec3::new(coords.x, coords.y, 0)) {
+        texture.store(coords, 0, value);
+    }
+}
+
+pub fn texture_store_array(texture: &StorageTexture, coords: IVec2, array_index: i32, value: Vec4) {
+    if let (Some(coords), Ok(layer)) = (
+        texel_coords(IVec3::new(coords.x, coords.y, 0)),
+        u32::try_from(array_index),
+    ) {
+        texture.store(coords, layer, value);
+    }
+}
+
+pub fn texture_store_3d(texture: &StorageTexture, coords: IVec3, value: Vec4) {
+    if let Some(coords) = texel_coords(coords) {
+        texture.store(coords, 0, value);
+    }
+}
+
+// 读取 read_only 或者 read_write 的存储纹理，超出范围时返回 0
+pub fn texture_load_storage(texture: &StorageTexture, coords: IVec2) -> Vec4 {
+    texel_coords(IVec3::new(coords.x, coords.y, 0))
+        .map_or(Vec4::ZERO, |coords| texture.load(coords, 0))
+}
+
+fn texel_coords(coords: IVec3) -> Option<UVec3> {
+    Some(UVec3::new(
+        u32::try_from(coords.x).ok()?,
+        u32::try_from(coords.y).ok()?,
+        u32::try_from(coords.z).ok()?,
+    ))
+}
+
+// 检查第 layer 层第 level 个 mip 层级中的纹素坐标，超出范围时返回 None
+fn load_texel(texture: &Texture, coords: IVec3, layer: u32, level: u32) -> Option<UVec3> {
+    let coords = texel_coords(coords)?;
+    if level >= texture.mip_level_count || layer >= texture.array_layer_count() {
+        return None;
+    }
+    let size = texture.mip_level_size(level);
+    if coords.x >= size.x || coords.y >= size.y || coords.z >= size.z {
+        return None;
+    }
+    Some(coords)
+}
+
+// 由导数计算 lod，开启各向异性过滤时沿着足迹的长轴方向多次采样取平均
+// https://registry.khronos.org/vulkan/specs/1.3-extensions/html/chap16.html#textures-lod-and-scale-factor
+fn sample_grad(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec3,
+    layer: u32,
+    ddx: Vec3,
+    ddy: Vec3,
+    bias: f32,
+) -> Vec4 {
+    // 以纹素为单位的足迹，二维纹理的深度为 1，z 方向的导数需要为 0
+    let size = texture.size();
+    let texel_length = |d: Vec3| {
+        Vec3::new(
+            d.x * size.x as f32,
+            d.y * size.y as f32,
+            d.z * size.z as f32,
+        )
+        .length()
+    };
+    let (length_x, length_y) = (texel_length(ddx), texel_length(ddy));
+    let (major, minor, major_axis) = if length_x >= length_y {
+        (length_x, length_y, ddx)
+    } else {
+        (length_y, length_x, ddy)
+    };
+    let max_anisotropy = sampler.state.anisotropy_clamp.max(1) as f32;
+    let samples = if max_anisotropy > 1. && minor > 0. {
+        (major / minor).ceil().min(max_anisotropy)
+    } else {
+        1.
+    };
+    let lod = (major / samples).log2() + bias;
+    if samples <= 1. {
+        return sample_lod(texture, sampler, coords, layer, lod);
+    }
+    let count = samples as u32;
+    (0..count).fold(Vec4::ZERO, |acc, i| {
+        let offset = (i as f32 + 0.5) / samples - 0.5;
+        acc + sample_lod(texture, sampler, coords + major_axis * offset, layer, lod)
+    }) * (1. / samples)
+}
+
+// 按照 lod 选择 mip 层级和过滤方式，lod <= 0 时为放大
+fn sample_lod(texture: &Texture, sampler: &Sampler, coords: Vec3, layer: u32, lod: f32) -> Vec4 {
+    let state = &sampler.state;
+    let lod = lod.clamp(state.lod_min_clamp, state.lod_max_clamp);
+    if lod <= 0. {
+        return sample_level(texture, sampler, coords, layer, 0, state.mag_filter);
+    }
+    let max_level = texture.mip_level_count.max(1) - 1;
+    match state.mipmap_filter {
+        FilterMode::Nearest => {
+            let level = ((lod + 0.5).floor() as u32).min(max_level);
+            sample_level(texture, sampler, coords, layer, level, state.min_filter)
+        }
+        // 三线性过滤，相邻两个层级之间线性插值
+        FilterMode::Linear => {
+            let lod = lod.min(max_level as f32);
+            let level = lod.floor();
+            let t = lod - level;
+            let level = level as u32;
+            let color = sample_level(texture, sampler, coords, layer, level, state.min_filter);
+            if t == 0. {
+                return color;
+            }
+            let next = sample_level(texture, sampler, coords, layer, level + 1, state.min_filter);
+            lerp(color, next, t)
+        }
+    }
+}
+
+// 在一个数组层的一个 mip 层级中按照过滤方式采样，纹理坐标范围 [0,1] 对应整个纹理
+// https://gpuweb.github.io/gpuweb/#texture-sampling
+fn sample_level(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec3,
+    layer: u32,
+    level: u32,
+    filter: FilterMode,
+) -> Vec4 {
+    let size = texture.mip_level_size(level);
+    filter_texels(
+        coords,
+        size,
+        filter,
+        texel_fetcher(texture, sampler, layer, level),
+    )
+}
+
+// 按照采样器的 address mode 读取纹素，ClampToBorder 超出范围时返回边框颜色
+fn texel_fetcher<'a>(
+    texture: &'a Texture,
+    sampler: &'a Sampler,
+    layer: u32,
+    level: u32,
+) -> impl Fn(i64, i64, i64) -> Vec4 + 'a {
+    let size = texture.mip_level_size(level);
+    let offset = texture.subresource_offset(layer, level);
+    move |x, y, z| {
+        let x = sampler.state.address_mode_u.apply(x, size.x);
+        let y = sampler.state.address_mode_v.apply(y, size.y);
+        let z = sampler.state.address_mode_w.apply(z, size.z);
+        let (Some(x), Some(y), Some(z)) = (x, y, z) else {
+            return sampler.border_color();
+        };
+        texture.decode_texel(offset, size, UVec3::new(x, y, z))
+    }
+}
+
+// 对 texel_fetch 读取的纹素进行过滤，三维纹理线性过滤时在相邻两个深度切片之间再做一次插值
+fn filter_texels(
+    coords: Vec3,
+    size: UVec3,
+    filter: FilterMode,
+    texel_fetch: impl Fn(i64, i64, i64) -> Vec4,
+) -> Vec4 {
+    let x = coords.x * size.x as f32;
+    let y = coords.y * size.y as f32;
+    let z = coords.z * size.z as f32;
+    match filter {
+        FilterMode::Nearest => {
+            // 二维纹理的深度为 1，z 总是取 0
+            let z = if size.z > 1 { z.floor() as i64 } else { 0 };
+            texel_fetch(x.floor() as i64, y.floor() as i64, z)
+        }
+        // 双线性插值，纹素中心在 (i + 0.5, j + 0.5)
+        FilterMode::Linear => {
+            let (x, y) = (x - 0.5, y - 0.5);
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as i64, y0 as i64);
+            let bilinear = |z: i64| {
+                let top = lerp(texel_fetch(x0, y0, z), texel_fetch(x0 + 1, y0, z), fx);
+                let bottom = lerp(
+                    texel_fetch(x0, y0 + 1, z),
+                    texel_fetch(x0 + 1, y0 + 1, z),
+                    fx,
+                );
+                lerp(top, bottom, fy)
+            };
+            if size.z <= 1 {
+                return bilinear(0);
+            }
+            let z = z - 0.5;
+            let z0 = z.floor();
+            let fz = z - z0;
+            let z0 = z0 as i64;
+            lerp(bilinear(z0), bilinear(z0 + 1), fz)
+        }
+    }
+}
+
+// 深度比较采样，总是使用第 0 个 mip 层级
+// 每个纹素先和 depth_ref 比较，通过为 1 否则为 0，线性过滤时再对比较结果做双线性插值（2x2 PCF）
+// ClampToBorder 超出范围时使用边框颜色的 r 分量作为深度
+fn sample_compare(
+    texture: &Texture,
+    sampler: &Sampler,
+    coords: Vec2,
+    layer: u32,
+    depth_ref: f32,
+) -> f32 {
+    let compare = sampler
+        .state
+        .compare
+        .expect("depth comparison requires a sampler with a compare function");
+    // 定点数的深度格式只能存储 [0,1] 范围内的深度
+    let depth_ref = if texture.format == TextureFormat::Depth32Float {
+        depth_ref
+    } else {
+        depth_ref.clamp(0., 1.)
+    };
+    let texel_fetch = texel_fetcher(texture, sampler, layer, 0);
+    filter_texels(
+        coords.extend(0.),
+        texture.mip_level_size(0),
+        sampler.state.mag_filter,
+        |x, y, z| {
+            let depth = texel_fetch(x, y, z).x;
+            Vec4::splat(compare.compare(depth_ref, depth) as u32 as f32)
+        },
+    )
+    .x
+}
+
+fn lerp(a: Vec4, b: Vec4, t: f32) -> Vec4 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture_2d(format: TextureFormat, width: u32, height: u32, data: Vec<u8>) -> Texture {
+        Texture {
+            width,
+            height,
+            format,
+            data,
+            ..Default::default()
         }
     }
+
+    fn rgba(v: Vec4) -> (f32, f32, f32, f32) {
+        v.into()
+    }
+
+    fn sampler(address_mode: AddressMode, filter: FilterMode) -> Sampler {
+        Sampler::new(SamplerDescriptor {
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            mag_filter: filter,
+            min_filter: filter,
+            border_color: Some(crate::SamplerBorderColor::OpaqueWhite),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn address_modes_at_edge_uvs() {
+        // 2x1 的纹理，左边的纹素为 0，右边为 1
+        let texture = texture_2d(TextureFormat::R8Unorm, 2, 1, vec![0, 255]);
+        let sample =
+            |mode, filter, u| texture_sample(&texture, &sampler(mode, filter), Vec2::new(u, 0.5)).x;
+        // 线性过滤时 u = 0 和 u = 1 处于边缘纹素和它外侧纹素的正中间
+        for (mode, at_zero, at_one) in [
+            (AddressMode::ClampToEdge, 0., 1.),
+            (AddressMode::Repeat, 0.5, 0.5),
+            (AddressMode::MirrorRepeat, 0., 1.),
+            (AddressMode::ClampToBorder, 0.5, 1.),
+        ] {
+            assert_eq!(sample(mode, FilterMode::Linear, 0.), at_zero, "{mode:?}");
+            assert_eq!(sample(mode, FilterMode::Linear, 1.), at_one, "{mode:?}");
+        }
+        // 最近邻过滤时 u = 1 落在右侧纹素之外
+        for (mode, at_one) in [
+            (AddressMode::ClampToEdge, 1.),
+            (AddressMode::Repeat, 0.),
+            (AddressMode::MirrorRepeat, 1.),
+            (AddressMode::ClampToBorder, 1.),
+        ] {
+            assert_eq!(sample(mode, FilterMode::Nearest, 0.), 0., "{mode:?}");
+            assert_eq!(sample(mode, FilterMode::Nearest, 1.), at_one, "{mode:?}");
+        }
+        // 超出 [0, 1] 的坐标
+        assert_eq!(sample(AddressMode::Repeat, FilterMode::Nearest, 1.75), 1.);
+        assert_eq!(
+            sample(AddressMode::MirrorRepeat, FilterMode::Nearest, 1.75),
+            0.
+        );
+        assert_eq!(
+            sample(AddressMode::ClampToEdge, FilterMode::Nearest, -0.5),
+            0.
+        );
+    }
+
+    #[test]
+    fn grad_selects_box_filtered_level() {
+        let values: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        let mut texture = texture_2d(
+            TextureFormat::R32Float,
+            4,
+            4,
+            bytemuck::cast_slice(&values).to_vec(),
+        );
+        texture.generate_mipmaps(crate::MipmapFilter::Box);
+        assert_eq!(texture_num_levels(&texture), 3);
+        assert_eq!(texture_dimensions(&texture, 1), UVec2::new(2, 2));
+
+        let sampler = sampler(AddressMode::ClampToEdge, FilterMode::Nearest);
+        let coords = Vec2::new(0.25, 0.25);
+        // 每个像素跨过 2 个纹素时 lod 为 1，跨过 4 个纹素时 lod 为 2
+        for (ddx, expected) in [(0.25, 5.), (0.5, 2.5), (1., 7.5)] {
+            let ddx = Vec2::new(ddx, 0.);
+            let value = texture_sample_grad(&texture, &sampler, coords, ddx, Vec2::ZERO).x;
+            assert_eq!(value, expected, "{ddx:?}");
+        }
+        assert_eq!(texture_sample_level(&texture, &sampler, coords, 1.).x, 2.5);
+    }
+
+    #[test]
+    fn cube_face_selection() {
+        // 每个主轴方向的面和面上的纹理坐标，和 Vulkan 的 cube map face selection 表一致
+        for (direction, face, coords) in [
+            (Vec3::new(1., 0.5, 0.25), 0, Vec2::new(0.375, 0.25)),
+            (Vec3::new(-1., 0.5, 0.25), 1, Vec2::new(0.625, 0.25)),
+            (Vec3::new(0.25, 1., 0.5), 2, Vec2::new(0.625, 0.75)),
+            (Vec3::new(0.25, -1., 0.5), 3, Vec2::new(0.625, 0.25)),
+            (Vec3::new(0.25, 0.5, 1.), 4, Vec2::new(0.625, 0.25)),
+            (Vec3::new(0.25, 0.5, -1.), 5, Vec2::new(0.375, 0.25)),
+        ] {
+            // 方向的长度不影响结果
+            for scale in [1., 4.] {
+                let (selected, uv) = cube_face(direction * scale);
+                assert_eq!(selected, face, "{direction:?}");
+                assert_eq!((uv.x, uv.y), (coords.x, coords.y), "{direction:?}");
+            }
+        }
+        let (face, uv) = cube_face(Vec3::ZERO);
+        assert_eq!((face, uv.x, uv.y), (0, 0.5, 0.5));
+
+        // 1x1 的立方体贴图，每个面的纹素为面的索引 * 50
+        let texture = Texture {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 6,
+            dimension: TextureViewDimension::Cube,
+            format: TextureFormat::R8Unorm,
+            mip_level_count: 1,
+            data: vec![0, 50, 100, 150, 200, 250],
+        };
+        let sampler = sampler(AddressMode::Repeat, FilterMode::Linear);
+        for (face, direction) in [
+            Vec3::X,
+            Vec3::NEG_X,
+            Vec3::Y,
+            Vec3::NEG_Y,
+            Vec3::Z,
+            Vec3::NEG_Z,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let value = texture_sample_cube(&texture, &sampler, direction + Vec3::splat(0.1)).x;
+            assert_eq!(value, face as f32 * 50. / 255., "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn pcf_partial_coverage() {
+        let depth_texture = |depths: [f32; 4]| {
+            texture_2d(
+                TextureFormat::Depth32Float,
+                2,
+                2,
+                bytemuck::cast_slice(&depths).to_vec(),
+            )
+        };
+        let compare_sampler = |filter| {
+            Sampler::new(SamplerDescriptor {
+                mag_filter: filter,
+                min_filter: filter,
+                compare: Some(crate::CompareFunction::Less),
+                ..Default::default()
+            })
+        };
+        // 左列的纹素比参考深度 0.5 近，右列更远，只有右列通过比较
+        let texture = depth_texture([0.2, 0.8, 0.2, 0.8]);
+        let linear = compare_sampler(FilterMode::Linear);
+        for (u, expected) in [
+            (0.25, 0.),
+            (0.375, 0.25),
+            (0.5, 0.5),
+            (0.625, 0.75),
+            (0.75, 1.),
+        ] {
+            let coords = Vec2::new(u, 0.5);
+            let value = texture_sample_compare(&texture, &linear, coords, 0.5);
+            assert_eq!(value, expected, "u = {u}");
+        }
+        // 最近邻过滤时只比较一个纹素
+        let nearest = compare_sampler(FilterMode::Nearest);
+        let coords = Vec2::new(0.375, 0.5);
+        assert_eq!(texture_sample_compare(&texture, &nearest, coords, 0.5), 0.);
+
+        // 2x2 中有 3 个纹素通过比较
+        let texture = depth_texture([0.2, 0.8, 0.8, 0.8]);
+        let center = Vec2::splat(0.5);
+        assert_eq!(texture_sample_compare(&texture, &linear, center, 0.5), 0.75);
+    }
+
+    #[test]
+    fn texture_store_load_round_trip() {
+        let value = Vec4::new(1., 0.5, 0.25, 0.75);
+        let storage = StorageTexture::new(
+            4,
+            4,
+            1,
+            TextureViewDimension::D2,
+            TextureFormat::Rgba32Float,
+        );
+        // 克隆的纹理和原纹理共享数据
+        texture_store(&storage.clone(), IVec2::new(1, 2), value);
+        // 超出范围时不写入
+        texture_store(&storage, IVec2::new(-1, 0), Vec4::ONE);
+        texture_store(&storage, IVec2::new(4, 0), Vec4::ONE);
+        assert_eq!(
+            rgba(texture_load_storage(&storage, IVec2::new(1, 2))),
+            rgba(value)
+        );
+        assert_eq!(
+            rgba(texture_load_storage(&storage, IVec2::new(4, 0))),
+            rgba(Vec4::ZERO)
+        );
+
+        let texture = storage.to_texture();
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if (x, y) == (1, 2) { value } else { Vec4::ZERO };
+                assert_eq!(
+                    rgba(texture_load(&texture, IVec2::new(x, y), 0)),
+                    rgba(expected)
+                );
+            }
+        }
+        let copy = StorageTexture::from_texture(&texture);
+        assert_eq!(
+            rgba(texture_load_storage(&copy, IVec2::new(1, 2))),
+            rgba(value)
+        );
+
+        // 数组纹理按层写入
+        let storage = StorageTexture::new(
+            2,
+            2,
+            3,
+            TextureViewDimension::D2Array,
+            TextureFormat::Rgba32Float,
+        );
+        texture_store_array(&storage, IVec2::new(1, 0), 2, value);
+        texture_store_array(&storage, IVec2::new(0, 0), 3, Vec4::ONE);
+        let texture = storage.to_texture();
+        assert_eq!(
+            rgba(texture_load_array(&texture, IVec2::new(1, 0), 2, 0)),
+            rgba(value)
+        );
+        assert_eq!(
+            rgba(texture_load_array(&texture, IVec2::new(1, 0), 1, 0)),
+            rgba(Vec4::ZERO)
+        );
+        // 超出层数的写入被忽略
+        let written = (0..3)
+            .flat_map(|layer| (0..4).map(move |i| (layer, IVec2::new(i % 2, i / 2))))
+            .filter(|&(layer, coords)| {
+                rgba(texture_load_array(&texture, coords, layer, 0)) != rgba(Vec4::ZERO)
+            })
+            .count();
+        assert_eq!(written, 1);
+
+        // 三维纹理按深度写入
+        let storage = StorageTexture::new(
+            2,
+            2,
+            2,
+            TextureViewDimension::D3,
+            TextureFormat::Rgba32Float,
+        );
+        texture_store_3d(&storage, IVec3::new(0, 1, 1), value);
+        let texture = storage.to_texture();
+        assert_eq!(
+            rgba(texture_load_3d(&texture, IVec3::new(0, 1, 1), 0)),
+            rgba(value)
+        );
+        assert_eq!(
+            rgba(texture_load_3d(&texture, IVec3::new(0, 1, 0), 0)),
+            rgba(Vec4::ZERO)
+        );
+    }
 }
@@ -0,0 +1,675 @@
+use math::{UVec3, UVec4, Vec4};
+
+use crate::{Buffer, Extent3d, Origin3d, TextureFormat};
+
+/// Dimensions of a texture view, how the texture is interpreted when sampled.
+///
+/// Corresponds to [WebGPU `GPUTextureViewDimension`](
+/// https://gpuweb.github.io/gpuweb/#enumdef-gputextureviewdimension).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextureViewDimension {
+    // 一维纹理，height 和 depth_or_array_layers 都为 1
+    D1,
+    #[default]
+    D2,
+    // 二维纹理数组，depth_or_array_layers 为数组的层数
+    D2Array,
+    // 立方体贴图，6 层依次为 +X -X +Y -Y +Z -Z 六个面
+    Cube,
+    // 立方体贴图数组，层数是 6 的倍数，每 6 层为一个立方体
+    CubeArray,
+    // 三维纹理，depth_or_array_layers 为深度
+    D3,
+}
+
+// 纹理，data 中按照数组层依次存放，每一层中从第 0 层开始依次存放每个 mip 层级的纹素
+// 第 n 个 mip 层级的大小为 max(1, width >> n) x max(1, height >> n)，三维纹理的深度也同样减半
+// 块压缩格式每个层级按块逐行存放，不足一个块的边缘也占用一个完整的块
+// 这里没有区分纹理和纹理视图，dimension 直接决定纹理如何被采样
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    // 三维纹理的深度或者数组纹理的层数，其他纹理为 1
+    pub depth_or_array_layers: u32,
+    pub dimension: TextureViewDimension,
+    pub format: TextureFormat,
+    // mip 层级的数量，至少为 1
+    pub mip_level_count: u32,
+    pub data: Vec<u8>,
+}
+impl Default for Texture {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            depth_or_array_layers: 1,
+            dimension: TextureViewDimension::default(),
+            format: TextureFormat::default(),
+            mip_level_count: 1,
+            data: Vec::new(),
+        }
+    }
+}
+
+// 生成 mipmap 使用的下采样滤波器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MipmapFilter {
+    // 相邻的 2x2 纹素取平均
+    #[default]
+    Box,
+    // Kaiser 窗口的 sinc 滤波，比 Box 更锐利，高频的棋盘格纹理缩小后更不容易出现摩尔纹
+    Kaiser,
+}
+impl MipmapFilter {
+    // 滤波器的半径，单位为下一层级的纹素
+    fn support(&self) -> f32 {
+        match self {
+            MipmapFilter::Box => 0.5,
+            MipmapFilter::Kaiser => 3.,
+        }
+    }
+
+    fn weight(&self, x: f32) -> f32 {
+        match self {
+            MipmapFilter::Box => {
+                if x.abs() < 0.5 {
+                    1.
+                } else if x.abs() == 0.5 {
+                    0.5
+                } else {
+                    0.
+                }
+            }
+            MipmapFilter::Kaiser => {
+                const ALPHA: f32 = 4.;
+                let support = self.support();
+                if x.abs() >= support {
+                    return 0.;
+                }
+                let t = x / support;
+                sinc(x) * bessel_i0(ALPHA * (1. - t * t).sqrt()) / bessel_i0(ALPHA)
+            }
+        }
+    }
+}
+
+impl Texture {
+    // 纹理大小允许的最大 mip 层级数量，三维纹理的深度也需要传入 size 中
+    pub fn max_mip_level_count(size: UVec3) -> u32 {
+        32 - size.x.max(size.y).max(size.z).max(1).leading_zeros()
+    }
+
+    // 每个 mip 层级都会减半的大小，只有三维纹理的深度会减半
+    pub fn size(&self) -> UVec3 {
+        let depth = match self.dimension {
+            TextureViewDimension::D3 => self.depth_or_array_layers,
+            _ => 1,
+        };
+        UVec3::new(self.width, self.height, depth)
+    }
+
+    pub fn array_layer_count(&self) -> u32 {
+        match self.dimension {
+            TextureViewDimension::D3 => 1,
+            _ => self.depth_or_array_layers,
+        }
+    }
+
+    // 纹理的大小和维度是否匹配，并且 data 中有所有子资源的数据
+    // 块压缩格式第 0 个层级的宽高需要是块大小的整数倍
+    pub fn is_valid(&self) -> bool {
+        let size = self.size();
+        let (block_width, block_height) = self.format.block_dimensions();
+        let layers = self.depth_or_array_layers;
+        let dimension_valid = match self.dimension {
+            TextureViewDimension::D1 => size.y == 1 && layers == 1,
+            TextureViewDimension::D2 => layers == 1,
+            TextureViewDimension::D2Array | TextureViewDimension::D3 => true,
+            TextureViewDimension::Cube => size.x == size.y && layers == 6,
+            TextureViewDimension::CubeArray => size.x == size.y && layers.is_multiple_of(6),
+        };
+        dimension_valid
+            && size.x > 0
+            && size.y > 0
+            && layers > 0
+            && self.mip_level_count > 0
+            && size.x.is_multiple_of(block_width)
+            && size.y.is_multiple_of(block_height)
+            && self
+                .checked_size_in_bytes()
+                .is_some_and(|size| self.data.len() >= size)
+    }
+
+    pub fn mip_level_size(&self, level: u32) -> UVec3 {
+        let size = self.size();
+        UVec3::new(
+            (size.x >> level).max(1),
+            (size.y >> level).max(1),
+            (size.z >> level).max(1),
+        )
+    }
+
+    fn mip_level_size_in_bytes(&self, level: u32) -> usize {
+        self.checked_mip_level_size_in_bytes(level)
+            .expect("texture size overflows usize")
+    }
+
+    fn checked_mip_level_size_in_bytes(&self, level: u32) -> Option<usize> {
+        let size = self.mip_level_size(level);
+        let (block_width, block_height) = self.format.block_dimensions();
+        (size.x.div_ceil(block_width) as usize)
+            .checked_mul(size.y.div_ceil(block_height) as usize)?
+            .checked_mul(size.z as usize)?
+            .checked_mul(self.format.size())
+    }
+
+    // 一个数组层所有 mip 层级的字节数
+    fn array_layer_size_in_bytes(&self) -> usize {
+        (0..self.mip_level_count)
+            .map(|level| self.mip_level_size_in_bytes(level))
+            .sum()
+    }
+
+    // 第 layer 层第 level 个 mip 层级的数据在 data 中的起始位置
+    pub fn subresource_offset(&self, layer: u32, level: u32) -> usize {
+        layer as usize * self.array_layer_size_in_bytes()
+            + (0..level)
+                .map(|level| self.mip_level_size_in_bytes(level))
+                .sum::<usize>()
+    }
+
+    // 所有数组层和 mip 层级需要的字节数
+    pub fn size_in_bytes(&self) -> usize {
+        self.array_layer_count() as usize * self.array_layer_size_in_bytes()
+    }
+
+    // 和 size_in_bytes 相同，mip 层级数量超过纹理大小允许的数量或者字节数溢出时返回 None
+    // 纹理描述来自文件等不可信的来源时，分配 data 之前使用这个检查
+    pub fn checked_size_in_bytes(&self) -> Option<usize> {
+        if self.mip_level_count > Self::max_mip_level_count(self.size()) {
+            return None;
+        }
+        (0..self.mip_level_count)
+            .try_fold(0usize, |sum, level| {
+                sum.checked_add(self.checked_mip_level_size_in_bytes(level)?)
+            })?
+            .checked_mul(self.array_layer_count() as usize)
+    }
+
+    pub fn mip_level_data(&self, layer: u32, level: u32) -> &[u8] {
+        let offset = self.subresource_offset(layer, level);
+        &self.data[offset..offset + self.mip_level_size_in_bytes(level)]
+    }
+
+    // 读取第 layer 层第 level 个 mip 层级的纹素，坐标需要在范围内
+    pub fn texel(&self, coords: UVec3, layer: u32, level: u32) -> Vec4 {
+        let offset = self.subresource_offset(layer, level);
+        self.decode_texel(offset, self.mip_level_size(level), coords)
+    }
+
+    // 整数格式的纹素，不经过浮点转换
+    pub fn texel_uint(&self, coords: UVec3, layer: u32, level: u32) -> UVec4 {
+        let offset = self.subresource_offset(layer, level);
+        let index = self.texel_index(offset, self.mip_level_size(level), coords);
+        self.format
+            .decode_uint(&self.data[index..index + self.format.size()])
+    }
+
+    // 纹素在 data 中的位置，offset 为子资源的起始位置，size 为 mip 层级的大小
+    // 块压缩格式为纹素所在块的位置
+    fn texel_index(&self, offset: usize, size: UVec3, coords: UVec3) -> usize {
+        let (block_width, block_height) = self.format.block_dimensions();
+        let blocks_per_row = size.x.div_ceil(block_width) as usize;
+        let block_rows = size.y.div_ceil(block_height) as usize;
+        let block = (coords.z as usize * block_rows + (coords.y / block_height) as usize)
+            * blocks_per_row
+            + (coords.x / block_width) as usize;
+        offset + block * self.format.size()
+    }
+
+    // 解码子资源中的一个纹素，块压缩格式需要解码纹素所在的块
+    // 绑定到着色器的纹理已经在 BindGroups::set 中解压，采样时不会走到块解码
+    pub(crate) fn decode_texel(&self, offset: usize, size: UVec3, coords: UVec3) -> Vec4 {
+        let index = self.texel_index(offset, size, coords);
+        let bytes = &self.data[index..index + self.format.size()];
+        if self.format.is_compressed() {
+            let (block_width, block_height) = self.format.block_dimensions();
+            let texel = (coords.y % block_height) * block_width + coords.x % block_width;
+            self.format.decode_block_texel(bytes, texel as usize)
+        } else {
+            self.format.decode(bytes)
+        }
+    }
+
+    // 把块压缩格式解压为对应的非压缩格式，包括所有数组层和 mip 层级
+    // 解压之后采样不需要每次都解码整个块，非压缩格式直接返回克隆
+    pub fn decompress(&self) -> Texture {
+        if !self.format.is_compressed() {
+            return self.clone();
+        }
+        let format = self.format.decompressed_format();
+        let texel_size = format.size();
+        let (block_width, block_height) = self.format.block_dimensions();
+
+        let mut data = Vec::new();
+        for layer in 0..self.array_layer_count() {
+            for level in 0..self.mip_level_count {
+                let size = self.mip_level_size(level);
+                let blocks_per_row = size.x.div_ceil(block_width);
+                let block_rows = size.y.div_ceil(block_height);
+                let mut level_data = vec![0; (size.x * size.y * size.z) as usize * texel_size];
+                let blocks = self
+                    .mip_level_data(layer, level)
+                    .chunks_exact(self.format.size());
+                for (i, block) in blocks.enumerate() {
+                    let i = i as u32;
+                    let block_x = i % blocks_per_row * block_width;
+                    let block_y = i / blocks_per_row % block_rows * block_height;
+                    let z = i / (blocks_per_row * block_rows);
+                    for (j, value) in self.format.decode_block(block).iter().enumerate() {
+                        let x = block_x + j as u32 % block_width;
+                        let y = block_y + j as u32 / block_width;
+                        // 边缘块超出层级大小的部分丢弃
+                        if x < size.x && y < size.y {
+                            let index = ((z * size.y + y) * size.x + x) as usize * texel_size;
+                            format.encode(*value, &mut level_data[index..index + texel_size]);
+                        }
+                    }
+                }
+                data.extend(level_data);
+            }
+        }
+        Texture {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: self.depth_or_array_layers,
+            dimension: self.dimension,
+            format,
+            mip_level_count: self.mip_level_count,
+            data,
+        }
+    }
+
+    // 拷贝时第 level 个 mip 层级的大小，三维纹理为深度，其他纹理为数组层数
+    pub(crate) fn copy_extent(&self, level: u32) -> Extent3d {
+        let size = self.mip_level_size(level);
+        let depth_or_array_layers = match self.dimension {
+            TextureViewDimension::D3 => size.z,
+            _ => self.array_layer_count(),
+        };
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers,
+        }
+    }
+
+    // 拷贝区域中第 z 个图像第 row 行的第一个纹素在 data 中的位置，块压缩格式每行为一行块
+    pub(crate) fn copy_offset(&self, level: u32, origin: Origin3d, z: u32, row: u32) -> usize {
+        let size = self.mip_level_size(level);
+        let (_, block_height) = self.format.block_dimensions();
+        let (x, y, z) = (origin.x, origin.y + row * block_height, origin.z + z);
+        let (layer, z) = match self.dimension {
+            TextureViewDimension::D3 => (0, z),
+            _ => (z, 0),
+        };
+        self.texel_index(
+            self.subresource_offset(layer, level),
+            size,
+            UVec3::new(x, y, z),
+        )
+    }
+
+    // 由每个数组层的第 0 个层级生成完整的 mip 链，已有的其他层级会被替换
+    // 每一层都由上一层下采样得到，依次在 x、y（三维纹理还有 z）方向上做一维滤波
+    pub fn generate_mipmaps(&mut self, filter: MipmapFilter) {
+        assert!(
+            !self.format.is_integer()
+                && !self.format.has_depth_aspect()
+                && !self.format.is_compressed(),
+            "cannot generate mipmaps for {:?} textures",
+            self.format
+        );
+        let texel_size = self.format.size();
+        let mip_level_count = Self::max_mip_level_count(self.size());
+
+        let mut data = Vec::new();
+        for layer in 0..self.array_layer_count() {
+            let base = self.mip_level_data(layer, 0);
+            data.extend_from_slice(base);
+            let mut src: Vec<Vec4> = base
+                .chunks_exact(texel_size)
+                .map(|texel| self.format.decode(texel))
+                .collect();
+            let mut src_size = self.size();
+            for level in 1..mip_level_count {
+                let size = self.mip_level_size(level);
+                let mut dst_size = src_size;
+                for axis in 0..3 {
+                    let len = [size.x, size.y, size.z][axis];
+                    src = downsample(&src, dst_size, axis, len, filter);
+                    match axis {
+                        0 => dst_size.x = len,
+                        1 => dst_size.y = len,
+                        _ => dst_size.z = len,
+                    }
+                }
+
+                let mut bytes = vec![0; src.len() * texel_size];
+                for (value, texel) in src.iter().zip(bytes.chunks_exact_mut(texel_size)) {
+                    self.format.encode(*value, texel);
+                }
+                data.extend(bytes);
+                src_size = size;
+            }
+        }
+        self.data = data;
+        self.mip_level_count = mip_level_count;
+    }
+}
+
+// 存储纹理的访问方式，对应 wgpu 的 StorageTextureAccess
+// 和存储缓冲的 read_only 一样只是声明，不会限制着色器的读写
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StorageTextureAccess {
+    #[default]
+    WriteOnly,
+    ReadOnly,
+    ReadWrite,
+}
+
+// 存储纹理，着色器可以通过 texture_store 写入，只有一个 mip 层级
+// 数据存储在 Buffer 中，克隆得到的纹理和原纹理共享同一块数据，着色器只需要不可变引用就可以写入
+#[derive(Debug, Clone)]
+pub struct StorageTexture {
+    pub width: u32,
+    pub height: u32,
+    pub depth_or_array_layers: u32,
+    // 不支持 Cube 和 CubeArray
+    pub dimension: TextureViewDimension,
+    pub format: TextureFormat,
+    pub data: Buffer,
+}
+impl StorageTexture {
+    // 创建内容为 0 的存储纹理
+    pub fn new(
+        width: u32,
+        height: u32,
+        depth_or_array_layers: u32,
+        dimension: TextureViewDimension,
+        format: TextureFormat,
+    ) -> Self {
+        assert!(
+            !matches!(
+                dimension,
+                TextureViewDimension::Cube | TextureViewDimension::CubeArray
+            ),
+            "storage textures cannot have {dimension:?} dimension"
+        );
+        assert!(
+            !format.is_compressed(),
+            "storage textures cannot use block-compressed format {format:?}"
+        );
+        let mut texture = Self {
+            width,
+            height,
+            depth_or_array_layers,
+            dimension,
+            format,
+            data: Buffer::new(0),
+        };
+        let size = texture
+            .checked_size_in_bytes()
+            .expect("storage texture size overflows usize");
+        texture.data = Buffer::new(size);
+        texture
+    }
+
+    // 复制纹理的第 0 个 mip 层级
+    pub fn from_texture(texture: &Texture) -> Self {
+        let storage = Self::new(
+            texture.width,
+            texture.height,
+            texture.depth_or_array_layers,
+            texture.dimension,
+            texture.format,
+        );
+        for layer in 0..texture.array_layer_count() {
+            storage.data.write_bytes(
+                storage.layer_offset(layer),
+                texture.mip_level_data(layer, 0),
+            );
+        }
+        storage
+    }
+
+    // 转换为只有一个 mip 层级的纹理，写入完成之后可以绑定到绑定组中采样
+    pub fn to_texture(&self) -> Texture {
+        Texture {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: self.depth_or_array_layers,
+            dimension: self.dimension,
+            format: self.format,
+            mip_level_count: 1,
+            data: self.data.read_bytes(0, self.size_in_bytes()),
+        }
+    }
+
+    pub fn size(&self) -> UVec3 {
+        let depth = match self.dimension {
+            TextureViewDimension::D3 => self.depth_or_array_layers,
+            _ => 1,
+        };
+        UVec3::new(self.width, self.height, depth)
+    }
+
+    pub fn array_layer_count(&self) -> u32 {
+        match self.dimension {
+            TextureViewDimension::D3 => 1,
+            _ => self.depth_or_array_layers,
+        }
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.checked_size_in_bytes()
+            .expect("storage texture size overflows usize")
+    }
+
+    // 和 Texture::checked_size_in_bytes 一样在 usize 中计算，溢出时返回 None
+    pub fn checked_size_in_bytes(&self) -> Option<usize> {
+        (self.width as usize)
+            .checked_mul(self.height as usize)?
+            .checked_mul(self.depth_or_array_layers as usize)?
+            .checked_mul(self.format.size())
+    }
+
+    // 创建时已经检查过总字节数不会溢出，一层的字节数也不会溢出
+    fn layer_offset(&self, layer: u32) -> usize {
+        let size = self.size();
+        layer as usize * size.x as usize * size.y as usize * size.z as usize * self.format.size()
+    }
+
+    // 纹素在 data 中的位置，坐标或者数组层超出范围时返回 None
+    fn texel_offset(&self, coords: UVec3, layer: u32) -> Option<usize> {
+        let size = self.size();
+        if coords.x >= size.x
+            || coords.y >= size.y
+            || coords.z >= size.z
+            || layer >= self.array_layer_count()
+        {
+            return None;
+        }
+        let index = (coords.z as usize * size.y as usize + coords.y as usize) * size.x as usize
+            + coords.x as usize;
+        Some(self.layer_offset(layer) + index * self.format.size())
+    }
+
+    // 读取纹素，超出范围时返回 0
+    pub fn load(&self, coords: UVec3, layer: u32) -> Vec4 {
+        match self.texel_offset(coords, layer) {
+            Some(offset) => self
+                .format
+                .decode(&self.data.read_bytes(offset, self.format.size())),
+            None => Vec4::ZERO,
+        }
+    }
+
+    // 写入纹素，超出范围时不写入
+    pub fn store(&self, coords: UVec3, layer: u32, value: Vec4) {
+        if let Some(offset) = self.texel_offset(coords, layer) {
+            let mut texel = vec![0; self.format.size()];
+            self.format.encode(value, &mut texel);
+            self.data.write_bytes(offset, &texel);
+        }
+    }
+}
+
+// 沿 axis 方向把纹素下采样为 dst_len 个，另外两个方向保持不变，size 为 src 的大小
+fn downsample(
+    src: &[Vec4],
+    size: UVec3,
+    axis: usize,
+    dst_len: u32,
+    filter: MipmapFilter,
+) -> Vec<Vec4> {
+    let src_len = [size.x, size.y, size.z][axis];
+    if src_len == dst_len {
+        return src.to_vec();
+    }
+    let mut dst_size = [size.x, size.y, size.z];
+    dst_size[axis] = dst_len;
+    let scale = src_len as f32 / dst_len as f32;
+    let radius = (filter.support() * scale).ceil() as i64;
+
+    // 每个目标纹素的权重只和它在这个方向上的位置有关，提前计算
+    let kernels: Vec<Vec<(u32, f32)>> = (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let first = center.floor() as i64 - radius;
+            let mut taps: Vec<(u32, f32)> = (first..=first + 2 * radius)
+                .filter_map(|j| {
+                    let weight = filter.weight((j as f32 + 0.5 - center) / scale);
+                    // 超出边缘的纹素按照 clamp to edge 处理
+                    (weight != 0.).then(|| (j.clamp(0, src_len as i64 - 1) as u32, weight))
+                })
+                .collect();
+            let sum: f32 = taps.iter().map(|(_, weight)| weight).sum();
+            taps.iter_mut().for_each(|(_, weight)| *weight /= sum);
+            taps
+        })
+        .collect();
+
+    // 沿 axis 方向相邻两个纹素在 src 中的距离
+    let stride = [1, size.x, size.x * size.y][axis] as usize;
+    let mut dst = Vec::with_capacity(dst_size.iter().product::<u32>() as usize);
+    for z in 0..dst_size[2] {
+        for y in 0..dst_size[1] {
+            for x in 0..dst_size[0] {
+                let mut coords = [x, y, z];
+                let i = coords[axis];
+                coords[axis] = 0;
+                let base = ((coords[2] * size.y + coords[1]) * size.x + coords[0]) as usize;
+                let value = kernels[i as usize]
+                    .iter()
+                    .fold(Vec4::ZERO, |acc, &(j, weight)| {
+                        acc + src[base + j as usize * stride] * weight
+                    });
+                dst.push(value);
+            }
+        }
+    }
+    dst
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0. {
+        return 1.;
+    }
+    let x = x * std::f32::consts::PI;
+    x.sin() / x
+}
+
+// 第一类零阶修正贝塞尔函数，级数展开
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.;
+    let mut term = 1.;
+    let half_x_squared = x * x / 4.;
+    for k in 1..32 {
+        term *= half_x_squared / (k * k) as f32;
+        sum += term;
+        if term < sum * 1e-7 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4x4 的 R32Float 纹理，每层的纹素依次为 offset + 0..16
+    fn ramp_texture(layers: u32) -> Texture {
+        let values: Vec<f32> = (0..layers)
+            .flat_map(|layer| (0..16).map(move |i| (layer * 100 + i) as f32))
+            .collect();
+        Texture {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: layers,
+            dimension: if layers > 1 {
+                TextureViewDimension::D2Array
+            } else {
+                TextureViewDimension::D2
+            },
+            format: TextureFormat::R32Float,
+            mip_level_count: 1,
+            data: bytemuck::cast_slice(&values).to_vec(),
+        }
+    }
+
+    #[test]
+    fn box_filtered_mipmaps() {
+        let mut texture = ramp_texture(2);
+        texture.generate_mipmaps(MipmapFilter::Box);
+        assert_eq!(texture.mip_level_count, 3);
+        assert_eq!(texture.data.len(), 2 * (16 + 4 + 1) * 4);
+        assert!(texture.is_valid());
+        for layer in 0..2 {
+            let offset = (layer * 100) as f32;
+            let texel = |x, y, level| texture.texel(UVec3::new(x, y, 0), layer, level).x;
+            // 第 0 层保持不变
+            assert_eq!(texel(3, 2, 0), offset + 11.);
+            // 每个纹素是上一层相邻 2x2 纹素的平均
+            assert_eq!(texel(0, 0, 1), offset + 2.5);
+            assert_eq!(texel(1, 0, 1), offset + 4.5);
+            assert_eq!(texel(0, 1, 1), offset + 10.5);
+            assert_eq!(texel(1, 1, 1), offset + 12.5);
+            assert_eq!(texel(0, 0, 2), offset + 7.5);
+        }
+    }
+
+    #[test]
+    fn kaiser_mipmaps_keep_constant_texture() {
+        let mut texture = ramp_texture(1);
+        texture.data = bytemuck::cast_slice(&[0.25f32; 16]).to_vec();
+        texture.generate_mipmaps(MipmapFilter::Kaiser);
+        assert_eq!(texture.mip_level_count, 3);
+        for level in 1..3 {
+            let value = texture.texel(UVec3::ZERO, 0, level).x;
+            assert!((value - 0.25).abs() < 1e-6, "level {level}: {value}");
+        }
+    }
+
+    #[test]
+    fn mip_level_sizes() {
+        let texture = ramp_texture(1);
+        assert_eq!(Texture::max_mip_level_count(UVec3::new(4, 4, 1)), 3);
+        assert_eq!(Texture::max_mip_level_count(UVec3::new(5, 3, 1)), 3);
+        assert_eq!(Texture::max_mip_level_count(UVec3::new(1, 1, 1)), 1);
+        assert_eq!(texture.mip_level_size(1), UVec3::new(2, 2, 1));
+        assert_eq!(texture.mip_level_size(5), UVec3::new(1, 1, 1));
+    }
+}
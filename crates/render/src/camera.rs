@@ -4,6 +4,7 @@ use math::{Mat4, Vec2, Vec3, Vec4};
 pub trait CameraProjection {
     fn get_projection_matrix(&self) -> Mat4;
     fn update(&mut self, width: f32, height: f32);
+    fn near(&self) -> f32;
     fn far(&self) -> f32;
 }
 pub struct PerspectiveProjection {
@@ -76,6 +77,10 @@ impl CameraProjection for PerspectiveProjection {
         self.aspect_ratio = width / height;
     }
 
+    fn near(&self) -> f32 {
+        self.near
+    }
+
     fn far(&self) -> f32 {
         self.far
     }
@@ -1,7 +1,10 @@
 use std::vec;
 
 use math::Vec4;
-use pipeline::{BindType, Sampler, Texture};
+use pipeline::{
+    AddressMode, BindType, FilterMode, Sampler, SamplerDescriptor, Texture, TextureFormat,
+    TextureViewDimension,
+};
 
 use crate::{
     shader_uniform::{StandardMaterialFlags, StandardMaterialUniform},
@@ -137,8 +140,36 @@ pub struct StandardMaterial {
     /// make sure your meshes have their tangents set.
     ///
     /// [`Mesh::generate_tangents`]: bevy_render::mesh::Mesh::generate_tangents
+    ///
+    /// Two-component normal maps ([`TextureFormat::Rg8Unorm`], [`TextureFormat::Rg16Unorm`],
+    /// [`TextureFormat::Bc5RgUnorm`] or [`TextureFormat::EacRg11Unorm`])
+    /// only store x and y, the z-component is reconstructed in the shader.
     pub normal_map_texture: Option<Texture>,
 
+    /// Scales the x and y components of the tangent space normal sampled from
+    /// [`normal_map_texture`], `normalTexture.scale` in glTF.
+    ///
+    /// Defaults to `1.0`. Values below `1.0` flatten the surface, values above exaggerate it.
+    ///
+    /// [`normal_map_texture`]: StandardMaterial::normal_map_texture
+    pub normal_map_scale: f32,
+
+    /// A second normal map blended on top of [`normal_map_texture`] to add fine surface detail.
+    ///
+    /// It is sampled with the same UVs and follows the same conventions as the normal map,
+    /// including [`flip_normal_map_y`]. See [`NormalMapBlend`] for how the two are combined.
+    ///
+    /// [`normal_map_texture`]: StandardMaterial::normal_map_texture
+    /// [`flip_normal_map_y`]: StandardMaterial::flip_normal_map_y
+    pub detail_normal_map_texture: Option<Texture>,
+
+    /// How [`detail_normal_map_texture`] is blended with the normal map.
+    ///
+    /// Defaults to [`NormalMapBlend::Whiteout`].
+    ///
+    /// [`detail_normal_map_texture`]: StandardMaterial::detail_normal_map_texture
+    pub detail_normal_map_blend: NormalMapBlend,
+
     /// Normal map textures authored for DirectX have their y-component flipped. Set this to flip
     /// it to right-handed conventions.
     pub flip_normal_map_y: bool,
@@ -230,6 +261,9 @@ impl Default for StandardMaterial {
             // <https://google.github.io/filament/Material%20Properties.pdf>
             reflectance: 0.5,
             normal_map_texture: None,
+            normal_map_scale: 1.0,
+            detail_normal_map_texture: None,
+            detail_normal_map_blend: NormalMapBlend::Whiteout,
             flip_normal_map_y: false,
             double_sided: false,
             fog_enabled: true,
@@ -238,6 +272,19 @@ impl Default for StandardMaterial {
     }
 }
 
+/// How a detail normal map is blended with the normal map in tangent space.
+///
+/// See <https://blog.selfshadow.com/publications/blending-in-detail/>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalMapBlend {
+    /// Adds the x and y components and keeps the z-component of the normal map.
+    /// Cheap, but flattens the detail on steep parts of the normal map.
+    Udn,
+    /// Adds the x and y components and multiplies the z-components.
+    #[default]
+    Whiteout,
+}
+
 impl StandardMaterial {
     // bevy中对于material的绑定方式，我们也按照这个来
 
@@ -263,6 +310,11 @@ impl StandardMaterial {
     // var normal_map_texture: texture_2d<f32>;
     // @group(1) @binding(10)
     // var normal_map_sampler: sampler;
+    // 我们没有 occlusion 纹理，法线贴图从 7 开始，之后是细节法线贴图
+    // @group(1) @binding(9)
+    // var detail_normal_map_texture: texture_2d<f32>;
+    // @group(1) @binding(10)
+    // var detail_normal_map_sampler: sampler;
 
     // 返回material 的绑定组
     pub fn get_material_bind_group(&self) -> Vec<BindType> {
@@ -282,6 +334,23 @@ impl StandardMaterial {
         if self.fog_enabled {
             flags |= StandardMaterialFlags::FOG_ENABLED;
         }
+        if let Some(texture) = &self.normal_map_texture {
+            if is_two_component_normal_map(texture.format) {
+                flags |= StandardMaterialFlags::TWO_COMPONENT_NORMAL_MAP;
+            }
+        }
+        if self.flip_normal_map_y {
+            flags |= StandardMaterialFlags::FLIP_NORMAL_MAP_Y;
+        }
+        if let Some(texture) = &self.detail_normal_map_texture {
+            flags |= StandardMaterialFlags::DETAIL_NORMAL_MAP;
+            if is_two_component_normal_map(texture.format) {
+                flags |= StandardMaterialFlags::TWO_COMPONENT_DETAIL_MAP;
+            }
+            if self.detail_normal_map_blend == NormalMapBlend::Whiteout {
+                flags |= StandardMaterialFlags::DETAIL_BLEND_WHITEOUT;
+            }
+        }
         let uniform = StandardMaterialUniform {
             base_color: self.base_color.to_vec4(),
             emissive: self.emissive.to_vec4(),
@@ -289,17 +358,79 @@ impl StandardMaterial {
             reflectance: self.reflectance,
             perceptual_roughness: self.perceptual_roughness,
             flags: flags.bits(),
+            normal_map_scale: self.normal_map_scale,
         };
+        // 没有纹理时使用 1x1 的后备纹理，保证绑定组满足 pbr 的绑定组布局
         vec![
             uniform.into(),
-            BindType::Texture(self.base_color_texture.clone().unwrap_or_default()),
-            BindType::Sampler(Sampler {}),
-            BindType::Texture(self.emissive_texture.clone().unwrap_or_default()),
-            BindType::Sampler(Sampler {}),
-            BindType::Texture(self.metallic_roughness_texture.clone().unwrap_or_default()),
-            BindType::Sampler(Sampler {}),
-            BindType::Texture(self.normal_map_texture.clone().unwrap_or_default()),
-            BindType::Sampler(Sampler {}),
+            BindType::Texture(
+                self.base_color_texture
+                    .clone()
+                    .unwrap_or_else(|| fallback_texture([255, 255, 255, 255])),
+            ),
+            BindType::Sampler(material_sampler()),
+            BindType::Texture(
+                self.emissive_texture
+                    .clone()
+                    .unwrap_or_else(|| fallback_texture([255, 255, 255, 255])),
+            ),
+            BindType::Sampler(material_sampler()),
+            BindType::Texture(
+                self.metallic_roughness_texture
+                    .clone()
+                    .unwrap_or_else(|| fallback_texture([255, 255, 255, 255])),
+            ),
+            BindType::Sampler(material_sampler()),
+            // 法线贴图的后备纹理是切线空间中朝上的法线
+            BindType::Texture(
+                self.normal_map_texture
+                    .clone()
+                    .unwrap_or_else(|| fallback_texture([128, 128, 255, 255])),
+            ),
+            BindType::Sampler(material_sampler()),
+            BindType::Texture(
+                self.detail_normal_map_texture
+                    .clone()
+                    .unwrap_or_else(|| fallback_texture([128, 128, 255, 255])),
+            ),
+            BindType::Sampler(material_sampler()),
         ]
     }
 }
+
+// 只有 xy 两个通道的法线贴图，和 bevy 一样按照纹理格式判断
+fn is_two_component_normal_map(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Rg8Unorm
+            | TextureFormat::Rg16Unorm
+            | TextureFormat::Bc5RgUnorm
+            | TextureFormat::EacRg11Unorm
+    )
+}
+
+// 材质纹理的采样器，和 glTF 默认的采样一致，重复寻址加线性过滤
+fn material_sampler() -> Sampler {
+    Sampler::new(SamplerDescriptor {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        address_mode_w: AddressMode::Repeat,
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        mipmap_filter: FilterMode::Linear,
+        ..Default::default()
+    })
+}
+
+// 单个像素的后备纹理，类似 bevy 的 FallbackImage
+fn fallback_texture(texel: [u8; 4]) -> Texture {
+    Texture {
+        width: 1,
+        height: 1,
+        depth_or_array_layers: 1,
+        dimension: TextureViewDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        mip_level_count: 1,
+        data: texel.to_vec(),
+    }
+}
@@ -69,6 +69,10 @@ impl Mesh {
     pub const ATTRIBUTE_JOINT_WEIGHT: MeshVertexAttribute =
         MeshVertexAttribute::new("Vertex_JointWeight", 5, VertexFormat::Float32x4);
 
+    /// Per vertex joint transform matrix index. Use in conjunction with [`Mesh::insert_attribute`]
+    pub const ATTRIBUTE_JOINT_INDEX: MeshVertexAttribute =
+        MeshVertexAttribute::new("Vertex_JointIndex", 6, VertexFormat::Uint16x4);
+
     pub fn new() -> Self {
         Mesh {
             attributes: Default::default(),
@@ -187,28 +191,97 @@ impl Mesh {
 #[derive(Clone, Debug)]
 pub enum VertexAttributeValues {
     Float32(Vec<f32>),
+    Sint32(Vec<i32>),
     Uint32(Vec<u32>),
     Float32x2(Vec<[f32; 2]>),
+    Sint32x2(Vec<[i32; 2]>),
+    Uint32x2(Vec<[u32; 2]>),
     Float32x3(Vec<[f32; 3]>),
+    Sint32x3(Vec<[i32; 3]>),
+    Uint32x3(Vec<[u32; 3]>),
     Float32x4(Vec<[f32; 4]>),
+    Sint32x4(Vec<[i32; 4]>),
+    Uint32x4(Vec<[u32; 4]>),
+    Sint16x2(Vec<[i16; 2]>),
+    Snorm16x2(Vec<[i16; 2]>),
+    Uint16x2(Vec<[u16; 2]>),
+    Unorm16x2(Vec<[u16; 2]>),
+    Sint16x4(Vec<[i16; 4]>),
+    Snorm16x4(Vec<[i16; 4]>),
+    Uint16x4(Vec<[u16; 4]>),
+    Unorm16x4(Vec<[u16; 4]>),
+    Sint8x2(Vec<[i8; 2]>),
+    Snorm8x2(Vec<[i8; 2]>),
+    Uint8x2(Vec<[u8; 2]>),
+    Unorm8x2(Vec<[u8; 2]>),
+    Sint8x4(Vec<[i8; 4]>),
+    Snorm8x4(Vec<[i8; 4]>),
+    Uint8x4(Vec<[u8; 4]>),
+    Unorm8x4(Vec<[u8; 4]>),
 }
 impl VertexAttributeValues {
     pub fn len(&self) -> usize {
         match self {
-            VertexAttributeValues::Uint32(v) => v.len(),
             VertexAttributeValues::Float32(v) => v.len(),
+            VertexAttributeValues::Sint32(v) => v.len(),
+            VertexAttributeValues::Uint32(v) => v.len(),
             VertexAttributeValues::Float32x2(v) => v.len(),
+            VertexAttributeValues::Sint32x2(v) => v.len(),
+            VertexAttributeValues::Uint32x2(v) => v.len(),
             VertexAttributeValues::Float32x3(v) => v.len(),
+            VertexAttributeValues::Sint32x3(v) => v.len(),
+            VertexAttributeValues::Uint32x3(v) => v.len(),
             VertexAttributeValues::Float32x4(v) => v.len(),
+            VertexAttributeValues::Sint32x4(v) => v.len(),
+            VertexAttributeValues::Uint32x4(v) => v.len(),
+            VertexAttributeValues::Sint16x2(v) => v.len(),
+            VertexAttributeValues::Snorm16x2(v) => v.len(),
+            VertexAttributeValues::Uint16x2(v) => v.len(),
+            VertexAttributeValues::Unorm16x2(v) => v.len(),
+            VertexAttributeValues::Sint16x4(v) => v.len(),
+            VertexAttributeValues::Snorm16x4(v) => v.len(),
+            VertexAttributeValues::Uint16x4(v) => v.len(),
+            VertexAttributeValues::Unorm16x4(v) => v.len(),
+            VertexAttributeValues::Sint8x2(v) => v.len(),
+            VertexAttributeValues::Snorm8x2(v) => v.len(),
+            VertexAttributeValues::Uint8x2(v) => v.len(),
+            VertexAttributeValues::Unorm8x2(v) => v.len(),
+            VertexAttributeValues::Sint8x4(v) => v.len(),
+            VertexAttributeValues::Snorm8x4(v) => v.len(),
+            VertexAttributeValues::Uint8x4(v) => v.len(),
+            VertexAttributeValues::Unorm8x4(v) => v.len(),
         }
     }
     pub fn get_bytes(&self) -> &[u8] {
         match self {
-            VertexAttributeValues::Uint32(v) => cast_slice(v),
             VertexAttributeValues::Float32(v) => cast_slice(v),
+            VertexAttributeValues::Sint32(v) => cast_slice(v),
+            VertexAttributeValues::Uint32(v) => cast_slice(v),
             VertexAttributeValues::Float32x2(v) => cast_slice(v),
+            VertexAttributeValues::Sint32x2(v) => cast_slice(v),
+            VertexAttributeValues::Uint32x2(v) => cast_slice(v),
             VertexAttributeValues::Float32x3(v) => cast_slice(v),
+            VertexAttributeValues::Sint32x3(v) => cast_slice(v),
+            VertexAttributeValues::Uint32x3(v) => cast_slice(v),
             VertexAttributeValues::Float32x4(v) => cast_slice(v),
+            VertexAttributeValues::Sint32x4(v) => cast_slice(v),
+            VertexAttributeValues::Uint32x4(v) => cast_slice(v),
+            VertexAttributeValues::Sint16x2(v) => cast_slice(v),
+            VertexAttributeValues::Snorm16x2(v) => cast_slice(v),
+            VertexAttributeValues::Uint16x2(v) => cast_slice(v),
+            VertexAttributeValues::Unorm16x2(v) => cast_slice(v),
+            VertexAttributeValues::Sint16x4(v) => cast_slice(v),
+            VertexAttributeValues::Snorm16x4(v) => cast_slice(v),
+            VertexAttributeValues::Uint16x4(v) => cast_slice(v),
+            VertexAttributeValues::Unorm16x4(v) => cast_slice(v),
+            VertexAttributeValues::Sint8x2(v) => cast_slice(v),
+            VertexAttributeValues::Snorm8x2(v) => cast_slice(v),
+            VertexAttributeValues::Uint8x2(v) => cast_slice(v),
+            VertexAttributeValues::Unorm8x2(v) => cast_slice(v),
+            VertexAttributeValues::Sint8x4(v) => cast_slice(v),
+            VertexAttributeValues::Snorm8x4(v) => cast_slice(v),
+            VertexAttributeValues::Uint8x4(v) => cast_slice(v),
+            VertexAttributeValues::Unorm8x4(v) => cast_slice(v),
         }
     }
 }
@@ -216,27 +289,135 @@ impl VertexAttributeValues {
 impl From<&VertexAttributeValues> for VertexFormat {
     fn from(values: &VertexAttributeValues) -> Self {
         match values {
-            VertexAttributeValues::Uint32(_) => VertexFormat::Uint32,
             VertexAttributeValues::Float32(_) => VertexFormat::Float32,
+            VertexAttributeValues::Sint32(_) => VertexFormat::Sint32,
+            VertexAttributeValues::Uint32(_) => VertexFormat::Uint32,
             VertexAttributeValues::Float32x2(_) => VertexFormat::Float32x2,
+            VertexAttributeValues::Sint32x2(_) => VertexFormat::Sint32x2,
+            VertexAttributeValues::Uint32x2(_) => VertexFormat::Uint32x2,
             VertexAttributeValues::Float32x3(_) => VertexFormat::Float32x3,
+            VertexAttributeValues::Sint32x3(_) => VertexFormat::Sint32x3,
+            VertexAttributeValues::Uint32x3(_) => VertexFormat::Uint32x3,
             VertexAttributeValues::Float32x4(_) => VertexFormat::Float32x4,
+            VertexAttributeValues::Sint32x4(_) => VertexFormat::Sint32x4,
+            VertexAttributeValues::Uint32x4(_) => VertexFormat::Uint32x4,
+            VertexAttributeValues::Sint16x2(_) => VertexFormat::Sint16x2,
+            VertexAttributeValues::Snorm16x2(_) => VertexFormat::Snorm16x2,
+            VertexAttributeValues::Uint16x2(_) => VertexFormat::Uint16x2,
+            VertexAttributeValues::Unorm16x2(_) => VertexFormat::Unorm16x2,
+            VertexAttributeValues::Sint16x4(_) => VertexFormat::Sint16x4,
+            VertexAttributeValues::Snorm16x4(_) => VertexFormat::Snorm16x4,
+            VertexAttributeValues::Uint16x4(_) => VertexFormat::Uint16x4,
+            VertexAttributeValues::Unorm16x4(_) => VertexFormat::Unorm16x4,
+            VertexAttributeValues::Sint8x2(_) => VertexFormat::Sint8x2,
+            VertexAttributeValues::Snorm8x2(_) => VertexFormat::Snorm8x2,
+            VertexAttributeValues::Uint8x2(_) => VertexFormat::Uint8x2,
+            VertexAttributeValues::Unorm8x2(_) => VertexFormat::Unorm8x2,
+            VertexAttributeValues::Sint8x4(_) => VertexFormat::Sint8x4,
+            VertexAttributeValues::Snorm8x4(_) => VertexFormat::Snorm8x4,
+            VertexAttributeValues::Uint8x4(_) => VertexFormat::Uint8x4,
+            VertexAttributeValues::Unorm8x4(_) => VertexFormat::Unorm8x4,
         }
     }
 }
 
+impl From<Vec<f32>> for VertexAttributeValues {
+    fn from(value: Vec<f32>) -> Self {
+        VertexAttributeValues::Float32(value)
+    }
+}
+impl From<Vec<i32>> for VertexAttributeValues {
+    fn from(value: Vec<i32>) -> Self {
+        VertexAttributeValues::Sint32(value)
+    }
+}
+impl From<Vec<u32>> for VertexAttributeValues {
+    fn from(value: Vec<u32>) -> Self {
+        VertexAttributeValues::Uint32(value)
+    }
+}
 impl From<Vec<[f32; 2]>> for VertexAttributeValues {
     fn from(value: Vec<[f32; 2]>) -> Self {
         VertexAttributeValues::Float32x2(value)
     }
 }
+impl From<Vec<[i32; 2]>> for VertexAttributeValues {
+    fn from(value: Vec<[i32; 2]>) -> Self {
+        VertexAttributeValues::Sint32x2(value)
+    }
+}
+impl From<Vec<[u32; 2]>> for VertexAttributeValues {
+    fn from(value: Vec<[u32; 2]>) -> Self {
+        VertexAttributeValues::Uint32x2(value)
+    }
+}
 impl From<Vec<[f32; 3]>> for VertexAttributeValues {
     fn from(value: Vec<[f32; 3]>) -> Self {
         VertexAttributeValues::Float32x3(value)
     }
 }
+impl From<Vec<[i32; 3]>> for VertexAttributeValues {
+    fn from(value: Vec<[i32; 3]>) -> Self {
+        VertexAttributeValues::Sint32x3(value)
+    }
+}
+impl From<Vec<[u32; 3]>> for VertexAttributeValues {
+    fn from(value: Vec<[u32; 3]>) -> Self {
+        VertexAttributeValues::Uint32x3(value)
+    }
+}
 impl From<Vec<[f32; 4]>> for VertexAttributeValues {
     fn from(value: Vec<[f32; 4]>) -> Self {
         VertexAttributeValues::Float32x4(value)
     }
 }
+impl From<Vec<[i32; 4]>> for VertexAttributeValues {
+    fn from(value: Vec<[i32; 4]>) -> Self {
+        VertexAttributeValues::Sint32x4(value)
+    }
+}
+impl From<Vec<[u32; 4]>> for VertexAttributeValues {
+    fn from(value: Vec<[u32; 4]>) -> Self {
+        VertexAttributeValues::Uint32x4(value)
+    }
+}
+impl From<Vec<[i16; 2]>> for VertexAttributeValues {
+    fn from(value: Vec<[i16; 2]>) -> Self {
+        VertexAttributeValues::Sint16x2(value)
+    }
+}
+impl From<Vec<[u16; 2]>> for VertexAttributeValues {
+    fn from(value: Vec<[u16; 2]>) -> Self {
+        VertexAttributeValues::Uint16x2(value)
+    }
+}
+impl From<Vec<[i16; 4]>> for VertexAttributeValues {
+    fn from(value: Vec<[i16; 4]>) -> Self {
+        VertexAttributeValues::Sint16x4(value)
+    }
+}
+impl From<Vec<[u16; 4]>> for VertexAttributeValues {
+    fn from(value: Vec<[u16; 4]>) -> Self {
+        VertexAttributeValues::Uint16x4(value)
+    }
+}
+impl From<Vec<[i8; 2]>> for VertexAttributeValues {
+    fn from(value: Vec<[i8; 2]>) -> Self {
+        VertexAttributeValues::Sint8x2(value)
+    }
+}
+impl From<Vec<[u8; 2]>> for VertexAttributeValues {
+    fn from(value: Vec<[u8; 2]>) -> Self {
+        VertexAttributeValues::Uint8x2(value)
+    }
+}
+impl From<Vec<[i8; 4]>> for VertexAttributeValues {
+    fn from(value: Vec<[i8; 4]>) -> Self {
+        VertexAttributeValues::Sint8x4(value)
+    }
+}
+impl From<Vec<[u8; 4]>> for VertexAttributeValues {
+    fn from(value: Vec<[u8; 4]>) -> Self {
+        VertexAttributeValues::Uint8x4(value)
+    }
+}
@@ -2,5 +2,6 @@ pub mod shader_function;
 pub mod shader_type;
 pub mod shader_uniform;
 pub mod pbr_shder;
+pub mod pbr_bindings;
 pub mod mesh_function;
 pub mod pbr_function;
@@ -0,0 +1,118 @@
+use pipeline::{
+    AddressSpace, BindGroupLayout, BindGroupLayoutEntry, BindingType, PipelineLayout,
+    SamplerBindingType, ShaderStages, ShaderStruct, TextureSampleType, TextureViewDimension,
+};
+
+use crate::shader_uniform::{MeshUniform, PointLightUniform, StandardMaterialUniform, ViewUniform};
+
+// pbr 着色器使用的绑定组和绑定索引，对应 bevy 中 wgsl 的 @group @binding
+
+// @group(0) 视图
+pub const VIEW_GROUP: usize = 0;
+pub const VIEW_UNIFORM_BINDING: usize = 0;
+pub const POINT_LIGHT_BINDING: usize = 1;
+
+// @group(1) 材质
+pub const MATERIAL_GROUP: usize = 1;
+pub const MATERIAL_UNIFORM_BINDING: usize = 0;
+pub const BASE_COLOR_TEXTURE_BINDING: usize = 1;
+pub const BASE_COLOR_SAMPLER_BINDING: usize = 2;
+pub const EMISSIVE_TEXTURE_BINDING: usize = 3;
+pub const EMISSIVE_SAMPLER_BINDING: usize = 4;
+pub const METALLIC_ROUGHNESS_TEXTURE_BINDING: usize = 5;
+pub const METALLIC_ROUGHNESS_SAMPLER_BINDING: usize = 6;
+pub const NORMAL_MAP_TEXTURE_BINDING: usize = 7;
+pub const NORMAL_MAP_SAMPLER_BINDING: usize = 8;
+pub const DETAIL_NORMAL_MAP_TEXTURE_BINDING: usize = 9;
+pub const DETAIL_NORMAL_MAP_SAMPLER_BINDING: usize = 10;
+
+// @group(2) 网格
+pub const MESH_GROUP: usize = 2;
+pub const MESH_UNIFORM_BINDING: usize = 0;
+
+fn uniform_entry<T: ShaderStruct>(
+    binding: usize,
+    visibility: ShaderStages,
+    has_dynamic_offset: bool,
+) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Uniform {
+            has_dynamic_offset,
+            min_binding_size: T::size(AddressSpace::Uniform),
+        },
+    }
+}
+
+fn texture_entries(texture_binding: usize, sampler_binding: usize) -> [BindGroupLayoutEntry; 2] {
+    [
+        BindGroupLayoutEntry {
+            binding: texture_binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+            },
+        },
+        BindGroupLayoutEntry {
+            binding: sampler_binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+        },
+    ]
+}
+
+pub fn view_bind_group_layout() -> BindGroupLayout {
+    BindGroupLayout::new(vec![
+        uniform_entry::<ViewUniform>(VIEW_UNIFORM_BINDING, ShaderStages::VERTEX_FRAGMENT, false),
+        uniform_entry::<PointLightUniform>(POINT_LIGHT_BINDING, ShaderStages::FRAGMENT, false),
+    ])
+}
+
+pub fn material_bind_group_layout() -> BindGroupLayout {
+    let mut entries = vec![uniform_entry::<StandardMaterialUniform>(
+        MATERIAL_UNIFORM_BINDING,
+        ShaderStages::FRAGMENT,
+        false,
+    )];
+    entries.extend(texture_entries(
+        BASE_COLOR_TEXTURE_BINDING,
+        BASE_COLOR_SAMPLER_BINDING,
+    ));
+    entries.extend(texture_entries(
+        EMISSIVE_TEXTURE_BINDING,
+        EMISSIVE_SAMPLER_BINDING,
+    ));
+    entries.extend(texture_entries(
+        METALLIC_ROUGHNESS_TEXTURE_BINDING,
+        METALLIC_ROUGHNESS_SAMPLER_BINDING,
+    ));
+    entries.extend(texture_entries(
+        NORMAL_MAP_TEXTURE_BINDING,
+        NORMAL_MAP_SAMPLER_BINDING,
+    ));
+    entries.extend(texture_entries(
+        DETAIL_NORMAL_MAP_TEXTURE_BINDING,
+        DETAIL_NORMAL_MAP_SAMPLER_BINDING,
+    ));
+    BindGroupLayout::new(entries)
+}
+
+// 网格的 uniform 使用动态偏移，多个网格的 uniform 可以放在同一个 DynamicUniformBuffer 中
+pub fn mesh_bind_group_layout() -> BindGroupLayout {
+    BindGroupLayout::new(vec![uniform_entry::<MeshUniform>(
+        MESH_UNIFORM_BINDING,
+        ShaderStages::VERTEX,
+        true,
+    )])
+}
+
+// pbr 管线布局，绑定组的顺序和上面的 group 索引一致
+pub fn pbr_pipeline_layout() -> PipelineLayout {
+    PipelineLayout::new(vec![
+        view_bind_group_layout(),
+        material_bind_group_layout(),
+        mesh_bind_group_layout(),
+    ])
+}
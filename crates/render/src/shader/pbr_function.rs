@@ -1,24 +1,64 @@
 use std::f32::consts::PI;
 
 use math::{Vec2, Vec3, Vec4};
-use pipeline::{texture_sample, Sampler, Texture};
 
-use crate::{shader_type::PbrInput, shader_uniform::PointLightUniform};
+use crate::{
+    shader_type::PbrInput,
+    shader_uniform::{PointLightUniform, StandardMaterialFlags},
+};
 
+// 对应 bevy pbr_functions.wgsl 的 apply_normal_mapping，纹理在片元着色器中采样之后传进来
+// detail_normal_map_sample 只有材质带有细节法线贴图时才有值
 pub fn apply_normal_mapping(
+    standard_material_flags: u32,
+    normal_map_scale: f32,
     world_normal: Vec3,
     world_tangent: Vec4,
-    uv: Vec2,
-    texture: &Texture,
-    _sampler: &Sampler,
+    normal_map_sample: Vec4,
+    detail_normal_map_sample: Option<Vec4>,
 ) -> Vec3 {
     // 切线空间计算，按照 mikktspace 标准
-    let mut N = world_normal;
+    let N = world_normal;
     let T = world_tangent.xyz();
     let B = world_tangent.w * N.cross(T);
-    let mut Nt = texture_sample(texture, _sampler, uv).xyz();
-    Nt = Nt * 2. - 1.;
-    Nt.x * T + Nt.y * B + Nt.z * N
+    let flip_y = standard_material_flags & StandardMaterialFlags::FLIP_NORMAL_MAP_Y.bits() != 0;
+    let two_component =
+        standard_material_flags & StandardMaterialFlags::TWO_COMPONENT_NORMAL_MAP.bits() != 0;
+    let mut Nt = tangent_space_normal(normal_map_sample, two_component, flip_y);
+    // glTF 的 normalTexture.scale，只缩放 xy 分量
+    Nt = Vec3::new(Nt.x * normal_map_scale, Nt.y * normal_map_scale, Nt.z);
+    if let Some(detail_normal_map_sample) = detail_normal_map_sample {
+        let two_component =
+            standard_material_flags & StandardMaterialFlags::TWO_COMPONENT_DETAIL_MAP.bits() != 0;
+        let Nd = tangent_space_normal(detail_normal_map_sample, two_component, flip_y);
+        // 在切线空间中混合两个法线，xy 的扰动相加
+        // UDN 保留基础法线的 z，whiteout 把两个 z 相乘，陡峭处的细节更明显
+        // https://blog.selfshadow.com/publications/blending-in-detail/
+        let z =
+            if standard_material_flags & StandardMaterialFlags::DETAIL_BLEND_WHITEOUT.bits() != 0 {
+                Nt.z * Nd.z
+            } else {
+                Nt.z
+            };
+        Nt = Vec3::new(Nt.x + Nd.x, Nt.y + Nd.y, z);
+    }
+    (Nt.x * T + Nt.y * B + Nt.z * N).normalize()
+}
+
+// 把法线贴图的采样值从 [0, 1] 映射到切线空间的 [-1, 1]
+// 双通道的法线贴图（RG、BC5）只存储 xy，z 由单位长度重建
+// DirectX 约定的法线贴图 y 轴朝下，需要翻转到 OpenGL 约定
+fn tangent_space_normal(sample: Vec4, two_component: bool, flip_y: bool) -> Vec3 {
+    let mut Nt = if two_component {
+        let xy = Vec2::new(sample.x, sample.y) * 2. - Vec2::ONE;
+        xy.extend((1. - xy.x * xy.x - xy.y * xy.y).max(0.).sqrt())
+    } else {
+        sample.xyz() * 2. - 1.
+    };
+    if flip_y {
+        Nt.y = -Nt.y;
+    }
+    Nt
 }
 
 pub fn F_AB(perceptual_roughness: f32, NoV: f32) -> Vec2 {
@@ -159,3 +199,89 @@ pub fn pbr(input: PbrInput, light_uniform: &PointLightUniform) -> Vec4 {
 
     (direct_light + emissive_light).extend(output_color.w)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_vec3(actual: Vec3, expected: [f32; 3]) {
+        let actual = [actual.x, actual.y, actual.z];
+        for (a, e) in actual.into_iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{actual:?} != {expected:?}");
+        }
+    }
+
+    // 切线空间和世界空间重合，结果就是归一化的切线空间法线
+    fn apply(flags: u32, scale: f32, sample: Vec4, detail: Option<Vec4>) -> Vec3 {
+        apply_normal_mapping(
+            flags,
+            scale,
+            Vec3::new(0., 0., 1.),
+            Vec4::new(1., 0., 0., 1.),
+            sample,
+            detail,
+        )
+    }
+
+    #[test]
+    fn two_component_reconstructs_z() {
+        // xy = (0.6, 0)，z 由单位长度重建为 0.8，忽略 b 通道
+        let Nt = tangent_space_normal(Vec4::new(0.8, 0.5, 0., 1.), true, false);
+        assert_vec3(Nt, [0.6, 0., 0.8]);
+        // 三通道直接使用 b 通道
+        let Nt = tangent_space_normal(Vec4::new(0.8, 0.5, 0., 1.), false, false);
+        assert_vec3(Nt, [0.6, 0., -1.]);
+        // xy 超出单位圆时 z 为 0
+        let Nt = tangent_space_normal(Vec4::new(1., 1., 0., 1.), true, false);
+        assert_vec3(Nt, [1., 1., 0.]);
+    }
+
+    #[test]
+    fn flip_y() {
+        let sample = Vec4::new(0.5, 0.8, 1., 1.);
+        assert_vec3(tangent_space_normal(sample, false, false), [0., 0.6, 1.]);
+        assert_vec3(tangent_space_normal(sample, false, true), [0., -0.6, 1.]);
+        assert_vec3(tangent_space_normal(sample, true, true), [0., -0.6, 0.8]);
+        let N = apply(
+            StandardMaterialFlags::FLIP_NORMAL_MAP_Y.bits(),
+            1.,
+            sample,
+            None,
+        );
+        assert_vec3(N, [0., -0.6 / 1.36f32.sqrt(), 1. / 1.36f32.sqrt()]);
+    }
+
+    #[test]
+    fn scale_only_affects_xy() {
+        // Nt = (0.5, 0, 1)
+        let sample = Vec4::new(0.75, 0.5, 1., 1.);
+        let flags = StandardMaterialFlags::empty().bits();
+        assert_vec3(apply(flags, 0., sample, None), [0., 0., 1.]);
+        let s = 0.5f32.sqrt();
+        assert_vec3(apply(flags, 2., sample, None), [s, 0., s]);
+    }
+
+    #[test]
+    fn detail_blend() {
+        // 基础法线 (0.6, 0, 0.8)，细节法线 (0, 0.6, 0.8)
+        let base = Vec4::new(0.8, 0.5, 0., 1.);
+        let detail = Some(Vec4::new(0.5, 0.8, 0., 1.));
+        let flags = (StandardMaterialFlags::TWO_COMPONENT_NORMAL_MAP
+            | StandardMaterialFlags::DETAIL_NORMAL_MAP
+            | StandardMaterialFlags::TWO_COMPONENT_DETAIL_MAP)
+            .bits();
+        // UDN 保留基础法线的 z
+        let len = (0.36f32 + 0.36 + 0.64).sqrt();
+        assert_vec3(
+            apply(flags, 1., base, detail),
+            [0.6 / len, 0.6 / len, 0.8 / len],
+        );
+        // whiteout 的 z 是两个 z 的乘积
+        let flags = flags | StandardMaterialFlags::DETAIL_BLEND_WHITEOUT.bits();
+        let len = (0.36f32 + 0.36 + 0.64 * 0.64).sqrt();
+        assert_vec3(
+            apply(flags, 1., base, detail),
+            [0.6 / len, 0.6 / len, 0.64 / len],
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    pbr_bindings::*,
     pbr_function::{apply_normal_mapping, pbr},
     shader_function::{
         construct_fragment_stage_mesh_input, construct_vertex_output, contruct_fragment_output,
@@ -10,23 +11,23 @@ use crate::{
 };
 use math::{Vec2, Vec3, Vec4};
 use pipeline::{
-    texture_sample, BindGroup, FragmentInput, FragmentOutput, Sampler, Texture, VertexInput,
+    texture_sample_grad, BindGroups, FragmentInput, FragmentOutput, ShaderStages, VertexInput,
     VertexOutput,
 };
 
-pub fn pbr_vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGroup>) -> VertexOutput {
+pub fn pbr_vertex_main(vertex_input: VertexInput, bind_groups: &BindGroups) -> VertexOutput {
     let in_position: Vec3 = vertex_input.location[0].into();
     let in_normal: Vec3 = vertex_input.location[1].into();
     let in_texture_uv: Vec2 = vertex_input.location[2].into();
     // todo 切线接入
     let in_tangent: Vec4 = vertex_input.location[3].into();
 
-    let view_uniform: ViewUniform = std::mem::take(&mut bind_groups[0][0]).into();
-    let light_uniform: PointLightUniform = std::mem::take(&mut bind_groups[0][1]).into();
-    let material_uniform: StandardMaterialUniform = std::mem::take(&mut bind_groups[1][0]).into();
-    let texture: Texture = std::mem::take(&mut bind_groups[1][1]).into();
-    let sampler: Sampler = std::mem::take(&mut bind_groups[1][2]).into();
-    let mesh_uniform: MeshUniform = std::mem::take(&mut bind_groups[2][0]).into();
+    let view_uniform: ViewUniform = bind_groups
+        .uniform(ShaderStages::VERTEX, VIEW_GROUP, VIEW_UNIFORM_BINDING)
+        .unwrap();
+    let mesh_uniform: MeshUniform = bind_groups
+        .uniform(ShaderStages::VERTEX, MESH_GROUP, MESH_UNIFORM_BINDING)
+        .unwrap();
 
     // println!("P{:?}", light_uniform);
     let clip_position = view_uniform.view_proj * in_position.extend(1.);
@@ -43,34 +44,108 @@ pub fn pbr_vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGrou
         world_tangent,
     };
 
-    bind_groups[0][0] = view_uniform.into();
-    bind_groups[0][1] = light_uniform.into();
-    bind_groups[1][0] = material_uniform.into();
-    bind_groups[1][1] = texture.into();
-    bind_groups[1][2] = sampler.into();
-    bind_groups[2][0] = mesh_uniform.into();
-
     construct_vertex_output(&out)
 }
 
-pub fn pbr_fragment_main(input: FragmentInput, bind_groups: &mut Vec<BindGroup>) -> FragmentOutput {
+pub fn pbr_fragment_main(input: FragmentInput, bind_groups: &BindGroups) -> FragmentOutput {
     let fragment_in = construct_fragment_stage_mesh_input(&input);
+    // uv 的导数，采样时用来选择 mip 层级
+    // FragmentState 没有开启 derivatives 时导数为空，按照零导数采样第 0 层
+    let uv_dpdx: Vec2 = input.location_dpdx.get(2).map_or(Vec2::ZERO, |&v| v.into());
+    let uv_dpdy: Vec2 = input.location_dpdy.get(2).map_or(Vec2::ZERO, |&v| v.into());
 
-    let view_uniform: ViewUniform = std::mem::take(&mut bind_groups[0][0]).into();
-    let light_uniform: PointLightUniform = std::mem::take(&mut bind_groups[0][1]).into();
-    let material_uniform: StandardMaterialUniform = std::mem::take(&mut bind_groups[1][0]).into();
-    let base_color_texture: Texture = std::mem::take(&mut bind_groups[1][1]).into();
-    let base_color_sampler: Sampler = std::mem::take(&mut bind_groups[1][2]).into();
-    let emissive_texture: Texture = std::mem::take(&mut bind_groups[1][3]).into();
-    let emissive_sampler: Sampler = std::mem::take(&mut bind_groups[1][4]).into();
-    let metallic_roughness_texture: Texture = std::mem::take(&mut bind_groups[1][5]).into();
-    let metallic_roughness_sampler: Sampler = std::mem::take(&mut bind_groups[1][6]).into();
-    let normal_map_texture: Texture = std::mem::take(&mut bind_groups[1][7]).into();
-    let normal_map_sampler: Sampler = std::mem::take(&mut bind_groups[1][8]).into();
+    let view_uniform: ViewUniform = bind_groups
+        .uniform(ShaderStages::FRAGMENT, VIEW_GROUP, VIEW_UNIFORM_BINDING)
+        .unwrap();
+    let light_uniform: PointLightUniform = bind_groups
+        .uniform(ShaderStages::FRAGMENT, VIEW_GROUP, POINT_LIGHT_BINDING)
+        .unwrap();
+    let material_uniform: StandardMaterialUniform = bind_groups
+        .uniform(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            MATERIAL_UNIFORM_BINDING,
+        )
+        .unwrap();
+    let base_color_texture = bind_groups
+        .texture(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            BASE_COLOR_TEXTURE_BINDING,
+        )
+        .unwrap();
+    let base_color_sampler = bind_groups
+        .sampler(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            BASE_COLOR_SAMPLER_BINDING,
+        )
+        .unwrap();
+    let emissive_texture = bind_groups
+        .texture(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            EMISSIVE_TEXTURE_BINDING,
+        )
+        .unwrap();
+    let emissive_sampler = bind_groups
+        .sampler(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            EMISSIVE_SAMPLER_BINDING,
+        )
+        .unwrap();
+    let metallic_roughness_texture = bind_groups
+        .texture(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            METALLIC_ROUGHNESS_TEXTURE_BINDING,
+        )
+        .unwrap();
+    let metallic_roughness_sampler = bind_groups
+        .sampler(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            METALLIC_ROUGHNESS_SAMPLER_BINDING,
+        )
+        .unwrap();
+    let normal_map_texture = bind_groups
+        .texture(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            NORMAL_MAP_TEXTURE_BINDING,
+        )
+        .unwrap();
+    let normal_map_sampler = bind_groups
+        .sampler(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            NORMAL_MAP_SAMPLER_BINDING,
+        )
+        .unwrap();
+    let detail_normal_map_texture = bind_groups
+        .texture(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            DETAIL_NORMAL_MAP_TEXTURE_BINDING,
+        )
+        .unwrap();
+    let detail_normal_map_sampler = bind_groups
+        .sampler(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            DETAIL_NORMAL_MAP_SAMPLER_BINDING,
+        )
+        .unwrap();
 
     let mut output_color = material_uniform.base_color;
-    output_color =
-        texture_sample(&base_color_texture, &base_color_sampler, fragment_in.uv) * output_color;
+    output_color = texture_sample_grad(
+        base_color_texture,
+        base_color_sampler,
+        fragment_in.uv,
+        uv_dpdx,
+        uv_dpdy,
+    ) * output_color;
 
     let mut pbr_input = PbrInput::default();
 
@@ -83,15 +158,24 @@ pub fn pbr_fragment_main(input: FragmentInput, bind_groups: &mut Vec<BindGroup>)
     let mut perceptual_roughness = material_uniform.perceptual_roughness;
     if material_uniform.flags & StandardMaterialFlags::EMISSIVE_TEXTURE.bits() != 0 {
         emissive = (emissive.xyz()
-            * texture_sample(&emissive_texture, &emissive_sampler, fragment_in.uv).xyz())
+            * texture_sample_grad(
+                emissive_texture,
+                emissive_sampler,
+                fragment_in.uv,
+                uv_dpdx,
+                uv_dpdy,
+            )
+            .xyz())
         .extend(1.)
     }
 
     if material_uniform.flags & StandardMaterialFlags::METALLIC_ROUGHNESS_TEXTURE.bits() != 0 {
-        let metallic_roughness = texture_sample(
-            &metallic_roughness_texture,
-            &metallic_roughness_sampler,
+        let metallic_roughness = texture_sample_grad(
+            metallic_roughness_texture,
+            metallic_roughness_sampler,
             fragment_in.uv,
+            uv_dpdx,
+            uv_dpdy,
         );
         metallic = metallic * metallic_roughness.z;
         perceptual_roughness = perceptual_roughness * metallic_roughness.y;
@@ -104,24 +188,32 @@ pub fn pbr_fragment_main(input: FragmentInput, bind_groups: &mut Vec<BindGroup>)
     pbr_input.world_position = fragment_in.world_position;
     pbr_input.world_normal = fragment_in.world_normal;
     pbr_input.V = (view_uniform.world_position - fragment_in.world_position.xyz()).normalize();
+    let normal_map_sample = texture_sample_grad(
+        normal_map_texture,
+        normal_map_sampler,
+        fragment_in.uv,
+        uv_dpdx,
+        uv_dpdy,
+    );
+    // 没有细节法线贴图时不采样后备纹理，避免对法线再做一次扰动
+    let mut detail_normal_map_sample = None;
+    if material_uniform.flags & StandardMaterialFlags::DETAIL_NORMAL_MAP.bits() != 0 {
+        detail_normal_map_sample = Some(texture_sample_grad(
+            detail_normal_map_texture,
+            detail_normal_map_sampler,
+            fragment_in.uv,
+            uv_dpdx,
+            uv_dpdy,
+        ));
+    }
     pbr_input.N = apply_normal_mapping(
+        material_uniform.flags,
+        material_uniform.normal_map_scale,
         fragment_in.world_normal,
         fragment_in.world_tangent,
-        fragment_in.uv,
-        &normal_map_texture,
-        &normal_map_sampler,
+        normal_map_sample,
+        detail_normal_map_sample,
     );
     let output_color = pbr(pbr_input, &light_uniform);
-    bind_groups[0][0] = view_uniform.into();
-    bind_groups[0][1] = light_uniform.into();
-    bind_groups[1][0] = material_uniform.into();
-    bind_groups[1][1] = base_color_texture.into();
-    bind_groups[1][2] = base_color_sampler.into();
-    bind_groups[1][3] = emissive_texture.into();
-    bind_groups[1][4] = emissive_sampler.into();
-    bind_groups[1][5] = metallic_roughness_texture.into();
-    bind_groups[1][6] = metallic_roughness_sampler.into();
-    bind_groups[1][7] = normal_map_texture.into();
-    bind_groups[1][8] = normal_map_sampler.into();
     contruct_fragment_output(output_color)
 }
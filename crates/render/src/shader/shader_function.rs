@@ -26,6 +26,7 @@ pub fn construct_vertex_output(mesh_vertex_output: &MeshVertexOutput) -> VertexO
     let mut out = VertexOutput {
         position: Vec4::ONE,
         location: vec![ShaderType::Vec4(Vec4::ZERO); 5],
+        clip_distances: Vec::new(),
     };
     out.position = mesh_vertex_output.position;
     out.location[0] = mesh_vertex_output.world_position.into();
@@ -1,5 +1,5 @@
 use math::{Mat4, Vec2, Vec3, Vec4};
-use pipeline::BindType;
+use pipeline::{impl_shader_struct, BindType};
 
 // NOTE: These must match the bit flags in bevy_pbr/src/render/pbr_types.wgsl!
 bitflags::bitflags! {
@@ -17,6 +17,9 @@ bitflags::bitflags! {
       const FLIP_NORMAL_MAP_Y          = (1 << 7);
       const FOG_ENABLED                = (1 << 8);
       const DEPTH_MAP                  = (1 << 9); // Used for parallax mapping
+      const DETAIL_NORMAL_MAP          = (1 << 10);
+      const TWO_COMPONENT_DETAIL_MAP   = (1 << 11);
+      const DETAIL_BLEND_WHITEOUT      = (1 << 12);
       const ALPHA_MODE_RESERVED_BITS   = (Self::ALPHA_MODE_MASK_BITS << Self::ALPHA_MODE_SHIFT_BITS); // ← Bitmask reserving bits for the `AlphaMode`
       const ALPHA_MODE_OPAQUE          = (0 << Self::ALPHA_MODE_SHIFT_BITS);                          // ← Values are just sequential values bitshifted into
       const ALPHA_MODE_MASK            = (1 << Self::ALPHA_MODE_SHIFT_BITS);                          //   the bitmask, and can range from 0 to 7.
@@ -71,6 +74,8 @@ pub struct StandardMaterialUniform {
     pub reflectance: f32,
     /// The [`StandardMaterialFlags`] accessible in the `wgsl` shader.
     pub flags: u32,
+    /// Scales the x and y components of the tangent space normal, glTF `normalTexture.scale`
+    pub normal_map_scale: f32,
 }
 impl Default for StandardMaterialUniform {
     fn default() -> Self {
@@ -81,6 +86,7 @@ impl Default for StandardMaterialUniform {
             metallic: 0.0,
             reflectance: 0.5,
             flags: StandardMaterialFlags::ALPHA_MODE_OPAQUE.bits(),
+            normal_map_scale: 1.0,
         }
     }
 }
@@ -97,57 +103,111 @@ pub struct PointLightUniform {
     pub position_radius: Vec4,
 }
 
-impl From<BindType> for PointLightUniform {
-    fn from(value: BindType) -> Self {
-        match value {
-            BindType::Uniform(v) => unsafe { *(v.as_ptr() as *const PointLightUniform) },
-            _ => panic!("wrong format corresponding"),
-        }
-    }
-}
+// 按照 WGSL 的内存布局编码，字段顺序必须和结构体声明一致
+// 注意 ViewUniform 中 vec3 的 world_position 后面需要补齐到 16 字节
+impl_shader_struct!(ViewUniform {
+    view_proj: Mat4,
+    inverse_view_porj: Mat4,
+    view: Mat4,
+    inverse_view: Mat4,
+    projectiton: Mat4,
+    inverse_projection: Mat4,
+    world_position: Vec3,
+    viewport: Vec4,
+});
+impl_shader_struct!(StandardMaterialUniform {
+    base_color: Vec4,
+    emissive: Vec4,
+    perceptual_roughness: f32,
+    metallic: f32,
+    reflectance: f32,
+    flags: u32,
+    normal_map_scale: f32,
+});
+impl_shader_struct!(MeshUniform {
+    model: Mat4,
+    inverse_transpose_model: Mat4,
+});
+impl_shader_struct!(PointLightUniform {
+    color_inverse_square_range: Vec4,
+    position_radius: Vec4,
+});
+
 impl From<PointLightUniform> for BindType {
     fn from(value: PointLightUniform) -> Self {
-        BindType::Uniform(bytemuck::cast_slice(&[value]).to_vec())
+        BindType::uniform(&value)
     }
 }
 
-impl From<BindType> for ViewUniform {
-    fn from(value: BindType) -> Self {
-        match value {
-            BindType::Uniform(v) => unsafe { *(v.as_ptr() as *const ViewUniform) },
-            _ => panic!("wrong format corresponding"),
-        }
-    }
-}
 impl From<ViewUniform> for BindType {
     fn from(value: ViewUniform) -> Self {
-        BindType::Uniform(bytemuck::cast_slice(&[value]).to_vec())
-    }
-}
-impl From<BindType> for StandardMaterialUniform {
-    fn from(value: BindType) -> Self {
-        match value {
-            BindType::Uniform(v) => unsafe { *(v.as_ptr() as *const StandardMaterialUniform) },
-            _ => panic!("wrong format corresponding"),
-        }
+        BindType::uniform(&value)
     }
 }
 impl From<StandardMaterialUniform> for BindType {
     fn from(value: StandardMaterialUniform) -> Self {
-        BindType::Uniform(bytemuck::cast_slice(&[value]).to_vec())
+        BindType::uniform(&value)
     }
 }
 
-impl From<BindType> for MeshUniform {
-    fn from(value: BindType) -> Self {
-        match value {
-            BindType::Uniform(v) => unsafe { *(v.as_ptr() as *const MeshUniform) },
-            _ => panic!("wrong format corresponding"),
-        }
-    }
-}
 impl From<MeshUniform> for BindType {
     fn from(value: MeshUniform) -> Self {
-        BindType::Uniform(bytemuck::cast_slice(&[value]).to_vec())
+        BindType::uniform(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pipeline::{AddressSpace, ShaderStruct};
+
+    fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+        bytemuck::pod_read_unaligned(&bytes[offset..offset + 4])
+    }
+
+    // 绑定组布局的 min_binding_size 和着色器的读取都依赖这些大小，字段顺序改变时数据会错位
+    #[test]
+    fn uniform_sizes() {
+        assert_eq!(ViewUniform::size(AddressSpace::Uniform), 416);
+        assert_eq!(StandardMaterialUniform::size(AddressSpace::Uniform), 64);
+        assert_eq!(MeshUniform::size(AddressSpace::Uniform), 128);
+        assert_eq!(PointLightUniform::size(AddressSpace::Uniform), 32);
+    }
+
+    #[test]
+    fn view_uniform_offsets() {
+        let view = ViewUniform {
+            world_position: Vec3::new(1., 2., 3.),
+            viewport: Vec4::new(4., 5., 6., 7.),
+            ..Default::default()
+        };
+        let bytes = view.encode(AddressSpace::Uniform);
+        // 六个矩阵之后是 world_position，viewport 按照 16 字节对齐
+        assert_eq!(f32_at(&bytes, 384), 1.);
+        assert_eq!(f32_at(&bytes, 392), 3.);
+        assert_eq!(f32_at(&bytes, 400), 4.);
+        assert_eq!(f32_at(&bytes, 412), 7.);
+    }
+
+    #[test]
+    fn standard_material_uniform_offsets() {
+        let material = StandardMaterialUniform {
+            perceptual_roughness: 1.,
+            metallic: 2.,
+            reflectance: 3.,
+            flags: StandardMaterialFlags::DOUBLE_SIDED.bits(),
+            normal_map_scale: 4.,
+            ..Default::default()
+        };
+        let bytes = material.encode(AddressSpace::Uniform);
+        assert_eq!(bytes.len(), 64);
+        assert_eq!(f32_at(&bytes, 32), 1.);
+        assert_eq!(f32_at(&bytes, 36), 2.);
+        assert_eq!(f32_at(&bytes, 40), 3.);
+        assert_eq!(
+            u32::from_le_bytes(bytes[44..48].try_into().unwrap()),
+            StandardMaterialFlags::DOUBLE_SIDED.bits()
+        );
+        assert_eq!(f32_at(&bytes, 48), 4.);
     }
 }
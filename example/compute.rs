@@ -0,0 +1,82 @@
+use pipeline::{
+    BindGroupLayout, BindGroupLayoutEntry, BindGroups, BindingType, Buffer, ComputeInput,
+    ComputePipeline, ComputePipelineDescriptor, PipelineLayout, ShaderStages, Workgroup,
+};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+// 并行规约求和，先在工作组共享内存中规约，再用原子操作累加到输出
+// @group(0) @binding(0) var<storage, read> input: array<u32>;
+// @group(0) @binding(1) var<storage, read_write> output: atomic<u32>;
+// var<workgroup> partial_sums: array<u32, 64>;
+fn reduce_main(input: ComputeInput, bind_groups: &BindGroups, workgroup: &Workgroup) {
+    let values = bind_groups.storage(ShaderStages::COMPUTE, 0, 0).unwrap();
+    let output = bind_groups.storage(ShaderStages::COMPUTE, 0, 1).unwrap();
+
+    let index = input.global_invocation_id.x as usize;
+    let local_index = input.local_invocation_index as usize;
+    let value = if index * 4 < values.size() {
+        values.atomic_load(index * 4)
+    } else {
+        0
+    };
+    workgroup.memory.atomic_store(local_index * 4, value);
+    workgroup.barrier();
+
+    let mut stride = WORKGROUP_SIZE as usize / 2;
+    while stride > 0 {
+        if local_index < stride {
+            let other = workgroup.memory.atomic_load((local_index + stride) * 4);
+            workgroup.memory.atomic_add(local_index * 4, other);
+        }
+        workgroup.barrier();
+        stride /= 2;
+    }
+
+    if local_index == 0 {
+        output.atomic_add(0, workgroup.memory.atomic_load(0));
+    }
+}
+
+fn main() {
+    let values: Vec<u32> = (1..=1000).collect();
+    let input = Buffer::from_bytes(bytemuck::cast_slice(&values));
+    let output = Buffer::new(4);
+
+    let layout = PipelineLayout::new(vec![BindGroupLayout::new(vec![
+        BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Storage {
+                read_only: true,
+                min_binding_size: 4,
+            },
+        },
+        BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Storage {
+                read_only: false,
+                min_binding_size: 4,
+            },
+        },
+    ])]);
+    let mut pipeline = ComputePipeline::new(ComputePipelineDescriptor {
+        layout: Some(&layout),
+        shader: &reduce_main,
+        workgroup_size: [WORKGROUP_SIZE, 1, 1],
+        workgroup_memory_size: WORKGROUP_SIZE as usize * 4,
+    });
+    pipeline
+        .set_bind_group(0, vec![input.into(), output.clone().into()], &[])
+        .unwrap();
+    pipeline
+        .dispatch_workgroups(values.len().div_ceil(WORKGROUP_SIZE as usize) as u32, 1, 1)
+        .unwrap();
+
+    println!(
+        "sum: {} expected: {}",
+        output.atomic_load(0),
+        values.iter().sum::<u32>()
+    );
+}
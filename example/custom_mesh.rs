@@ -1,11 +1,12 @@
 use math::{Vec2, Vec3, Vec4};
-use render::Mesh;
 use pipeline::{
-    BindGroup, FragmentInput, FragmentOutput, FragmentState, RenderSurface, Renderer,
-    RendererDescriptor, ShaderType, TextureFormat, VertexInput, VertexOutput, VertexState,
+    BindGroups, CompositeAlphaMode, DepthStencilState, FragmentInput, FragmentOutput,
+    FragmentState, PrimitiveState, RenderSurface, Renderer, RendererDescriptor, ShaderType,
+    TextureFormat, VertexInput, VertexOutput, VertexState,
 };
+use render::Mesh;
 
-fn vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGroup>) -> VertexOutput {
+fn vertex_main(vertex_input: VertexInput, bind_groups: &BindGroups) -> VertexOutput {
     let in_postion: Vec3 = vertex_input.location[0].into();
     let in_color: Vec4 = vertex_input.location[1].into();
 
@@ -15,6 +16,7 @@ fn vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGroup>) -> V
     let mut out = VertexOutput {
         location: vec![ShaderType::Vec4(Vec4::ZERO), ShaderType::Vec2(Vec2::ONE)],
         position: Vec4::ONE,
+        clip_distances: Vec::new(),
     };
 
     out.position = Vec4::new(in_postion.x, in_postion.y, in_postion.z, 1.);
@@ -22,7 +24,7 @@ fn vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGroup>) -> V
     out
 }
 
-fn fragment_main(input: FragmentInput, bind_groups: &mut Vec<BindGroup>) -> FragmentOutput {
+fn fragment_main(input: FragmentInput, bind_groups: &BindGroups) -> FragmentOutput {
     let in_color: Vec4 = input.location[0].into();
     FragmentOutput {
         frag_depth: None,
@@ -43,23 +45,31 @@ fn main() {
     );
     println!("{:?}", mesh.get_vertex_buffer_layout());
     let desc = RendererDescriptor {
+        layout: None,
         surface: RenderSurface {
             format: TextureFormat::Rgba8Unorm,
             height: 1000,
             width: 1000,
+            alpha_mode: CompositeAlphaMode::Opaque,
         },
         vertex: VertexState {
-            shader: vertex_main,
+            shader: &vertex_main,
             layout: &mesh.get_vertex_buffer_layout(),
         },
         fragment: FragmentState {
-            shader: fragment_main,
+            shader: &fragment_main,
+            derivatives: false,
         },
+        primitive: PrimitiveState::default(),
+        depth_stencil: Some(DepthStencilState::default()),
+        occlusion_query_set: None,
     };
     let mut renderer = Renderer::new(desc);
     let binding = mesh.get_vertex_buffer_data();
     renderer.set_vertex_buffer(&binding);
-    renderer.draw(0..mesh.count_vertices() as u32);
+    renderer
+        .draw(0..mesh.count_vertices() as u32, 0..1)
+        .unwrap();
     image::save_buffer(
         "image_mesh.png",
         &renderer.frame_buffer,
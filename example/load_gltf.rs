@@ -1,55 +1,68 @@
 use loader::load_gltf;
 use math::{Vec2, Vec3, Vec4};
 use pipeline::{
-    texture_sample, BindGroup, BindType, FragmentInput, FragmentOutput, FragmentState,
-    RenderSurface, Renderer, RendererDescriptor, Sampler, ShaderType, Texture, TextureFormat,
-    VertexInput, VertexOutput, VertexState,
+    texture_sample_grad, BindGroups, BindType, CompositeAlphaMode, DepthStencilState,
+    FragmentInput, FragmentOutput, FragmentState, PrimitiveState, RenderSurface, Renderer,
+    RendererDescriptor, ShaderStages, ShaderType, TextureFormat, VertexInput, VertexOutput,
+    VertexState,
+};
+use render::{
+    pbr_bindings::{
+        BASE_COLOR_SAMPLER_BINDING, BASE_COLOR_TEXTURE_BINDING, MATERIAL_GROUP, VIEW_GROUP,
+        VIEW_UNIFORM_BINDING,
+    },
+    shader_uniform::ViewUniform,
+    Camera, Transform,
 };
-use render::{shader_uniform::ViewUniform, Camera, Transform};
 
-fn vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGroup>) -> VertexOutput {
+fn vertex_main(vertex_input: VertexInput, bind_groups: &BindGroups) -> VertexOutput {
     let mut out = VertexOutput {
         location: vec![ShaderType::Vec4(Vec4::ZERO), ShaderType::Vec2(Vec2::ONE)],
         position: Vec4::ONE,
+        clip_distances: Vec::new(),
     };
     let in_postion: Vec3 = vertex_input.location[0].into();
     let in_normal: Vec3 = vertex_input.location[1].into();
     let in_texture_uv: Vec2 = vertex_input.location[2].into();
 
-    let view_uniform: ViewUniform = std::mem::take(&mut bind_groups[0][0]).into();
-    let texture: Texture = std::mem::take(&mut bind_groups[1][1]).into();
-    let sampler: Sampler = std::mem::take(&mut bind_groups[1][2]).into();
+    let view_uniform: ViewUniform = bind_groups
+        .uniform(ShaderStages::VERTEX, VIEW_GROUP, VIEW_UNIFORM_BINDING)
+        .unwrap();
 
     //
     let clip_postion = view_uniform.view_proj * in_postion.extend(1.);
 
-    // 还原bindgroup
-    bind_groups[0][0] = view_uniform.into();
-    bind_groups[1][1] = texture.into();
-    bind_groups[1][2] = sampler.into();
-
     out.position = clip_postion.into();
     out.location[0] = in_normal.into();
     out.location[1] = in_texture_uv.into();
     out
 }
 
-fn fragment_main(input: FragmentInput, bind_groups: &mut Vec<BindGroup>) -> FragmentOutput {
+fn fragment_main(input: FragmentInput, bind_groups: &BindGroups) -> FragmentOutput {
     let in_normal: Vec3 = input.location[0].into();
     let in_texture_uv: Vec2 = input.location[1].into();
+    // FragmentState 没有开启 derivatives 时导数为空，按照零导数采样第 0 层
+    let uv_dpdx: Vec2 = input.location_dpdx.get(1).map_or(Vec2::ZERO, |&v| v.into());
+    let uv_dpdy: Vec2 = input.location_dpdy.get(1).map_or(Vec2::ZERO, |&v| v.into());
     // println!("tex_coord:{:?}", in_texture_uv);
 
-    let view_uniform: ViewUniform = std::mem::take(&mut bind_groups[0][0]).into();
-    // println!("view_uniform:{:?}", view_uniform);
-    let texture: Texture = std::mem::take(&mut bind_groups[1][1]).into();
-    let sampler: Sampler = std::mem::take(&mut bind_groups[1][2]).into();
-
-    let in_color = texture_sample(&texture, &sampler, in_texture_uv);
+    let texture = bind_groups
+        .texture(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            BASE_COLOR_TEXTURE_BINDING,
+        )
+        .unwrap();
+    let sampler = bind_groups
+        .sampler(
+            ShaderStages::FRAGMENT,
+            MATERIAL_GROUP,
+            BASE_COLOR_SAMPLER_BINDING,
+        )
+        .unwrap();
+
+    let in_color = texture_sample_grad(texture, sampler, in_texture_uv, uv_dpdx, uv_dpdy);
 
-    // 还原bindgroup
-    bind_groups[0][0] = view_uniform.into();
-    bind_groups[1][1] = texture.into();
-    bind_groups[1][2] = sampler.into();
     FragmentOutput {
         frag_depth: None,
         sample_mask: 0,
@@ -69,18 +82,24 @@ fn main() {
     let material = &materials[0];
 
     let desc = RendererDescriptor {
+        layout: None,
         surface: RenderSurface {
-            format: TextureFormat::Rgba8Unorm,
+            format: TextureFormat::Rgba8UnormSrgb,
             height: 1000,
             width: 1000,
+            alpha_mode: CompositeAlphaMode::Opaque,
         },
         vertex: VertexState {
-            shader: vertex_main,
+            shader: &vertex_main,
             layout: &mesh.get_vertex_buffer_layout(),
         },
         fragment: FragmentState {
-            shader: fragment_main,
+            shader: &fragment_main,
+            derivatives: true,
         },
+        primitive: PrimitiveState::default(),
+        depth_stencil: Some(DepthStencilState::default()),
+        occlusion_query_set: None,
     };
 
     let camera = Camera::default()
@@ -98,9 +117,15 @@ fn main() {
 
     renderer.set_vertex_buffer(&vertex_buffer);
     renderer.set_index_buffer(&index_buffer);
-    renderer.set_bind_group(0, bind_group_0);
-    renderer.set_bind_group(1, bind_group_material);
-    renderer.draw_indexed(0..mesh.count_indices() as u32);
+    renderer
+        .set_bind_group(VIEW_GROUP, bind_group_0, &[])
+        .unwrap();
+    renderer
+        .set_bind_group(MATERIAL_GROUP, bind_group_material, &[])
+        .unwrap();
+    renderer
+        .draw_indexed(0..mesh.count_indices() as u32, 0, 0..1)
+        .unwrap();
     image::save_buffer(
         "image_pbr.png",
         &renderer.frame_buffer,
@@ -1,9 +1,11 @@
 use loader::load_gltf;
 use math::Vec3;
 use pipeline::{
-    FragmentState, RenderSurface, Renderer, RendererDescriptor, TextureFormat, VertexState,
+    BindType, CompositeAlphaMode, DepthStencilState, DynamicUniformBuffer, FragmentState,
+    PrimitiveState, RenderSurface, Renderer, RendererDescriptor, TextureFormat, VertexState,
 };
 use render::{
+    pbr_bindings::{pbr_pipeline_layout, MATERIAL_GROUP, MESH_GROUP, VIEW_GROUP},
     pbr_shder::{pbr_fragment_main, pbr_vertex_main},
     shader_uniform::MeshUniform,
     Camera, PointLight, Transform,
@@ -28,19 +30,26 @@ fn main() {
         inverse_transpose_model: mesh_tranform.compute_matrix().transpose(),
     };
 
+    let layout = pbr_pipeline_layout();
     let desc = RendererDescriptor {
+        layout: Some(&layout),
         surface: RenderSurface {
-            format: TextureFormat::Rgba8Unorm,
+            format: TextureFormat::Rgba8UnormSrgb,
             height: 2000,
             width: 2000,
+            alpha_mode: CompositeAlphaMode::Opaque,
         },
         vertex: VertexState {
-            shader: pbr_vertex_main,
+            shader: &pbr_vertex_main,
             layout: &mesh.get_vertex_buffer_layout(),
         },
         fragment: FragmentState {
-            shader: pbr_fragment_main,
+            shader: &pbr_fragment_main,
+            derivatives: true,
         },
+        primitive: PrimitiveState::default(),
+        depth_stencil: Some(DepthStencilState::default()),
+        occlusion_query_set: None,
     };
 
     let camera = Camera::default()
@@ -61,7 +70,10 @@ fn main() {
         light.get_point_light_uniform().into(),
     ];
     let bind_group_material = material.get_material_bind_group();
-    let bind_group_mesh = vec![mesh_uniform.into()];
+    // 网格 uniform 使用动态偏移，多个网格可以共用一个缓冲
+    let mut mesh_uniforms = DynamicUniformBuffer::default();
+    let mesh_offset = mesh_uniforms.push(&mesh_uniform);
+    let bind_group_mesh = vec![BindType::Uniform(mesh_uniforms.into_bytes())];
 
     println!("layout: {:?}", mesh.get_vertex_buffer_layout());
     println!("vertex count:{:?}", mesh.count_vertices());
@@ -69,10 +81,18 @@ fn main() {
 
     renderer.set_vertex_buffer(&vertex_buffer);
     renderer.set_index_buffer(&index_buffer);
-    renderer.set_bind_group(0, bind_group_0);
-    renderer.set_bind_group(1, bind_group_material);
-    renderer.set_bind_group(2, bind_group_mesh);
-    renderer.draw_indexed(0..mesh.count_indices() as u32);
+    renderer
+        .set_bind_group(VIEW_GROUP, bind_group_0, &[])
+        .unwrap();
+    renderer
+        .set_bind_group(MATERIAL_GROUP, bind_group_material, &[])
+        .unwrap();
+    renderer
+        .set_bind_group(MESH_GROUP, bind_group_mesh, &[mesh_offset])
+        .unwrap();
+    renderer
+        .draw_indexed(0..mesh.count_indices() as u32, 0, 0..1)
+        .unwrap();
     image::save_buffer(
         "image_pbr.png",
         &renderer.frame_buffer,
@@ -1,14 +1,15 @@
 use math::{Vec2, Vec3, Vec4};
 use pipeline::{
-    BindGroup, FragmentInput, FragmentOutput, FragmentState, RenderSurface, Renderer,
-    RendererDescriptor, ShaderType, TextureFormat, VertexFormat, VertexInput, VertexOutput,
-    VertexState,
+    BindGroups, CompositeAlphaMode, DepthStencilState, FragmentInput, FragmentOutput,
+    FragmentState, PrimitiveState, RenderSurface, Renderer, RendererDescriptor, ShaderType,
+    TextureFormat, VertexFormat, VertexInput, VertexOutput, VertexState,
 };
 
-fn vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGroup>) -> VertexOutput {
+fn vertex_main(vertex_input: VertexInput, bind_groups: &BindGroups) -> VertexOutput {
     let mut out = VertexOutput {
         location: vec![ShaderType::Vec4(Vec4::ZERO), ShaderType::Vec2(Vec2::ONE)],
         position: Vec4::ONE,
+        clip_distances: Vec::new(),
     };
     let in_postion: Vec3 = vertex_input.location[0].into();
     let in_color: Vec4 = vertex_input.location[1].into();
@@ -19,7 +20,7 @@ fn vertex_main(vertex_input: VertexInput, bind_groups: &mut Vec<BindGroup>) -> V
     out
 }
 
-fn fragment_main(input: FragmentInput, bind_groups: &mut Vec<BindGroup>) -> FragmentOutput {
+fn fragment_main(input: FragmentInput, bind_groups: &BindGroups) -> FragmentOutput {
     let in_color: Vec4 = input.location[0].into();
     FragmentOutput {
         frag_depth: None,
@@ -35,22 +36,28 @@ fn main() {
         [0.5, 0.5, 1., 0., 0., 1., 1.],
     ];
     let desc = RendererDescriptor {
+        layout: None,
         surface: RenderSurface {
             format: TextureFormat::Rgba8Unorm,
             height: 1000,
             width: 1000,
+            alpha_mode: CompositeAlphaMode::Opaque,
         },
         vertex: VertexState {
-            shader: vertex_main,
+            shader: &vertex_main,
             layout: &[VertexFormat::Float32x3, VertexFormat::Float32x4],
         },
         fragment: FragmentState {
-            shader: fragment_main,
+            shader: &fragment_main,
+            derivatives: false,
         },
+        primitive: PrimitiveState::default(),
+        depth_stencil: Some(DepthStencilState::default()),
+        occlusion_query_set: None,
     };
     let mut renderer = Renderer::new(desc);
     renderer.set_vertex_buffer(bytemuck::cast_slice(&vertex_buffer));
-    renderer.draw(0..vertex_buffer.len() as u32);
+    renderer.draw(0..vertex_buffer.len() as u32, 0..1).unwrap();
     image::save_buffer(
         "image_triangle.png",
         &renderer.frame_buffer,